@@ -0,0 +1,81 @@
+use proc_macro_error::abort;
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use syn::{Data, DataEnum, DataStruct, DataUnion, Fields, Ident, spanned::Spanned};
+
+pub fn get_variants(ident: &Ident, data: Data) -> Vec<Ident> {
+    let variants = match data {
+        Data::Enum(DataEnum { variants, .. }) => variants,
+        Data::Struct(DataStruct { struct_token, .. }) => {
+            abort!(
+                struct_token.span(), "unexpected keyword `struct` (expected `enum`)";
+                note = "`EnumSetType` can only be derived for enums, not structs or unions";
+            );
+        }
+        Data::Union(DataUnion { union_token, .. }) => {
+            abort!(
+                union_token.span(), "unexpected keyword `union` (expected `enum`)";
+                note = "`EnumSetType` can only be derived for enums, not structs or unions";
+            );
+        }
+    };
+    if variants.is_empty() {
+        abort!(
+            ident, "found 0 variants (expected at least 1)";
+            note = "there is nothing to store in a set of an empty enum";
+        );
+    }
+    variants
+        .into_iter()
+        .map(|variant| {
+            if !matches!(variant.fields, Fields::Unit) {
+                abort!(
+                    variant.fields.span(), "unexpected fields";
+                    note = "`EnumSetType` can only be derived for fieldless enums";
+                );
+            }
+            variant.ident
+        })
+        .collect()
+}
+
+pub fn generate_code(ident: Ident, variants: Vec<Ident>) -> TokenStream {
+    let variant_count = variants.len();
+    let repr = match variant_count {
+        0..=8 => format_ident!("BitSet8"),
+        9..=16 => format_ident!("BitSet16"),
+        17..=32 => format_ident!("BitSet32"),
+        33..=64 => format_ident!("BitSet64"),
+        65..=128 => format_ident!("BitSet128"),
+        _ => abort!(
+            ident, "found {} variants (expected at most 128)", variant_count;
+            note = "the largest bitset available to an `EnumSet` is `BitSet128`";
+        ),
+    };
+    let indices = 0..variant_count;
+    let to_index_arms = quote! { #(Self::#variants => #indices,)* };
+    let indices = 0..variant_count;
+    let from_index_arms = quote! {
+        #(#indices => ::core::option::Option::Some(Self::#variants),)*
+    };
+    quote! {
+        impl ::rose_bitsets::EnumSetType for #ident {
+            type Repr = ::rose_bitsets::#repr;
+
+            const VARIANT_COUNT: usize = #variant_count;
+
+            fn to_index(self) -> usize {
+                match self {
+                    #to_index_arms
+                }
+            }
+
+            fn from_index(index: usize) -> ::core::option::Option<Self> {
+                match index {
+                    #from_index_arms
+                    _ => ::core::option::Option::None,
+                }
+            }
+        }
+    }
+}
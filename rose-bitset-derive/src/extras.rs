@@ -1,68 +1,182 @@
-use crate::{impls, type_utils::signed_counterpart};
+use crate::impls;
 use proc_macro_error::{Diagnostic, DiagnosticExt, Level, SpanRange, abort};
 use proc_macro2::{Span, TokenStream};
-use syn::{
-    Attribute, Error, Ident, Token, Type, parse::Parse, punctuated::Punctuated, spanned::Spanned,
-};
+use quote::{format_ident, quote};
+use syn::{Attribute, Error, Ident, LitInt, Path, Token, Type, parse::Parse, spanned::Spanned};
 
-#[derive(Clone, Copy, Default)]
+#[derive(Clone, Default)]
 pub struct Extras {
-    debug: bool,
-    indices: bool,
-    iter: bool,
-    tests: bool,
+    pub atomic: bool,
+    pub combinations: bool,
+    pub debug: bool,
+    pub indices: bool,
+    pub iter: bool,
+    pub permutation: bool,
+    pub runs: bool,
+    pub subsets: bool,
+    pub tests: bool,
+    indices_name: Option<Ident>,
+    iter_name: Option<Ident>,
+    atomic_name: Option<Ident>,
+    subsets_name: Option<Ident>,
+    combinations_name: Option<Ident>,
+    permutation_name: Option<Ident>,
+    runs_name: Option<Ident>,
+    krate: Option<Path>,
+    capacity: Option<LitInt>,
+}
+
+/// The names of everything the extras generate alongside a bitset.
+pub struct Names {
+    /// The path to the `rose_bitsets` crate.
+    pub krate: Path,
+    pub indices: Ident,
+    pub iter: Ident,
+    pub atomic: Ident,
+    pub subsets: Ident,
+    pub combinations: Ident,
+    pub permutation: Ident,
+    pub runs: Ident,
+    pub tests: Ident,
+    /// The `#[doc(cfg(...))]` attribute for the bitsets provided by `rose_bitsets` itself.
+    pub doc_cfg: TokenStream,
 }
 
 impl Parse for Extras {
     fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
         let mut extras = Self::default();
         let mut debug_span: Option<Span> = None;
-        for ident in Punctuated::<Ident, Token![,]>::parse_terminated(input)? {
-            let ident_as_string = ident.to_string();
-            match ident_as_string.as_str() {
-                "debug" => {
-                    if !extras.debug {
-                        extras.debug = true;
-                        debug_span = Some(ident.span());
-                    } else {
-                        return Err(syn::Error::new_spanned(
-                            ident,
-                            "duplicate of `debug` specifier",
-                        ));
-                    }
+        while !input.is_empty() {
+            if input.peek(Token![crate]) {
+                let crate_token: Token![crate] = input.parse()?;
+                input.parse::<Token![=]>()?;
+                if extras.krate.is_some() {
+                    return Err(syn::Error::new_spanned(
+                        crate_token,
+                        "duplicate of `crate` specifier",
+                    ));
                 }
-                "indices" => {
-                    if !extras.indices {
-                        extras.indices = true;
-                    } else {
-                        return Err(syn::Error::new_spanned(
-                            ident,
-                            "duplicate of `indices` specifier",
-                        ));
+                extras.krate = Some(input.parse()?);
+            } else {
+                let ident: Ident = input.parse()?;
+                let ident_as_string = ident.to_string();
+                match ident_as_string.as_str() {
+                    "atomic" => {
+                        if !extras.atomic {
+                            extras.atomic = true;
+                            extras.atomic_name = parse_optional_name(input)?;
+                        } else {
+                            return Err(syn::Error::new_spanned(
+                                ident,
+                                "duplicate of `atomic` specifier",
+                            ));
+                        }
                     }
-                }
-                "iter" => {
-                    if !extras.iter {
-                        extras.iter = true;
-                    } else {
-                        return Err(syn::Error::new_spanned(
-                            ident,
-                            "duplicate of `iter` specifier",
-                        ));
+                    "debug" => {
+                        if !extras.debug {
+                            extras.debug = true;
+                            debug_span = Some(ident.span());
+                        } else {
+                            return Err(syn::Error::new_spanned(
+                                ident,
+                                "duplicate of `debug` specifier",
+                            ));
+                        }
                     }
-                }
-                "tests" => {
-                    if !extras.tests {
-                        extras.tests = true;
-                    } else {
-                        return Err(syn::Error::new_spanned(
-                            ident,
-                            "duplicate of `tests` specifier",
-                        ));
+                    "indices" => {
+                        if !extras.indices {
+                            extras.indices = true;
+                            extras.indices_name = parse_optional_name(input)?;
+                        } else {
+                            return Err(syn::Error::new_spanned(
+                                ident,
+                                "duplicate of `indices` specifier",
+                            ));
+                        }
+                    }
+                    "iter" => {
+                        if !extras.iter {
+                            extras.iter = true;
+                            extras.iter_name = parse_optional_name(input)?;
+                        } else {
+                            return Err(syn::Error::new_spanned(
+                                ident,
+                                "duplicate of `iter` specifier",
+                            ));
+                        }
+                    }
+                    "subsets" => {
+                        if !extras.subsets {
+                            extras.subsets = true;
+                            extras.subsets_name = parse_optional_name(input)?;
+                        } else {
+                            return Err(syn::Error::new_spanned(
+                                ident,
+                                "duplicate of `subsets` specifier",
+                            ));
+                        }
+                    }
+                    "combinations" => {
+                        if !extras.combinations {
+                            extras.combinations = true;
+                            extras.combinations_name = parse_optional_name(input)?;
+                        } else {
+                            return Err(syn::Error::new_spanned(
+                                ident,
+                                "duplicate of `combinations` specifier",
+                            ));
+                        }
+                    }
+                    "permutation" => {
+                        if !extras.permutation {
+                            extras.permutation = true;
+                            extras.permutation_name = parse_optional_name(input)?;
+                        } else {
+                            return Err(syn::Error::new_spanned(
+                                ident,
+                                "duplicate of `permutation` specifier",
+                            ));
+                        }
+                    }
+                    "runs" => {
+                        if !extras.runs {
+                            extras.runs = true;
+                            extras.runs_name = parse_optional_name(input)?;
+                        } else {
+                            return Err(syn::Error::new_spanned(
+                                ident,
+                                "duplicate of `runs` specifier",
+                            ));
+                        }
                     }
+                    "capacity" => {
+                        if extras.capacity.is_none() {
+                            input.parse::<Token![=]>()?;
+                            extras.capacity = Some(input.parse()?);
+                        } else {
+                            return Err(syn::Error::new_spanned(
+                                ident,
+                                "duplicate of `capacity` specifier",
+                            ));
+                        }
+                    }
+                    "tests" => {
+                        if !extras.tests {
+                            extras.tests = true;
+                        } else {
+                            return Err(syn::Error::new_spanned(
+                                ident,
+                                "duplicate of `tests` specifier",
+                            ));
+                        }
+                    }
+                    _ => return Err(syn::Error::new_spanned(ident, "unknown specifier")),
                 }
-                _ => return Err(syn::Error::new_spanned(ident, "unknown specifier")),
             }
+            if input.is_empty() {
+                break;
+            }
+            input.parse::<Token![,]>()?;
         }
         if extras.debug && !extras.indices {
             return Err(syn::Error::new(
@@ -74,64 +188,162 @@ impl Parse for Extras {
     }
 }
 
+/// Parses the `= Name` that may follow a specifier.
+fn parse_optional_name(input: syn::parse::ParseStream) -> syn::Result<Option<Ident>> {
+    if input.peek(Token![=]) {
+        input.parse::<Token![=]>()?;
+        Ok(Some(input.parse()?))
+    } else {
+        Ok(None)
+    }
+}
+
 impl Extras {
-    pub fn generate_code_from_attribute(
-        bitset: &Attribute,
-        ident: &Ident,
-        uint: &Type,
-    ) -> TokenStream {
+    pub fn from_attribute(bitset: &Attribute) -> Self {
         match bitset.meta.require_list() {
-            Ok(meta_list) => meta_list
-                .parse_args::<Extras>()
-                .unwrap_or_else(|error| {
-                    diagnostic_from_error(error).abort();
-                })
-                .generate_code(ident, uint),
+            Ok(meta_list) => meta_list.parse_args::<Extras>().unwrap_or_else(|error| {
+                diagnostic_from_error(error).abort();
+            }),
             Err(_) => abort!(bitset.meta.span(), "expected list"),
         }
     }
 
+    /// The capacity given by `capacity = N`, if any.
+    pub fn capacity(&self) -> Option<&LitInt> {
+        self.capacity.as_ref()
+    }
+
     pub fn generate_code(&self, ident: &Ident, uint: &Type) -> TokenStream {
         if self.nothing_to_implement() {
             return TokenStream::new();
         }
 
-        let ident_as_string = ident.to_string();
-        let suffix = ident_as_string.strip_prefix("BitSet").unwrap_or_else(|| {
-            abort!(
-                ident, "bad name";
-                help = "bitsets should be named `BitSet{N}`, where N is the size in bits";
-            );
-        });
-
+        let names = self.names(ident);
         let mut code = TokenStream::new();
         if self.debug {
-            code.extend(impls::debug::generate_code(ident));
+            code.extend(impls::debug::generate_code(ident, &names));
         }
         if self.indices {
-            code.extend(impls::indices::generate_code(ident, uint, suffix));
+            code.extend(impls::indices::generate_code(ident, uint, &names));
         }
         if self.iter {
-            let int = signed_counterpart(uint)
-                .unwrap_or_else(|| abort!(uint, "not a primitive unsigned integer"));
-            code.extend(impls::iter::generate_code(ident, uint, &int, suffix));
+            code.extend(impls::iter::generate_code(ident, uint, &names));
+        }
+        if self.indices && self.iter {
+            code.extend(impls::bitset::generate_code(ident, uint, &names));
+        }
+        if self.atomic {
+            code.extend(impls::atomic::generate_code(ident, uint, &names, self.debug));
+        }
+        if self.subsets {
+            code.extend(impls::subsets::generate_code(ident, uint, &names));
+        }
+        if self.combinations {
+            code.extend(impls::combinations::generate_code(ident, uint, &names));
+        }
+        if self.permutation {
+            code.extend(impls::permutation::generate_code(ident, uint, &names));
+        }
+        if self.runs {
+            code.extend(impls::runs::generate_code(ident, uint, &names));
         }
         if self.tests {
-            code.extend(impls::tests::generate_code(
-                ident,
-                suffix,
-                self.debug,
-                self.indices,
-                self.iter,
-            ));
+            code.extend(impls::tests::generate_code(ident, &names, self));
         }
 
         code
     }
 
+    /// Names the generated items.
+    ///
+    /// Bitsets named `BitSet{N}` get names matching the ones in `rose_bitsets`, and anything else
+    /// gets names built from `{Name}`. Each can be overridden with the option after it:
+    ///
+    /// - `BitSetIndices{N}` or `{Name}Indices`, with `indices = ...`
+    /// - `BitSetIter{N}` or `{Name}Iter`, with `iter = ...`
+    /// - `BitSetSubsets{N}` or `{Name}Subsets`, with `subsets = ...`
+    /// - `BitSetCombinations{N}` or `{Name}Combinations`, with `combinations = ...`
+    /// - `BitSetPermutation{N}` or `{Name}Permutation`, with `permutation = ...`
+    /// - `BitSetRuns{N}` or `{Name}Runs`, with `runs = ...`
+    /// - `Atomic{Name}`, with `atomic = ...`
+    fn names(&self, ident: &Ident) -> Names {
+        let ident_as_string = ident.to_string();
+        let suffix = ident_as_string
+            .strip_prefix("BitSet")
+            .filter(|suffix| !suffix.is_empty());
+        let (indices, iter, subsets, combinations, permutation, runs, tests) = match suffix {
+            Some(suffix) => (
+                format_ident!("BitSetIndices{suffix}"),
+                format_ident!("BitSetIter{suffix}"),
+                format_ident!("BitSetSubsets{suffix}"),
+                format_ident!("BitSetCombinations{suffix}"),
+                format_ident!("BitSetPermutation{suffix}"),
+                format_ident!("BitSetRuns{suffix}"),
+                format_ident!("bitset_{}_tests", suffix.to_lowercase()),
+            ),
+            None => (
+                format_ident!("{ident}Indices"),
+                format_ident!("{ident}Iter"),
+                format_ident!("{ident}Subsets"),
+                format_ident!("{ident}Combinations"),
+                format_ident!("{ident}Permutation"),
+                format_ident!("{ident}Runs"),
+                format_ident!("{}_tests", to_snake_case(&ident_as_string)),
+            ),
+        };
+        let doc_cfg = match suffix {
+            Some(suffix @ ("8" | "16" | "32" | "64" | "128" | "Size")) if self.krate.is_none() => {
+                let feature_flag = format!("b{}", suffix.to_lowercase());
+                quote! { #[cfg_attr(docsrs, doc(cfg(feature = #feature_flag)))] }
+            }
+            _ => TokenStream::new(),
+        };
+        Names {
+            krate: self
+                .krate
+                .clone()
+                .unwrap_or_else(|| syn::parse_quote!(::rose_bitsets)),
+            indices: self.indices_name.clone().unwrap_or(indices),
+            iter: self.iter_name.clone().unwrap_or(iter),
+            atomic: self
+                .atomic_name
+                .clone()
+                .unwrap_or_else(|| format_ident!("Atomic{ident}")),
+            subsets: self.subsets_name.clone().unwrap_or(subsets),
+            combinations: self.combinations_name.clone().unwrap_or(combinations),
+            permutation: self.permutation_name.clone().unwrap_or(permutation),
+            runs: self.runs_name.clone().unwrap_or(runs),
+            tests,
+            doc_cfg,
+        }
+    }
+
     const fn nothing_to_implement(&self) -> bool {
-        !(self.debug || self.indices || self.iter || self.tests)
+        !(self.atomic
+            || self.combinations
+            || self.debug
+            || self.indices
+            || self.iter
+            || self.permutation
+            || self.runs
+            || self.subsets
+            || self.tests)
+    }
+}
+
+fn to_snake_case(camel_case: &str) -> String {
+    let mut snake_case = String::with_capacity(camel_case.len() + 4);
+    for (i, c) in camel_case.char_indices() {
+        if c.is_uppercase() {
+            if i != 0 {
+                snake_case.push('_');
+            }
+            snake_case.extend(c.to_lowercase());
+        } else {
+            snake_case.push(c);
+        }
     }
+    snake_case
 }
 
 fn diagnostic_from_error(error: Error) -> Diagnostic {
@@ -0,0 +1,192 @@
+use crate::{extras::Names, type_utils::repr};
+use proc_macro_error::abort;
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use syn::{Ident, Type};
+
+pub fn generate_code(ident: &Ident, uint: &Type, names: &Names, debug: bool) -> TokenStream {
+    let Names {
+        atomic: atomic_ident,
+        doc_cfg,
+        ..
+    } = names;
+    let (atomic, target_width) = match repr(uint).as_str() {
+        "u8" => (format_ident!("AtomicU8"), "8"),
+        "u16" => (format_ident!("AtomicU16"), "16"),
+        "u32" => (format_ident!("AtomicU32"), "32"),
+        "u64" => (format_ident!("AtomicU64"), "64"),
+        "usize" => (format_ident!("AtomicUsize"), "ptr"),
+        _ => abort!(
+            uint, "no atomic counterpart for `{}`", repr(uint);
+            note = "atomic bitsets can only wrap `u8`, `u16`, `u32`, `u64`, or `usize`";
+        ),
+    };
+    let bitset_link = format!("[`{ident}`] that can be shared between threads.");
+    let atomic_link = format!(
+        "It wraps an [`{atomic}`](core::sync::atomic::{atomic}), so every operation takes an"
+    );
+    let insert_link =
+        format!("Like [`{ident}::insert`], this does nothing if `index` is out of range.");
+    let debug_impl = if debug {
+        quote! {
+            ::core::fmt::Debug::fmt(&self.load(::core::sync::atomic::Ordering::Relaxed), f)
+        }
+    } else {
+        quote! { ::core::fmt::Debug::fmt(&self.0, f) }
+    };
+    quote! {
+        #[doc = "A"]
+        #[doc = #bitset_link]
+        #[doc = ""]
+        #[doc = #atomic_link]
+        #[doc = "[`Ordering`](core::sync::atomic::Ordering) with the same meaning it has there."]
+        #[cfg(target_has_atomic = #target_width)]
+        #doc_cfg
+        #[derive(Default)]
+        #[repr(transparent)]
+        pub struct #atomic_ident(::core::sync::atomic::#atomic);
+
+        #[cfg(target_has_atomic = #target_width)]
+        impl #atomic_ident {
+            /// Creates an atomic set holding `set`.
+            #[must_use]
+            pub const fn new(set: #ident) -> Self {
+                Self(::core::sync::atomic::#atomic::new(set.bits()))
+            }
+
+            /// Consumes the atomic set, returning the set it holds.
+            #[must_use]
+            pub fn into_inner(self) -> #ident {
+                #ident::from_bits(self.0.into_inner())
+            }
+
+            /// Loads the set.
+            #[must_use]
+            pub fn load(&self, order: ::core::sync::atomic::Ordering) -> #ident {
+                #ident::from_bits(self.0.load(order))
+            }
+
+            /// Stores `set`, replacing whatever was there before.
+            pub fn store(&self, set: #ident, order: ::core::sync::atomic::Ordering) {
+                self.0.store(set.bits(), order)
+            }
+
+            /// Stores `set`, returning the previous set.
+            pub fn swap(&self, set: #ident, order: ::core::sync::atomic::Ordering) -> #ident {
+                #ident::from_bits(self.0.swap(set.bits(), order))
+            }
+
+            /// Stores `new` if the set is still `current`. Returns the previous set, wrapped in
+            /// [`Ok`] if the exchange happened and [`Err`] if it didn't.
+            pub fn compare_exchange(
+                &self,
+                current: #ident,
+                new: #ident,
+                success: ::core::sync::atomic::Ordering,
+                failure: ::core::sync::atomic::Ordering,
+            ) -> ::core::result::Result<#ident, #ident> {
+                self.0
+                    .compare_exchange(current.bits(), new.bits(), success, failure)
+                    .map(#ident::from_bits)
+                    .map_err(#ident::from_bits)
+            }
+
+            /// Like [`compare_exchange`](Self::compare_exchange), but may fail even when the set
+            /// is `current`, which can be faster inside a loop.
+            pub fn compare_exchange_weak(
+                &self,
+                current: #ident,
+                new: #ident,
+                success: ::core::sync::atomic::Ordering,
+                failure: ::core::sync::atomic::Ordering,
+            ) -> ::core::result::Result<#ident, #ident> {
+                self.0
+                    .compare_exchange_weak(current.bits(), new.bits(), success, failure)
+                    .map(#ident::from_bits)
+                    .map_err(#ident::from_bits)
+            }
+
+            /// Returns `true` if the bit at `index` is set.
+            #[must_use]
+            pub fn contains(&self, index: usize, order: ::core::sync::atomic::Ordering) -> bool {
+                self.load(order).contains(index)
+            }
+
+            /// Sets the bit at `index` to `1`. Returns the previous set.
+            ///
+            #[doc = #insert_link]
+            pub fn insert(&self, index: usize, order: ::core::sync::atomic::Ordering) -> #ident {
+                self.union_with(#ident::unit(index), order)
+            }
+
+            /// Sets the bit at `index` to `0`. Returns the previous set.
+            pub fn remove(&self, index: usize, order: ::core::sync::atomic::Ordering) -> #ident {
+                self.difference_with(#ident::unit(index), order)
+            }
+
+            /// Sets the bit at `index` to `bit`. Returns the previous set.
+            pub fn replace(
+                &self,
+                index: usize,
+                bit: bool,
+                order: ::core::sync::atomic::Ordering,
+            ) -> #ident {
+                if bit {
+                    self.insert(index, order)
+                } else {
+                    self.remove(index, order)
+                }
+            }
+
+            /// Adds the values in `set`. Returns the previous set.
+            pub fn union_with(
+                &self,
+                set: #ident,
+                order: ::core::sync::atomic::Ordering,
+            ) -> #ident {
+                #ident::from_bits(self.0.fetch_or(set.bits(), order))
+            }
+
+            /// Keeps only the values that are also in `set`. Returns the previous set.
+            pub fn intersect_with(
+                &self,
+                set: #ident,
+                order: ::core::sync::atomic::Ordering,
+            ) -> #ident {
+                #ident::from_bits(self.0.fetch_and(set.bits(), order))
+            }
+
+            /// Removes the values in `set`. Returns the previous set.
+            pub fn difference_with(
+                &self,
+                set: #ident,
+                order: ::core::sync::atomic::Ordering,
+            ) -> #ident {
+                #ident::from_bits(self.0.fetch_and(!set.bits(), order))
+            }
+
+            /// Toggles the values in `set`. Returns the previous set.
+            pub fn symmetric_difference_with(
+                &self,
+                set: #ident,
+                order: ::core::sync::atomic::Ordering,
+            ) -> #ident {
+                #ident::from_bits(self.0.fetch_xor(set.bits(), order))
+            }
+        }
+
+        #[cfg(target_has_atomic = #target_width)]
+        impl ::core::convert::From<#ident> for #atomic_ident {
+            fn from(set: #ident) -> Self {
+                Self::new(set)
+            }
+        }
+
+        #[cfg(target_has_atomic = #target_width)]
+        impl ::core::fmt::Debug for #atomic_ident {
+            fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+                #debug_impl
+            }
+        }
+    }
+}
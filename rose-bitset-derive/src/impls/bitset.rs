@@ -0,0 +1,194 @@
+use crate::extras::Names;
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{Ident, Type};
+
+pub fn generate_code(ident: &Ident, uint: &Type, names: &Names) -> TokenStream {
+    let Names {
+        krate,
+        indices,
+        iter,
+        ..
+    } = names;
+    quote! {
+        impl #krate::BitSet for #ident {
+            type Bits = #uint;
+            type Indices<'a> = #indices<'a, #krate::Ascending>;
+            type Iter<'a> = #iter<'a, #krate::Ascending>;
+
+            const CAPACITY: usize = #ident::CAPACITY;
+
+            fn new() -> Self {
+                #ident::new()
+            }
+
+            fn unit(index: usize) -> Self {
+                #ident::unit(index)
+            }
+
+            fn all() -> Self {
+                #ident::all()
+            }
+
+            fn from_bits(bits: #uint) -> Self {
+                #ident::from_bits(bits)
+            }
+
+            fn bits(self) -> #uint {
+                #ident::bits(self)
+            }
+
+            fn complement(self) -> Self {
+                #ident::complement(self)
+            }
+
+            fn intersection(self, rhs: Self) -> Self {
+                #ident::intersection(self, rhs)
+            }
+
+            fn union(self, rhs: Self) -> Self {
+                #ident::union(self, rhs)
+            }
+
+            fn difference(self, rhs: Self) -> Self {
+                #ident::difference(self, rhs)
+            }
+
+            fn symmetric_difference(self, rhs: Self) -> Self {
+                #ident::symmetric_difference(self, rhs)
+            }
+
+            fn is_disjoint(self, rhs: Self) -> bool {
+                #ident::is_disjoint(self, rhs)
+            }
+
+            fn is_subset(self, rhs: Self) -> bool {
+                #ident::is_subset(self, rhs)
+            }
+
+            fn is_strict_subset(self, rhs: Self) -> bool {
+                #ident::is_strict_subset(self, rhs)
+            }
+
+            fn is_superset(self, rhs: Self) -> bool {
+                #ident::is_superset(self, rhs)
+            }
+
+            fn is_strict_superset(self, rhs: Self) -> bool {
+                #ident::is_strict_superset(self, rhs)
+            }
+
+            fn is_empty(self) -> bool {
+                #ident::is_empty(self)
+            }
+
+            fn is_full(self) -> bool {
+                #ident::is_full(self)
+            }
+
+            fn len(self) -> usize {
+                #ident::len(self)
+            }
+
+            fn contains(self, index: usize) -> bool {
+                #ident::contains(self, index)
+            }
+
+            fn min_index(self) -> usize {
+                #ident::min_index(self)
+            }
+
+            fn max_index(self) -> usize {
+                #ident::max_index(self)
+            }
+
+            fn max_index_checked(self) -> ::core::option::Option<usize> {
+                #ident::max_index_checked(self)
+            }
+
+            fn shifted_up_by(self, shift: u32) -> Self {
+                #ident::shifted_up_by(self, shift)
+            }
+
+            fn shift_up_by(&mut self, shift: u32) {
+                #ident::shift_up_by(self, shift)
+            }
+
+            fn shifted_up_by_signed(self, shift: i32) -> Self {
+                #ident::shifted_up_by_signed(self, shift)
+            }
+
+            fn shift_up_by_signed(&mut self, shift: i32) {
+                #ident::shift_up_by_signed(self, shift)
+            }
+
+            fn shifted_down_by(self, shift: u32) -> Self {
+                #ident::shifted_down_by(self, shift)
+            }
+
+            fn shift_down_by(&mut self, shift: u32) {
+                #ident::shift_down_by(self, shift)
+            }
+
+            fn shifted_down_by_signed(self, shift: i32) -> Self {
+                #ident::shifted_down_by_signed(self, shift)
+            }
+
+            fn shift_down_by_signed(&mut self, shift: i32) {
+                #ident::shift_down_by_signed(self, shift)
+            }
+
+            fn clear(&mut self) {
+                #ident::clear(self)
+            }
+
+            fn masked_0_to_i(self, index: usize) -> Self {
+                #ident::masked_0_to_i(self, index)
+            }
+
+            fn mask_0_to_i(&mut self, index: usize) {
+                #ident::mask_0_to_i(self, index)
+            }
+
+            fn cleared_0_to_i(self, index: usize) -> Self {
+                #ident::cleared_0_to_i(self, index)
+            }
+
+            fn clear_0_to_i(&mut self, index: usize) {
+                #ident::clear_0_to_i(self, index)
+            }
+
+            fn insert_quiet(&mut self, index: usize) {
+                #ident::insert_quiet(self, index)
+            }
+
+            fn insert(&mut self, index: usize) -> bool {
+                #ident::insert(self, index)
+            }
+
+            fn replace_quiet(&mut self, index: usize, bit: bool) {
+                #ident::replace_quiet(self, index, bit)
+            }
+
+            fn replace(&mut self, index: usize, bit: bool) -> bool {
+                #ident::replace(self, index, bit)
+            }
+
+            fn remove_quiet(&mut self, index: usize) {
+                #ident::remove_quiet(self, index)
+            }
+
+            fn remove(&mut self, index: usize) -> bool {
+                #ident::remove(self, index)
+            }
+
+            fn iter_indices(&self) -> Self::Indices<'_> {
+                #indices::new(self)
+            }
+
+            fn iter_bits(&self) -> Self::Iter<'_> {
+                #iter::new(self)
+            }
+        }
+    }
+}
@@ -0,0 +1,256 @@
+use crate::{extras::Names, type_utils::exact_size_cfg};
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{Ident, Type};
+
+pub fn generate_code(ident: &Ident, uint: &Type, names: &Names) -> TokenStream {
+    let Names {
+        combinations: iterator,
+        doc_cfg,
+        ..
+    } = names;
+    let bitset_link = format!("[`{ident}`].");
+    let exact_size_impl = exact_size_cfg(uint).map(|cfg| {
+        quote! {
+            #cfg
+            impl ::core::iter::ExactSizeIterator for #iterator {}
+        }
+    });
+    quote! {
+        #[doc = "An iterator over the subsets with a fixed number of elements of a"]
+        #[doc = #bitset_link]
+        ///
+        /// The subsets are yielded in colexicographic order, which compares two subsets by the
+        /// largest element that only one of them has.
+        #doc_cfg
+        #[derive(Clone, Debug)]
+        pub struct #iterator {
+            mask: #uint,
+            /// The `len` lowest bits, where `len` is the number of elements in the set.
+            ///
+            /// The front and back are kept as combinations of these bits, and spread out over
+            /// `mask` when they are yielded.
+            dense_mask: #uint,
+            front: #uint,
+            back: #uint,
+            /// One less than the number of combinations left, or `None` once there are none.
+            left: ::core::option::Option<u128>,
+        }
+
+        impl #iterator {
+            /// Creates an iterator over the `k`-element subsets of `set`.
+            pub const fn new(set: #ident, k: usize) -> Self {
+                let len = set.len();
+                let dense_mask = Self::low_bits(len);
+                let front = Self::low_bits(k);
+                Self {
+                    mask: set.bits(),
+                    dense_mask,
+                    front,
+                    back: match front.checked_shl(len.saturating_sub(k) as u32) {
+                        ::core::option::Option::Some(back) => back,
+                        ::core::option::Option::None => 0,
+                    },
+                    left: (Self::binomial(len, k) as u128).checked_sub(1),
+                }
+            }
+
+            /// Returns the `count` lowest bits.
+            const fn low_bits(count: usize) -> #uint {
+                match <#uint>::MAX.checked_shr(<#uint>::BITS.saturating_sub(count as u32)) {
+                    ::core::option::Option::Some(bits) if count != 0 => bits,
+                    _ => 0,
+                }
+            }
+
+            /// The number of rows in [`Self::BINOMIALS`], one for each `n` in `0..=BITS`.
+            const BINOMIAL_ROWS: usize = <#uint>::BITS as usize + 1;
+
+            /// Every binomial coefficient `C(n, k)` for `n <= BITS`, stored row by row as Pascal's
+            /// triangle. No entry can overflow, since `C(n, k) < 2^n`.
+            const BINOMIALS: [#uint; Self::BINOMIAL_ROWS * (Self::BINOMIAL_ROWS + 1) / 2] = {
+                let mut table = [0; Self::BINOMIAL_ROWS * (Self::BINOMIAL_ROWS + 1) / 2];
+                let mut n = 0;
+                while n < Self::BINOMIAL_ROWS {
+                    let row = n * (n + 1) / 2;
+                    table[row] = 1;
+                    table[row + n] = 1;
+                    let mut k = 1;
+                    while k < n {
+                        let previous_row = (n - 1) * n / 2;
+                        table[row + k] = table[previous_row + k - 1] + table[previous_row + k];
+                        k += 1;
+                    }
+                    n += 1;
+                }
+                table
+            };
+
+            /// Returns the number of ways to choose `k` elements out of `n`.
+            const fn binomial(n: usize, k: usize) -> #uint {
+                if k > n {
+                    0
+                } else {
+                    Self::BINOMIALS[n * (n + 1) / 2 + k]
+                }
+            }
+
+            /// Returns the combination of the same size that comes right after `bits` in
+            /// colexicographic order, using Gosper's hack.
+            const fn next_combination(bits: #uint) -> #uint {
+                let lowest = bits & bits.wrapping_neg();
+                let ripple = bits.wrapping_add(lowest);
+                (((ripple ^ bits) >> 2) / lowest) | ripple
+            }
+
+            /// Counts off the current combination, returning whether there are any left after it.
+            const fn take(&mut self) -> ::core::option::Option<bool> {
+                match self.left {
+                    ::core::option::Option::Some(left) => {
+                        self.left = left.checked_sub(1);
+                        ::core::option::Option::Some(left != 0)
+                    }
+                    ::core::option::Option::None => ::core::option::Option::None,
+                }
+            }
+        }
+
+        impl ::core::iter::Iterator for #iterator {
+            type Item = #ident;
+
+            fn next(&mut self) -> ::core::option::Option<Self::Item> {
+                let combination = self.front;
+                if self.take()? {
+                    self.front = Self::next_combination(self.front);
+                }
+                let combination = #ident::from_bits(combination);
+                ::core::option::Option::Some(combination.expand(#ident::from_bits(self.mask)))
+            }
+
+            fn size_hint(&self) -> (usize, ::core::option::Option<usize>) {
+                match self.left {
+                    ::core::option::Option::Some(left) => {
+                        match usize::try_from(left).ok().and_then(|left| left.checked_add(1)) {
+                            ::core::option::Option::Some(len) => {
+                                (len, ::core::option::Option::Some(len))
+                            }
+                            ::core::option::Option::None => {
+                                (usize::MAX, ::core::option::Option::None)
+                            }
+                        }
+                    }
+                    ::core::option::Option::None => (0, ::core::option::Option::Some(0)),
+                }
+            }
+        }
+
+        impl ::core::iter::DoubleEndedIterator for #iterator {
+            fn next_back(&mut self) -> ::core::option::Option<Self::Item> {
+                let combination = self.back;
+                if self.take()? {
+                    // Taking complements reverses colexicographic order, so the combination before
+                    // this one is the complement of the one after its complement.
+                    let complement = !self.back & self.dense_mask;
+                    self.back = !Self::next_combination(complement) & self.dense_mask;
+                }
+                let combination = #ident::from_bits(combination);
+                ::core::option::Option::Some(combination.expand(#ident::from_bits(self.mask)))
+            }
+        }
+
+        #exact_size_impl
+
+        impl ::core::iter::FusedIterator for #iterator {}
+
+        impl #ident {
+            /// Creates an iterator over every subset of the set with exactly `k` elements.
+            #[must_use]
+            #doc_cfg
+            pub const fn combinations(self, k: usize) -> #iterator {
+                #iterator::new(self, k)
+            }
+
+            /// Returns the position of the set among all sets of the same size, in
+            /// colexicographic order.
+            ///
+            /// This is the set's value in the combinatorial number system, so the sets with `k`
+            /// elements are numbered densely from `0` up to (but not including) the number of ways
+            /// to choose `k` out of [`Self::CAPACITY`].
+            #[must_use]
+            #doc_cfg
+            pub const fn rank_combination(self) -> #uint {
+                let mut bits = self.bits();
+                let mut rank = 0;
+                let mut i = 1;
+                while bits != 0 {
+                    rank += #iterator::binomial(bits.trailing_zeros() as usize, i);
+                    bits &= bits - 1;
+                    i += 1;
+                }
+                rank
+            }
+
+            /// Returns the set with `k` elements at position `rank` in colexicographic order, or
+            /// [`None`] if there aren't that many sets with `k` elements.
+            ///
+            /// This is the inverse of [`rank_combination`](Self::rank_combination).
+            #[must_use]
+            #doc_cfg
+            pub const fn unrank_combination(
+                k: usize,
+                mut rank: #uint,
+            ) -> ::core::option::Option<Self> {
+                if k > Self::CAPACITY || rank >= #iterator::binomial(Self::CAPACITY, k) {
+                    return ::core::option::Option::None;
+                }
+                // Greedily take the largest element that leaves enough rank for the rest.
+                let mut set = Self::new();
+                let mut element = Self::CAPACITY;
+                let mut i = k;
+                while i > 0 {
+                    element -= 1;
+                    while #iterator::binomial(element, i) > rank {
+                        element -= 1;
+                    }
+                    set.insert_quiet(element);
+                    rank -= #iterator::binomial(element, i);
+                    i -= 1;
+                }
+                ::core::option::Option::Some(set)
+            }
+
+            /// Returns the set of the same size that comes right after this one in
+            /// colexicographic order, or [`None`] if this is the last one.
+            #[must_use]
+            #doc_cfg
+            pub const fn next_combination(self) -> ::core::option::Option<Self> {
+                let bits = self.bits();
+                let lowest = bits & bits.wrapping_neg();
+                let ripple = match bits.checked_add(lowest) {
+                    ::core::option::Option::Some(ripple) if bits != 0 => ripple,
+                    _ => return ::core::option::Option::None,
+                };
+                let next = (((ripple ^ bits) >> 2) / lowest) | ripple;
+                if next & !Self::all().bits() != 0 {
+                    ::core::option::Option::None
+                } else {
+                    ::core::option::Option::Some(Self::from_bits(next))
+                }
+            }
+
+            /// Returns the set of the same size that comes right before this one in
+            /// colexicographic order, or [`None`] if this is the first one.
+            #[must_use]
+            #doc_cfg
+            pub const fn prev_combination(self) -> ::core::option::Option<Self> {
+                // Taking complements reverses colexicographic order.
+                match self.complement().next_combination() {
+                    ::core::option::Option::Some(next) => {
+                        ::core::option::Option::Some(next.complement())
+                    }
+                    ::core::option::Option::None => ::core::option::Option::None,
+                }
+            }
+        }
+    }
+}
@@ -1,13 +1,15 @@
+use crate::extras::Names;
 use proc_macro2::TokenStream;
 use quote::quote;
 use syn::Ident;
 
-pub fn generate_code(ident: &Ident) -> TokenStream {
+pub fn generate_code(ident: &Ident, names: &Names) -> TokenStream {
+    let krate = &names.krate;
     quote! {
         impl ::core::fmt::Debug for #ident {
             fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
                 f.debug_set()
-                    .entries(self.iter_indices::<crate::Ascending>())
+                    .entries(self.iter_indices::<#krate::Ascending>())
                     .finish()
             }
         }
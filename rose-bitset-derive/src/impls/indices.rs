@@ -1,75 +1,204 @@
+use crate::extras::Names;
 use proc_macro2::TokenStream;
-use quote::{format_ident, quote};
+use quote::quote;
 use syn::{Ident, Type};
 
-pub fn generate_code(ident: &Ident, uint: &Type, suffix: &str) -> TokenStream {
-    let iterator = format_ident!("BitSetIndices{suffix}");
+pub fn generate_code(ident: &Ident, uint: &Type, names: &Names) -> TokenStream {
+    let Names {
+        krate,
+        indices: iterator,
+        doc_cfg,
+        ..
+    } = names;
     let bitset_link = format!("[`{ident}`].");
-    let feature_flag = format!("b{suffix}");
     quote! {
         #[doc = "An iterator over the indices of the bits that are set in a"]
         #[doc = #bitset_link]
-        #[cfg_attr(docsrs, doc(cfg(feature = #feature_flag)))]
-        pub struct #iterator<'a, Direction = crate::Ascending> {
+        ///
+        /// The indices are visited in the order given by `D`, which can be any `Direction`.
+        #doc_cfg
+        pub struct #iterator<'a, D = #krate::Ascending> {
             bits: #uint,
-            shift: #uint,
-            _markers: (::core::marker::PhantomData<&'a ()>, ::core::marker::PhantomData<Direction>),
+            _markers: (::core::marker::PhantomData<&'a ()>, ::core::marker::PhantomData<D>),
         }
 
-        impl ::core::iter::Iterator for #iterator<'_, crate::Ascending> {
-            type Item = usize;
+        impl<'a, D> #iterator<'a, D> {
+            /// Creates an iterator over the indices of the bits that are set in `set`.
+            pub const fn new(set: &'a #ident) -> Self {
+                Self {
+                    bits: set.bits(),
+                    _markers: (::core::marker::PhantomData, ::core::marker::PhantomData),
+                }
+            }
 
-            fn next(&mut self) -> ::core::option::Option<Self::Item> {
-                let mut i = self.bits.trailing_zeros() as usize;
-                (i < #ident::CAPACITY).then(|| {
-                    let relative_shift = unsafe { i.unchecked_add(1) } as #uint;
-                    self.bits >>= relative_shift;
-                    i += self.shift as usize;
-                    self.shift += relative_shift;
-                    i
-                })
+            /// Creates an iterator over the indices of the bits that are set in `set`, without
+            /// borrowing it.
+            pub const fn from_set(set: #ident) -> Self {
+                Self {
+                    bits: set.bits(),
+                    _markers: (::core::marker::PhantomData, ::core::marker::PhantomData),
+                }
+            }
+
+            /// Returns the number of indices left.
+            const fn remaining(&self) -> usize {
+                self.bits.count_ones() as usize
+            }
+
+            /// Takes an index out of the ones left with `pop`, which sees them as a [`u128`].
+            fn pop_with(
+                &mut self,
+                pop: fn(&mut u128) -> ::core::option::Option<usize>,
+            ) -> ::core::option::Option<usize> {
+                let mut remaining = self.bits as u128;
+                let index = pop(&mut remaining);
+                self.bits = remaining as #uint;
+                index
+            }
+
+            /// Drops `n` of the indices left with `skip`, which sees them as a [`u128`].
+            fn skip_with(&mut self, skip: fn(&mut u128, usize), n: usize) {
+                let mut remaining = self.bits as u128;
+                skip(&mut remaining, n);
+                self.bits = remaining as #uint;
             }
         }
 
-        impl ::core::iter::Iterator for #iterator<'_, crate::Descending> {
+        impl<D: #krate::Direction> ::core::iter::Iterator for #iterator<'_, D> {
             type Item = usize;
 
             fn next(&mut self) -> ::core::option::Option<Self::Item> {
-                let relative_shift = {
-                    let leading_zeros = self.bits.leading_zeros() as #uint;
-                    if leading_zeros == #ident::CAPACITY as #uint {
-                        return ::core::option::Option::None;
-                    }
-                    unsafe { leading_zeros.unchecked_add(1) }
-                };
-                self.bits <<= relative_shift;
-                self.shift += relative_shift;
-                ::core::option::Option::Some(unsafe {
-                    #ident::CAPACITY.unchecked_sub(self.shift as usize)
-                })
+                self.pop_with(D::pop_first)
+            }
+
+            fn size_hint(&self) -> (usize, ::core::option::Option<usize>) {
+                (self.remaining(), ::core::option::Option::Some(self.remaining()))
+            }
+
+            fn count(self) -> usize {
+                self.remaining()
+            }
+
+            fn last(mut self) -> ::core::option::Option<Self::Item> {
+                self.pop_with(D::pop_last)
+            }
+
+            fn nth(&mut self, n: usize) -> ::core::option::Option<Self::Item> {
+                self.skip_with(D::skip_first, n);
+                self.pop_with(D::pop_first)
+            }
+
+            fn min(self) -> ::core::option::Option<Self::Item> {
+                (self.bits != 0).then(|| self.bits.trailing_zeros() as usize)
+            }
+
+            fn max(self) -> ::core::option::Option<Self::Item> {
+                (self.bits != 0).then(|| (<#uint>::BITS - 1 - self.bits.leading_zeros()) as usize)
             }
         }
 
-        impl<'a, Direction> #iterator<'a, Direction> {
-            /// Creates an iterator over the indices of the bits that are set in `set`.
-            pub const fn new(set: &'a #ident) -> Self {
+        impl<D: #krate::Direction> ::core::iter::DoubleEndedIterator for #iterator<'_, D> {
+            fn next_back(&mut self) -> ::core::option::Option<Self::Item> {
+                self.pop_with(D::pop_last)
+            }
+
+            fn nth_back(&mut self, n: usize) -> ::core::option::Option<Self::Item> {
+                self.skip_with(D::skip_last, n);
+                self.pop_with(D::pop_last)
+            }
+        }
+
+        impl<D: #krate::Direction> ::core::iter::ExactSizeIterator for #iterator<'_, D> {}
+
+        impl<D: #krate::Direction> ::core::iter::FusedIterator for #iterator<'_, D> {}
+
+        impl<D> ::core::clone::Clone for #iterator<'_, D> {
+            fn clone(&self) -> Self {
                 Self {
-                    bits: set.bits(),
-                    shift: 0,
+                    bits: self.bits,
                     _markers: (::core::marker::PhantomData, ::core::marker::PhantomData),
                 }
             }
         }
 
+        impl<D: #krate::Direction> ::core::fmt::Debug for #iterator<'_, D> {
+            fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+                write!(f, "{}(", ::core::stringify!(#iterator))?;
+                f.debug_list().entries(self.clone()).finish()?;
+                write!(f, ")")
+            }
+        }
+
+        impl ::core::iter::IntoIterator for #ident {
+            type Item = usize;
+            type IntoIter = #iterator<'static, #krate::Ascending>;
+
+            /// Creates an iterator over the elements of the set in ascending order.
+            fn into_iter(self) -> Self::IntoIter {
+                #iterator::from_set(self)
+            }
+        }
+
+        impl<'a> ::core::iter::IntoIterator for &'a #ident {
+            type Item = usize;
+            type IntoIter = #iterator<'a, #krate::Ascending>;
+
+            /// Creates an iterator over the elements of the set in ascending order.
+            fn into_iter(self) -> Self::IntoIter {
+                #iterator::new(self)
+            }
+        }
+
         impl #ident {
             /// Creates an iterator over the indices of the bits that are set in the set.
             #[must_use]
-            #[cfg_attr(docsrs, doc(cfg(feature = #feature_flag)))]
-            pub const fn iter_indices<Direction>(&self) -> #iterator<'_, Direction>
+            #doc_cfg
+            pub const fn iter_indices<D: #krate::Direction>(&self) -> #iterator<'_, D> {
+                #iterator::new(self)
+            }
+
+            /// Creates an iterator over the indices of the bits that are set in the set and lie
+            /// within `range`.
+            ///
+            /// The bits outside of `range` are masked off up front, so they cost nothing to skip.
+            #[must_use]
+            #doc_cfg
+            pub fn iter_indices_in<D, R>(&self, range: R) -> #iterator<'_, D>
             where
-                for<'a> #iterator<'a, Direction>: ::core::iter::Iterator<Item = usize>,
+                D: #krate::Direction,
+                R: ::core::ops::RangeBounds<usize>,
             {
-                #iterator::new(self)
+                #iterator::from_set(self.masked(range))
+            }
+
+            /// Creates an iterator over the indices of the bits that are set in the set among
+            /// `start`, `start + step`, `start + 2 * step` and so on.
+            ///
+            /// The other bits are masked off up front, so they cost nothing to skip.
+            ///
+            /// # Panics
+            ///
+            /// Panics if `step` is `0`.
+            #[must_use]
+            #doc_cfg
+            pub const fn iter_indices_step<D: #krate::Direction>(
+                &self,
+                start: usize,
+                step: usize,
+            ) -> #iterator<'_, D> {
+                ::core::assert!(step != 0, "step must be non-zero");
+                if start >= Self::CAPACITY {
+                    return #iterator::from_set(Self::new());
+                }
+                // Doubling the pattern each time fills every `step`th bit in a logarithmic number
+                // of steps.
+                let mut stride: #uint = 1;
+                let mut filled = step;
+                while filled < <#uint>::BITS as usize {
+                    stride |= stride << filled;
+                    filled *= 2;
+                }
+                #iterator::from_set(self.intersection(Self::from_bits(stride << start)))
             }
         }
     }
@@ -1,67 +1,137 @@
+use crate::extras::Names;
 use proc_macro2::TokenStream;
-use quote::{format_ident, quote};
+use quote::quote;
 use syn::{Ident, Type};
 
-pub fn generate_code(ident: &Ident, uint: &Type, int: &Type, suffix: &str) -> TokenStream {
-    let iterator = format_ident!("BitSetIter{suffix}");
+pub fn generate_code(ident: &Ident, uint: &Type, names: &Names) -> TokenStream {
+    let Names {
+        krate,
+        iter: iterator,
+        doc_cfg,
+        ..
+    } = names;
     let ident_link = format!("[`{ident}`].");
-    let feature_flag = format!("b{suffix}");
     quote! {
         #[doc = "An iterator over the bits of a"]
         #[doc = #ident_link]
-        #[cfg_attr(docsrs, doc(cfg(feature = #feature_flag)))]
-        pub struct #iterator<'a, Direction = crate::Ascending> {
+        ///
+        /// The bits are visited in the order of their indices given by `D`, which can be any
+        /// `Direction`.
+        #doc_cfg
+        pub struct #iterator<'a, D = #krate::Ascending> {
             bits: #uint,
-            i: #uint,
-            _markers: (::core::marker::PhantomData<&'a ()>, ::core::marker::PhantomData<Direction>),
+            /// The indices of the bits left.
+            unvisited: #uint,
+            _markers: (::core::marker::PhantomData<&'a ()>, ::core::marker::PhantomData<D>),
         }
 
-        impl ::core::iter::Iterator for #iterator<'_, crate::Ascending> {
-            type Item = bool;
+        impl<'a, D> #iterator<'a, D> {
+            /// Creates an iterator over the bits of `set`.
+            pub const fn new(set: &'a #ident) -> Self {
+                Self {
+                    bits: set.bits(),
+                    unvisited: #ident::all().bits(),
+                    _markers: (::core::marker::PhantomData, ::core::marker::PhantomData),
+                }
+            }
 
-            fn next(&mut self) -> ::core::option::Option<Self::Item> {
-                (self.i < #ident::CAPACITY as #uint).then(|| {
-                    self.i = unsafe { self.i.unchecked_add(1) };
-                    let bit = self.bits & 1 != 0;
-                    self.bits >>= 1;
-                    bit
-                })
+            /// Returns the number of bits left.
+            const fn remaining(&self) -> usize {
+                self.unvisited.count_ones() as usize
+            }
+
+            /// Takes a bit out of the ones left with `pop`, which sees their indices as a
+            /// [`u128`].
+            fn take_with(
+                &mut self,
+                pop: fn(&mut u128) -> ::core::option::Option<usize>,
+            ) -> ::core::option::Option<bool> {
+                let mut unvisited = self.unvisited as u128;
+                let index = pop(&mut unvisited)?;
+                self.unvisited = unvisited as #uint;
+                ::core::option::Option::Some(self.bits >> index & 1 != 0)
+            }
+
+            /// Drops `n` of the bits left with `skip`, which sees their indices as a [`u128`].
+            fn skip_with(&mut self, skip: fn(&mut u128, usize), n: usize) {
+                let mut unvisited = self.unvisited as u128;
+                skip(&mut unvisited, n);
+                self.unvisited = unvisited as #uint;
             }
         }
 
-        impl ::core::iter::Iterator for #iterator<'_, crate::Descending> {
+        impl<D: #krate::Direction> ::core::iter::Iterator for #iterator<'_, D> {
             type Item = bool;
 
             fn next(&mut self) -> ::core::option::Option<Self::Item> {
-                const MASK: #uint = <#int>::MIN as #uint;
-                (self.i < #ident::CAPACITY as #uint).then(|| {
-                    self.i = unsafe { self.i.unchecked_add(1) };
-                    let bit = self.bits & MASK != 0;
-                    self.bits <<= 1;
-                    bit
-                })
+                self.take_with(D::pop_first)
+            }
+
+            fn size_hint(&self) -> (usize, ::core::option::Option<usize>) {
+                (self.remaining(), ::core::option::Option::Some(self.remaining()))
+            }
+
+            fn count(self) -> usize {
+                self.remaining()
+            }
+
+            fn last(mut self) -> ::core::option::Option<Self::Item> {
+                self.take_with(D::pop_last)
+            }
+
+            fn nth(&mut self, n: usize) -> ::core::option::Option<Self::Item> {
+                self.skip_with(D::skip_first, n);
+                self.take_with(D::pop_first)
+            }
+
+            fn min(self) -> ::core::option::Option<Self::Item> {
+                // The smallest bit is `false` unless every bit left is `true`.
+                (self.unvisited != 0).then_some(self.bits & self.unvisited == self.unvisited)
+            }
+
+            fn max(self) -> ::core::option::Option<Self::Item> {
+                (self.unvisited != 0).then_some(self.bits & self.unvisited != 0)
             }
         }
 
-        impl<'a, Direction> #iterator<'a, Direction> {
-            /// Creates an iterator over the bits of `set`.
-            pub const fn new(set: &'a #ident) -> Self {
+        impl<D: #krate::Direction> ::core::iter::DoubleEndedIterator for #iterator<'_, D> {
+            fn next_back(&mut self) -> ::core::option::Option<Self::Item> {
+                self.take_with(D::pop_last)
+            }
+
+            fn nth_back(&mut self, n: usize) -> ::core::option::Option<Self::Item> {
+                self.skip_with(D::skip_last, n);
+                self.take_with(D::pop_last)
+            }
+        }
+
+        impl<D: #krate::Direction> ::core::iter::ExactSizeIterator for #iterator<'_, D> {}
+
+        impl<D: #krate::Direction> ::core::iter::FusedIterator for #iterator<'_, D> {}
+
+        impl<D> ::core::clone::Clone for #iterator<'_, D> {
+            fn clone(&self) -> Self {
                 Self {
-                    bits: set.bits(),
-                    i: 0,
+                    bits: self.bits,
+                    unvisited: self.unvisited,
                     _markers: (::core::marker::PhantomData, ::core::marker::PhantomData),
                 }
             }
         }
 
+        impl<D: #krate::Direction> ::core::fmt::Debug for #iterator<'_, D> {
+            fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+                write!(f, "{}(", ::core::stringify!(#iterator))?;
+                f.debug_list().entries(self.clone()).finish()?;
+                write!(f, ")")
+            }
+        }
+
         impl #ident {
             /// Creates an iterator over the bits of the set.
             #[must_use]
-            #[cfg_attr(docsrs, doc(cfg(feature = #feature_flag)))]
-            pub const fn iter_bits<Direction>(&self) -> #iterator<'_, Direction>
-            where
-                for<'a> #iterator<'a, Direction>: ::core::iter::Iterator<Item = bool>,
-            {
+            #doc_cfg
+            pub const fn iter_bits<D: #krate::Direction>(&self) -> #iterator<'_, D> {
                 #iterator::new(self)
             }
         }
@@ -1,8 +1,8 @@
 use crate::type_utils::repr;
 use proc_macro_error::abort;
-use proc_macro2::TokenStream;
+use proc_macro2::{Literal, TokenStream};
 use quote::{format_ident, quote};
-use syn::{Data, DataEnum, DataStruct, DataUnion, Ident, Type, spanned::Spanned};
+use syn::{Data, DataEnum, DataStruct, DataUnion, Ident, LitInt, Type, spanned::Spanned};
 
 pub fn get_uint(data: Data) -> Type {
     let fields = match data {
@@ -35,30 +35,146 @@ pub fn get_uint(data: Data) -> Type {
     field.ty.clone()
 }
 
-pub fn generate_code(ident: Ident, uint: Type) -> TokenStream {
-    let uint_repr = repr(&uint);
-    let uint_size = if uint_repr == "usize" {
-        size_of::<usize>()
+pub fn generate_code(ident: &Ident, uint: &Type, capacity: Option<&LitInt>) -> TokenStream {
+    let uint_repr = repr(uint);
+    // A `usize` is as wide as a pointer on the target, which the macro can't see from here, so it
+    // gets a version of the width-dependent items for each width a pointer can have.
+    let widths: Vec<(Option<&str>, usize)> = if uint_repr == "usize" {
+        vec![(Some("16"), 16), (Some("32"), 32), (Some("64"), 64)]
     } else {
-        uint_repr
+        let bits = uint_repr
             .strip_prefix('u')
             .and_then(|uint_repr| uint_repr.parse::<usize>().ok())
+            .filter(|bits| [8, 16, 32, 64, 128].contains(bits))
             .unwrap_or_else(|| {
                 abort!(
                     uint.span(), "unexpected type";
                     note = "the underlying type of a bitset must be a primitive, unsigned integer";
                 );
-            })
+            });
+        vec![(None, bits)]
+    };
+    let max_bits = widths.iter().map(|&(_, bits)| bits).max().unwrap();
+    let (widths, capacity_expr, capacity_check) = match capacity {
+        Some(capacity) => {
+            let value = capacity
+                .base10_parse::<usize>()
+                .unwrap_or_else(|error| abort!(capacity, "{}", error));
+            if value == 0 || value > max_bits {
+                abort!(
+                    capacity, "capacity must be between 1 and {}", max_bits;
+                    note = "the capacity cannot exceed the number of bits in `{}`", uint_repr;
+                );
+            }
+            let literal = Literal::usize_unsuffixed(value);
+            // Only a `usize` can turn out to be narrower than the capacity once the target is
+            // known.
+            let capacity_check = (uint_repr == "usize").then(|| {
+                let message = format!("the capacity of `{ident}` exceeds the width of `usize`");
+                quote! {
+                    const _: () = ::core::assert!(#literal <= <#uint>::BITS as usize, #message);
+                }
+            });
+            (vec![(None, value)], quote! { #literal }, capacity_check)
+        }
+        None => (widths, quote! { <#uint>::BITS as usize }, None),
     };
     let bitset_link = format!("[`{ident}`].");
     let plural_bitset_link = format!("[`{ident}`]s.");
-    let clear_i_to_size = format_ident!("clear_i_to_{uint_size}");
-    let cleared_i_to_size = format_ident!("cleared_i_to_{uint_size}");
-    let mask_i_to_size = format_ident!("mask_i_to_{uint_size}");
-    let masked_i_to_size = format_ident!("masked_i_to_{uint_size}");
-    let index_to_size = format!("`index..{uint_size}`");
-    let index_to_size_with_comma = format!("{index_to_size},");
-    let index_to_size_with_period = format!("{index_to_size}.\n");
+    let mut boundary_methods = TokenStream::new();
+    let mut split_off_must_use = TokenStream::new();
+    for (pointer_width, size) in widths {
+        let cfg = pointer_width.map(|width| quote! { #[cfg(target_pointer_width = #width)] });
+        let clear_i_to_size = format_ident!("clear_i_to_{size}");
+        let cleared_i_to_size = format_ident!("cleared_i_to_{size}");
+        let mask_i_to_size = format_ident!("mask_i_to_{size}");
+        let masked_i_to_size = format_ident!("masked_i_to_{size}");
+        let index_to_size = format!("`index..{size}`");
+        let index_to_size_with_comma = format!("{index_to_size},");
+        let index_to_size_with_period = format!("{index_to_size}.");
+        let message =
+            format!("consider using the return value or calling `{clear_i_to_size}` instead");
+        split_off_must_use.extend(match pointer_width {
+            // Each width has its own name for the method to suggest.
+            Some(width) => {
+                quote! { #[cfg_attr(target_pointer_width = #width, must_use = #message)] }
+            }
+            None => quote! { #[must_use = #message] },
+        });
+        boundary_methods.extend(quote! {
+            /// Creates a copy of this set that only has bits with indices greater than or equal to
+            /// `index`.
+            #cfg
+            #[must_use]
+            pub const fn #masked_i_to_size(mut self, index: usize) -> Self {
+                Self::#mask_i_to_size(&mut self, index);
+                self
+            }
+
+            #[doc = "Removes any bits with indices outside the range"]
+            #[doc = #index_to_size_with_period]
+            #cfg
+            pub const fn #mask_i_to_size(&mut self, index: usize) {
+                self.clear_0_to_i(index);
+            }
+
+            #[doc = "Creates a copy of this set without the bits with indices in the range"]
+            #[doc = #index_to_size_with_period]
+            #cfg
+            #[must_use]
+            pub const fn #cleared_i_to_size(mut self, index: usize) -> Self {
+                Self::#clear_i_to_size(&mut self, index);
+                self
+            }
+
+            #[doc = "Clears bits"]
+            #[doc = #index_to_size_with_comma]
+            #[doc = "keeping bits `0..index` in their original states."]
+            #cfg
+            pub const fn #clear_i_to_size(&mut self, index: usize) {
+                self.0 &= Self::__range_bits(0, index);
+            }
+        });
+    }
+    let select_body = |select_in_word: Ident| {
+        if uint_repr == "u128" {
+            quote! {
+                let low = self.0 as u64;
+                let low_len = low.count_ones() as usize;
+                if rank < low_len {
+                    Self::#select_in_word(low, rank)
+                } else {
+                    64 + Self::#select_in_word((self.0 >> 64) as u64, rank - low_len)
+                }
+            }
+        } else {
+            quote! { Self::#select_in_word(self.0 as u64, rank) }
+        }
+    };
+    let select_bmi2 = select_body(format_ident!("__select_in_word_bmi2"));
+    let select_const = select_body(format_ident!("__select_in_word"));
+    let (compress_bmi2, expand_bmi2) = if uint_repr == "u128" {
+        (
+            quote! {
+                let mask_low = mask.0 as u64;
+                let low = Self::__pext_u64(self.0 as u64, mask_low);
+                let high = Self::__pext_u64((self.0 >> 64) as u64, (mask.0 >> 64) as u64);
+                low as u128 | (high as u128) << mask_low.count_ones()
+            },
+            quote! {
+                let mask_low = mask.0 as u64;
+                let low = Self::__pdep_u64(self.0 as u64, mask_low);
+                let high_bits = (self.0 >> mask_low.count_ones()) as u64;
+                let high = Self::__pdep_u64(high_bits, (mask.0 >> 64) as u64);
+                low as u128 | (high as u128) << 64
+            },
+        )
+    } else {
+        (
+            quote! { Self::__pext_u64(self.0 as u64, mask.0 as u64) as #uint },
+            quote! { Self::__pdep_u64(self.0 as u64, mask.0 as u64) as #uint },
+        )
+    };
     quote! {
         impl ::core::fmt::Binary for #ident {
             fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
@@ -110,6 +226,20 @@ pub fn generate_code(ident: Ident, uint: Type) -> TokenStream {
             }
         }
 
+        impl ::core::iter::Extend<usize> for #ident {
+            fn extend<I: ::core::iter::IntoIterator<Item = usize>>(&mut self, iter: I) {
+                for index in iter {
+                    self.insert_quiet(index);
+                }
+            }
+        }
+
+        impl<'a> ::core::iter::Extend<&'a usize> for #ident {
+            fn extend<I: ::core::iter::IntoIterator<Item = &'a usize>>(&mut self, iter: I) {
+                self.extend(iter.into_iter().copied());
+            }
+        }
+
         impl ::core::convert::From<#uint> for #ident {
             fn from(value: #uint) -> Self {
                 Self::from_bits(value)
@@ -122,6 +252,30 @@ pub fn generate_code(ident: Ident, uint: Type) -> TokenStream {
             }
         }
 
+        impl ::core::iter::FromIterator<usize> for #ident {
+            /// Creates a set containing every index in `iter`. Indices that are at least
+            /// [`Self::CAPACITY`] are ignored.
+            fn from_iter<I: ::core::iter::IntoIterator<Item = usize>>(iter: I) -> Self {
+                let mut set = Self::new();
+                set.extend(iter);
+                set
+            }
+        }
+
+        impl ::core::iter::FromIterator<bool> for #ident {
+            /// Creates a set from a sequence of bits, starting with the bit at index `0`. Bits
+            /// past [`Self::CAPACITY`] are ignored.
+            fn from_iter<I: ::core::iter::IntoIterator<Item = bool>>(iter: I) -> Self {
+                let mut set = Self::new();
+                for (index, bit) in iter.into_iter().take(Self::CAPACITY).enumerate() {
+                    if bit {
+                        set.insert_quiet(index);
+                    }
+                }
+                set
+            }
+        }
+
         impl ::core::fmt::LowerHex for #ident {
             fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
                 write!(f, "{}(", ::core::stringify!(#ident))?;
@@ -686,13 +840,18 @@ pub fn generate_code(ident: Ident, uint: Type) -> TokenStream {
             }
         }
 
+        #capacity_check
+
         impl #ident {
             #[doc = "The capacity of a"]
             #[doc = #bitset_link]
-            pub const CAPACITY: usize = 8 * ::core::mem::size_of::<Self>();
+            pub const CAPACITY: usize = #capacity_expr;
 
             const __ONE: #uint = 1;
 
+            /// The bits that lie within [`Self::CAPACITY`]. Every other bit is always `0`.
+            const __MASK: #uint = <#uint>::MAX >> (<#uint>::BITS as usize - Self::CAPACITY);
+
             /// Creates an empty set.
             #[doc(alias = "empty")]
             #[must_use]
@@ -705,8 +864,8 @@ pub fn generate_code(ident: Ident, uint: Type) -> TokenStream {
             /// If `index >=`[`Self::CAPACITY`], the resulting set will be empty.
             #[must_use]
             pub const fn unit(index: usize) -> Self {
-                if let Some(bits) = Self::__ONE.checked_shl(index as u32) {
-                    Self::from_bits(bits)
+                if index < Self::CAPACITY {
+                    Self(Self::__ONE << index)
                 } else {
                     Self::new()
                 }
@@ -715,13 +874,15 @@ pub fn generate_code(ident: Ident, uint: Type) -> TokenStream {
             /// Creates a set with all bits set.
             #[must_use]
             pub const fn all() -> Self {
-                Self(<#uint>::MAX)
+                Self(Self::__MASK)
             }
 
             /// Creates a set with the given bits.
+            ///
+            /// Any bits at or above [`Self::CAPACITY`] are discarded.
             #[must_use]
             pub const fn from_bits(bits: #uint) -> Self {
-                Self(bits)
+                Self(bits & Self::__MASK)
             }
 
             /// Returns the underlying bits of the set.
@@ -740,7 +901,7 @@ pub fn generate_code(ident: Ident, uint: Type) -> TokenStream {
             #[doc(alias = "inverse")]
             #[must_use]
             pub const fn complement(self) -> Self {
-                Self(!self.0)
+                Self(!self.0 & Self::__MASK)
             }
 
             #[doc = "Creates a new set with values that are in both `self` and `rhs`.\n"]
@@ -763,6 +924,22 @@ pub fn generate_code(ident: Ident, uint: Type) -> TokenStream {
                 Self(self.0 | rhs.0)
             }
 
+            /// Creates a new set with the values that are in any of `sets`.
+            ///
+            /// This is the empty set if `sets` is empty.
+            #[must_use]
+            pub fn union_all<I: ::core::iter::IntoIterator<Item = Self>>(sets: I) -> Self {
+                sets.into_iter().fold(Self::new(), Self::union)
+            }
+
+            /// Creates a new set with the values that are in every one of `sets`.
+            ///
+            /// This is the full set if `sets` is empty.
+            #[must_use]
+            pub fn intersection_all<I: ::core::iter::IntoIterator<Item = Self>>(sets: I) -> Self {
+                sets.into_iter().fold(Self::all(), Self::intersection)
+            }
+
             #[doc = "Creates a new set with values that are in `self`, but not in `rhs`.\n"]
             #[doc = "This is the `const` alternative to"]
             #[doc = "[`Sub::sub`](https://doc.rust-lang.org/core/ops/trait.Sub.html#tymethod.sub)"]
@@ -849,7 +1026,7 @@ pub fn generate_code(ident: Ident, uint: Type) -> TokenStream {
             /// Returns `true` if the set contains all [`Self::CAPACITY`] elements.
             #[must_use]
             pub const fn is_full(self) -> bool {
-                self.0 == #uint::MAX
+                self.0 == Self::__MASK
             }
 
             /// Returns the number of elements in the set.
@@ -861,11 +1038,7 @@ pub fn generate_code(ident: Ident, uint: Type) -> TokenStream {
             /// Returns `true` if the bit at `index` is set.
             #[must_use]
             pub const fn contains(self, index: usize) -> bool {
-                if let Some(mask) = Self::__ONE.checked_shl(index as u32) {
-                    self.0 & mask != 0
-                } else {
-                    false
-                }
+                index < Self::CAPACITY && self.0 & (Self::__ONE << index) != 0
             }
 
             /// Gets the bit at `index`.
@@ -881,7 +1054,8 @@ pub fn generate_code(ident: Ident, uint: Type) -> TokenStream {
             /// If no bits are set, this returns [`Self::CAPACITY`].
             #[must_use]
             pub const fn min_index(self) -> usize {
-                self.0.trailing_zeros() as usize
+                // The bits above the capacity act as a sentinel, so an empty set lands on it.
+                (self.0 | !Self::__MASK).trailing_zeros() as usize
             }
 
             /// Returns the index of the most significant bit that is set.
@@ -906,6 +1080,289 @@ pub fn generate_code(ident: Ident, uint: Type) -> TokenStream {
                 }
             }
 
+            /// Returns the smallest element in the set, or [`None`] if it is empty.
+            #[must_use]
+            pub const fn first(self) -> ::core::option::Option<usize> {
+                if self.0 == 0 {
+                    ::core::option::Option::None
+                } else {
+                    ::core::option::Option::Some(self.0.trailing_zeros() as usize)
+                }
+            }
+
+            /// Returns the largest element in the set, or [`None`] if it is empty.
+            #[must_use]
+            pub const fn last(self) -> ::core::option::Option<usize> {
+                self.max_index_checked()
+            }
+
+            /// Returns the smallest element greater than `index`, or [`None`] if there isn't one.
+            #[must_use]
+            pub const fn next_after(self, index: usize) -> ::core::option::Option<usize> {
+                if index >= Self::CAPACITY - 1 {
+                    return ::core::option::Option::None;
+                }
+                Self(self.0 & (Self::__MASK << (index + 1))).first()
+            }
+
+            /// Returns the largest element less than `index`, or [`None`] if there isn't one.
+            #[must_use]
+            pub const fn prev_before(self, index: usize) -> ::core::option::Option<usize> {
+                if index >= Self::CAPACITY {
+                    return self.last();
+                }
+                Self(self.0 & ((Self::__ONE << index) - 1)).last()
+            }
+
+            /// Returns the number of elements less than `index`.
+            ///
+            /// If `index >=`[`Self::CAPACITY`], this is the same as [`len`](Self::len).
+            #[must_use]
+            pub const fn rank(self, index: usize) -> usize {
+                if index >= Self::CAPACITY {
+                    self.len()
+                } else {
+                    (self.0 & ((Self::__ONE << index) - 1)).count_ones() as usize
+                }
+            }
+
+            /// Returns the index of the element with `rank` elements less than it, or [`None`] if
+            /// the set has `rank` or fewer elements.
+            ///
+            /// This is the inverse of [`rank`](Self::rank), so `set.select(set.rank(i))` is
+            /// `Some(i)` for every `i` in the set. On `x86_64`, it uses the BMI2 `pdep`
+            /// instruction if the CPU supports it.
+            #[must_use]
+            pub fn select(self, rank: usize) -> ::core::option::Option<usize> {
+                #[cfg(target_arch = "x86_64")]
+                if Self::__has_bmi2() {
+                    if rank >= self.len() {
+                        return ::core::option::Option::None;
+                    }
+                    // SAFETY: The CPU supports BMI2.
+                    return ::core::option::Option::Some(unsafe { #select_bmi2 });
+                }
+                self.select_const(rank)
+            }
+
+            /// Returns the index of the element with `rank` elements greater than it, or [`None`]
+            /// if the set has `rank` or fewer elements.
+            #[must_use]
+            pub fn select_from_top(self, rank: usize) -> ::core::option::Option<usize> {
+                let len = self.len();
+                if rank >= len {
+                    ::core::option::Option::None
+                } else {
+                    self.select(len - 1 - rank)
+                }
+            }
+
+            /// Returns the index of the element with `rank` elements less than it, or [`None`] if
+            /// the set has `rank` or fewer elements.
+            ///
+            /// This is the `const` alternative to [`select`](Self::select), which is faster on
+            /// CPUs with BMI2.
+            #[must_use]
+            pub const fn select_const(self, rank: usize) -> ::core::option::Option<usize> {
+                if rank >= self.len() {
+                    return ::core::option::Option::None;
+                }
+                ::core::option::Option::Some({ #select_const })
+            }
+
+            /// Returns the index of the element with `rank` elements greater than it, or [`None`]
+            /// if the set has `rank` or fewer elements.
+            ///
+            /// This is the `const` alternative to [`select_from_top`](Self::select_from_top),
+            /// which is faster on CPUs with BMI2.
+            #[must_use]
+            pub const fn select_from_top_const(self, rank: usize) -> ::core::option::Option<usize> {
+                let len = self.len();
+                if rank >= len {
+                    ::core::option::Option::None
+                } else {
+                    self.select_const(len - 1 - rank)
+                }
+            }
+
+            /// Returns the index of the bit in `word` with `rank` set bits below it. The caller
+            /// must ensure that the CPU supports BMI2 and that `word` has more than `rank` bits
+            /// set.
+            #[cfg(target_arch = "x86_64")]
+            unsafe fn __select_in_word_bmi2(word: u64, rank: usize) -> usize {
+                // Depositing a lone bit into the positions of `word` lands it on the one we want.
+                // SAFETY: The caller ensures that the CPU supports BMI2.
+                let deposited = unsafe { Self::__pdep_u64(1 << rank, word) };
+                deposited.trailing_zeros() as usize
+            }
+
+            /// Returns the index of the bit in `word` with `rank` set bits below it. The caller
+            /// must ensure that `word` has more than `rank` bits set.
+            const fn __select_in_word(mut word: u64, mut rank: usize) -> usize {
+                // Narrow in on the bit by halves, skipping past any half that's too sparse to
+                // hold it.
+                let mut index = 0;
+                let mut width = 32;
+                while width > 0 {
+                    let low = word & ((1 << width) - 1);
+                    let low_len = low.count_ones() as usize;
+                    if rank < low_len {
+                        word = low;
+                    } else {
+                        rank -= low_len;
+                        word >>= width;
+                        index += width;
+                    }
+                    width /= 2;
+                }
+                index
+            }
+
+            /// Gathers the elements of the set that are also in `mask`, renumbering them densely so
+            /// that the `i`th smallest element of `mask` becomes `i`.
+            ///
+            /// This is the inverse of [`expand`](Self::expand) for sets that are subsets of `mask`.
+            /// On `x86_64`, it uses the BMI2 `pext` instruction if the CPU supports it.
+            #[must_use]
+            pub fn compress(self, mask: Self) -> Self {
+                #[cfg(target_arch = "x86_64")]
+                if Self::__has_bmi2() {
+                    // SAFETY: The CPU supports BMI2.
+                    return Self(unsafe { #compress_bmi2 });
+                }
+                self.compress_const(mask)
+            }
+
+            /// Spreads the elements of the set out over the elements of `mask`, so that `i` becomes
+            /// the `i`th smallest element of `mask`. Elements that are at least `mask.len()` are
+            /// dropped.
+            ///
+            /// This is the inverse of [`compress`](Self::compress). On `x86_64`, it uses the BMI2
+            /// `pdep` instruction if the CPU supports it.
+            #[must_use]
+            pub fn expand(self, mask: Self) -> Self {
+                #[cfg(target_arch = "x86_64")]
+                if Self::__has_bmi2() {
+                    // SAFETY: The CPU supports BMI2.
+                    return Self(unsafe { #expand_bmi2 });
+                }
+                self.expand_const(mask)
+            }
+
+            /// Gathers the elements of the set that are also in `mask`, renumbering them densely so
+            /// that the `i`th smallest element of `mask` becomes `i`.
+            ///
+            /// This is the `const` alternative to [`compress`](Self::compress), which is faster on
+            /// CPUs with BMI2.
+            #[must_use]
+            pub const fn compress_const(self, mask: Self) -> Self {
+                // This is the compress from Hacker's Delight. Each round shifts the bits that still
+                // have an odd multiple of `shift` gaps below them down by `shift`.
+                let mut bits = self.0 & mask.0;
+                let mut mask = mask.0;
+                let mut gaps_below = !mask << 1;
+                let mut shift = 1;
+                while shift < <#uint>::BITS {
+                    let odd_gaps = Self::__prefix_parity(gaps_below);
+                    let moving = odd_gaps & mask;
+                    mask = mask ^ moving | moving >> shift;
+                    let moved = bits & moving;
+                    bits = bits ^ moved | moved >> shift;
+                    gaps_below &= !odd_gaps;
+                    shift *= 2;
+                }
+                Self(bits)
+            }
+
+            /// Spreads the elements of the set out over the elements of `mask`, so that `i` becomes
+            /// the `i`th smallest element of `mask`.
+            ///
+            /// This is the `const` alternative to [`expand`](Self::expand), which is faster on CPUs
+            /// with BMI2.
+            #[must_use]
+            pub const fn expand_const(self, mask: Self) -> Self {
+                // This runs the rounds of `compress_const` backwards, shifting each group of bits
+                // back up by as much as it would have been shifted down.
+                let mut moves: [#uint; 8] = [0; 8];
+                let mut remaining = mask.0;
+                let mut gaps_below = !remaining << 1;
+                let mut round = 0;
+                while 1 << round < <#uint>::BITS {
+                    let odd_gaps = Self::__prefix_parity(gaps_below);
+                    let moving = odd_gaps & remaining;
+                    moves[round] = moving;
+                    remaining = remaining ^ moving | moving >> (1 << round);
+                    gaps_below &= !odd_gaps;
+                    round += 1;
+                }
+                let mut bits = self.0;
+                while round > 0 {
+                    round -= 1;
+                    let moving = moves[round];
+                    bits = bits & !moving | bits << (1 << round) & moving;
+                }
+                Self(bits & mask.0)
+            }
+
+            /// Returns the parity of the bits at or below each position in `bits`.
+            const fn __prefix_parity(mut bits: #uint) -> #uint {
+                let mut shift = 1;
+                while shift < <#uint>::BITS {
+                    bits ^= bits << shift;
+                    shift *= 2;
+                }
+                bits
+            }
+
+            /// Returns whether the CPU supports BMI2, checking at runtime unless the target already
+            /// guarantees it.
+            #[cfg(target_arch = "x86_64")]
+            fn __has_bmi2() -> bool {
+                #[cfg(target_feature = "bmi2")]
+                {
+                    true
+                }
+                #[cfg(not(target_feature = "bmi2"))]
+                {
+                    use ::core::sync::atomic::{AtomicU8, Ordering};
+                    // 0 until the CPU has been checked, then 1 without BMI2 and 2 with it.
+                    static DETECTED: AtomicU8 = AtomicU8::new(0);
+                    match DETECTED.load(Ordering::Relaxed) {
+                        0 => {
+                            // BMI2 is bit 8 of EBX in leaf 7, which needs no support from the OS.
+                            #[allow(unused_unsafe)]
+                            let has_bmi2 = unsafe {
+                                ::core::arch::x86_64::__cpuid(0).eax >= 7
+                                    && ::core::arch::x86_64::__cpuid_count(7, 0).ebx & 1 << 8 != 0
+                            };
+                            DETECTED.store(1 + has_bmi2 as u8, Ordering::Relaxed);
+                            has_bmi2
+                        }
+                        detected => detected == 2,
+                    }
+                }
+            }
+
+            /// The caller must ensure that the CPU supports BMI2.
+            #[cfg(target_arch = "x86_64")]
+            #[target_feature(enable = "bmi2")]
+            unsafe fn __pext_u64(bits: u64, mask: u64) -> u64 {
+                #[allow(unused_unsafe)]
+                unsafe {
+                    ::core::arch::x86_64::_pext_u64(bits, mask)
+                }
+            }
+
+            /// The caller must ensure that the CPU supports BMI2.
+            #[cfg(target_arch = "x86_64")]
+            #[target_feature(enable = "bmi2")]
+            unsafe fn __pdep_u64(bits: u64, mask: u64) -> u64 {
+                #[allow(unused_unsafe)]
+                unsafe {
+                    ::core::arch::x86_64::_pdep_u64(bits, mask)
+                }
+            }
+
             #[doc = "Creates a copy of this set with all values incremented by `shift`.\n"]
             #[doc = "Any values that reach or exceed [`Self::CAPACITY`] will be lost.\n"]
             #[doc = "This is the `const` alternative to"]
@@ -925,7 +1382,7 @@ pub fn generate_code(ident: Ident, uint: Type) -> TokenStream {
             #[doc = #plural_bitset_link]
             pub const fn shift_up_by(&mut self, shift: u32) {
                 self.0 = match self.0.checked_shl(shift) {
-                    Some(shifted) => shifted,
+                    Some(shifted) => shifted & Self::__MASK,
                     None => 0,
                 };
             }
@@ -944,7 +1401,7 @@ pub fn generate_code(ident: Ident, uint: Type) -> TokenStream {
             pub const fn shift_up_by_signed(&mut self, shift: i32) {
                 self.0 = if shift >= 0 {
                     match self.0.checked_shl(shift as u32) {
-                        Some(shifted) => shifted,
+                        Some(shifted) => shifted & Self::__MASK,
                         None => 0,
                     }
                 } else {
@@ -998,22 +1455,149 @@ pub fn generate_code(ident: Ident, uint: Type) -> TokenStream {
                     }
                 } else {
                     match self.0.checked_shl(-shift as u32) {
-                        Some(shifted) => shifted,
+                        Some(shifted) => shifted & Self::__MASK,
                         None => 0,
                     }
                 };
             }
 
+            /// Creates a copy of this set with all values incremented by `shift`, wrapping around
+            /// to `0` past [`Self::CAPACITY`]` - 1`.
+            #[must_use]
+            pub const fn rotated_up_by(mut self, shift: u32) -> Self {
+                self.rotate_up_by(shift);
+                self
+            }
+
+            /// Increments all values in this set by `shift`, wrapping around to `0` past
+            /// [`Self::CAPACITY`]` - 1`.
+            pub const fn rotate_up_by(&mut self, shift: u32) {
+                self.rotate_up_by_within(shift, Self::CAPACITY);
+            }
+
+            /// Creates a copy of this set with all values decremented by `shift`, wrapping around
+            /// to [`Self::CAPACITY`]` - 1` past `0`.
+            #[must_use]
+            pub const fn rotated_down_by(mut self, shift: u32) -> Self {
+                self.rotate_down_by(shift);
+                self
+            }
+
+            /// Decrements all values in this set by `shift`, wrapping around to
+            /// [`Self::CAPACITY`]` - 1` past `0`.
+            pub const fn rotate_down_by(&mut self, shift: u32) {
+                self.rotate_down_by_within(shift, Self::CAPACITY);
+            }
+
+            /// Creates a copy of this set with all values less than `modulus` incremented by
+            /// `shift` modulo `modulus`. Values that are at least `modulus` are left alone.
+            ///
+            /// A `modulus` greater than [`Self::CAPACITY`] is treated as [`Self::CAPACITY`].
+            #[must_use]
+            pub const fn rotated_up_by_within(mut self, shift: u32, modulus: usize) -> Self {
+                self.rotate_up_by_within(shift, modulus);
+                self
+            }
+
+            /// Increments all values less than `modulus` by `shift` modulo `modulus`. Values that
+            /// are at least `modulus` are left alone.
+            ///
+            /// A `modulus` greater than [`Self::CAPACITY`] is treated as [`Self::CAPACITY`].
+            pub const fn rotate_up_by_within(&mut self, shift: u32, modulus: usize) {
+                let modulus = if modulus > Self::CAPACITY {
+                    Self::CAPACITY
+                } else {
+                    modulus
+                };
+                if modulus == 0 {
+                    return;
+                }
+                let shift = (shift as usize % modulus) as u32;
+                if shift == 0 {
+                    return;
+                }
+                let window = Self::__range_bits(0, modulus);
+                let low = self.0 & window;
+                let rotated = (low << shift | low >> (modulus as u32 - shift)) & window;
+                self.0 = self.0 & !window | rotated;
+            }
+
+            /// Creates a copy of this set with all values less than `modulus` decremented by
+            /// `shift` modulo `modulus`. Values that are at least `modulus` are left alone.
+            ///
+            /// A `modulus` greater than [`Self::CAPACITY`] is treated as [`Self::CAPACITY`].
+            #[must_use]
+            pub const fn rotated_down_by_within(mut self, shift: u32, modulus: usize) -> Self {
+                self.rotate_down_by_within(shift, modulus);
+                self
+            }
+
+            /// Decrements all values less than `modulus` by `shift` modulo `modulus`. Values that
+            /// are at least `modulus` are left alone.
+            ///
+            /// A `modulus` greater than [`Self::CAPACITY`] is treated as [`Self::CAPACITY`].
+            pub const fn rotate_down_by_within(&mut self, shift: u32, modulus: usize) {
+                let modulus = if modulus > Self::CAPACITY {
+                    Self::CAPACITY
+                } else {
+                    modulus
+                };
+                if modulus == 0 {
+                    return;
+                }
+                // Rotating down by `shift` is the same as rotating up by what's left of a full
+                // turn.
+                let shift = (shift as usize % modulus) as u32;
+                self.rotate_up_by_within(modulus as u32 - shift, modulus);
+            }
+
+            /// Creates a copy of this set with every value `i` replaced by
+            /// [`Self::CAPACITY`]` - 1 - i`.
+            #[must_use]
+            pub const fn reversed(mut self) -> Self {
+                self.reverse();
+                self
+            }
+
+            /// Replaces every value `i` in this set with [`Self::CAPACITY`]` - 1 - i`.
+            pub const fn reverse(&mut self) {
+                self.0 = self.0.reverse_bits() >> (<#uint>::BITS as usize - Self::CAPACITY);
+            }
+
+            /// Creates a copy of this set with every value `i` less than `n` replaced by
+            /// `n - 1 - i`. Values that are at least `n` are left alone.
+            ///
+            /// An `n` greater than [`Self::CAPACITY`] is treated as [`Self::CAPACITY`].
+            #[must_use]
+            pub const fn reflected_within(mut self, n: usize) -> Self {
+                self.reflect_within(n);
+                self
+            }
+
+            /// Replaces every value `i` less than `n` in this set with `n - 1 - i`. Values that are
+            /// at least `n` are left alone.
+            ///
+            /// An `n` greater than [`Self::CAPACITY`] is treated as [`Self::CAPACITY`].
+            pub const fn reflect_within(&mut self, n: usize) {
+                let n = if n > Self::CAPACITY {
+                    Self::CAPACITY
+                } else {
+                    n
+                };
+                if n == 0 {
+                    return;
+                }
+                let window = Self::__range_bits(0, n);
+                let reflected = (self.0 & window).reverse_bits() >> (<#uint>::BITS as usize - n);
+                self.0 = self.0 & !window | reflected;
+            }
+
             /// Clears the set, removing all values.
             pub const fn clear(&mut self) {
                 self.0 = 0;
             }
 
             /// Creates a copy of this set that only has values less than `index`.
-            ///
-            /// # Panics
-            ///
-            /// Panics if `index >=`[`Self::CAPACITY`].
             #[must_use]
             pub const fn masked_0_to_i(mut self, index: usize) -> Self {
                 self.mask_0_to_i(index);
@@ -1021,71 +1605,171 @@ pub fn generate_code(ident: Ident, uint: Type) -> TokenStream {
             }
 
             /// Removes any bits with indices outside the range `0..index`.
+            pub const fn mask_0_to_i(&mut self, index: usize) {
+                self.0 &= Self::__range_bits(0, index);
+            }
+
+            /// Creates a copy of this set without the bits with indices in the range `0..index`.
+            #[must_use]
+            pub const fn cleared_0_to_i(mut self, index: usize) -> Self {
+                self.clear_0_to_i(index);
+                self
+            }
+
+            /// Clears bits `0..index`, keeping the bits from `index` up in their original states.
+            pub const fn clear_0_to_i(&mut self, index: usize) {
+                self.0 &= !Self::__range_bits(0, index);
+            }
+
+            #boundary_methods
+
+            /// Creates a set containing every index in `range`.
             ///
-            /// # Panics
+            /// Any part of `range` at or beyond [`Self::CAPACITY`] is ignored, as is a range that
+            /// starts after it ends.
+            #[must_use]
+            pub fn from_range<R: ::core::ops::RangeBounds<usize>>(range: R) -> Self {
+                let (start, end) = Self::__range_bounds(&range);
+                Self::from_range_const(start, end)
+            }
+
+            /// The `const` alternative to [`from_range`](Self::from_range), for the range
+            /// `start..end`.
+            #[must_use]
+            pub const fn from_range_const(start: usize, end: usize) -> Self {
+                Self(Self::__range_bits(start, end))
+            }
+
+            /// Sets every bit in `range` to `1`.
             ///
-            /// Panics if `index >=`[`Self::CAPACITY`].
-            pub const fn mask_0_to_i(&mut self, index: usize) {
-                Self::#clear_i_to_size(self, index);
+            /// Any part of `range` at or beyond [`Self::CAPACITY`] is ignored.
+            pub fn insert_range<R: ::core::ops::RangeBounds<usize>>(&mut self, range: R) {
+                let (start, end) = Self::__range_bounds(&range);
+                self.insert_range_const(start, end);
             }
 
-            /// Creates a copy of this set that only has bits with indices greater than or equal to
-            /// `index`.
+            /// The `const` alternative to [`insert_range`](Self::insert_range), for the range
+            /// `start..end`.
+            pub const fn insert_range_const(&mut self, start: usize, end: usize) {
+                self.0 |= Self::__range_bits(start, end);
+            }
+
+            /// Sets every bit in `range` to `0`.
             ///
-            /// # Panics
+            /// Any part of `range` at or beyond [`Self::CAPACITY`] is ignored.
+            pub fn remove_range<R: ::core::ops::RangeBounds<usize>>(&mut self, range: R) {
+                let (start, end) = Self::__range_bounds(&range);
+                self.remove_range_const(start, end);
+            }
+
+            /// The `const` alternative to [`remove_range`](Self::remove_range), for the range
+            /// `start..end`.
+            pub const fn remove_range_const(&mut self, start: usize, end: usize) {
+                self.0 &= !Self::__range_bits(start, end);
+            }
+
+            /// Flips every bit in `range`.
             ///
-            /// Panics if `index >=`[`Self::CAPACITY`].
-            #[must_use]
-            pub const fn #masked_i_to_size(mut self, index: usize) -> Self {
-                Self::#mask_i_to_size(&mut self, index);
-                self
+            /// Any part of `range` at or beyond [`Self::CAPACITY`] is ignored.
+            pub fn toggle_range<R: ::core::ops::RangeBounds<usize>>(&mut self, range: R) {
+                let (start, end) = Self::__range_bounds(&range);
+                self.toggle_range_const(start, end);
             }
 
-            #[doc = "Removes any bits with indices outside the range"]
-            #[doc = #index_to_size_with_period]
-            #[doc = "# Panics\n"]
-            #[doc = "Panics if `index >=`[`Self::CAPACITY`]."]
-            pub const fn #mask_i_to_size(&mut self, index: usize) {
-                self.clear_0_to_i(index);
+            /// The `const` alternative to [`toggle_range`](Self::toggle_range), for the range
+            /// `start..end`.
+            pub const fn toggle_range_const(&mut self, start: usize, end: usize) {
+                self.0 ^= Self::__range_bits(start, end);
             }
 
-            /// Creates a copy of this set without the bits with indices in the range `0..index`.
+            /// Creates a copy of this set that only has the elements within `range`.
             ///
-            /// # Panics
+            /// Unlike [`range`](Self::range), this never panics; a range that starts after it
+            /// ends simply leaves the set empty.
+            #[must_use]
+            pub fn masked<R: ::core::ops::RangeBounds<usize>>(self, range: R) -> Self {
+                let (start, end) = Self::__range_bounds(&range);
+                self.masked_const(start, end)
+            }
+
+            /// The `const` alternative to [`masked`](Self::masked), for the range `start..end`.
+            #[must_use]
+            pub const fn masked_const(self, start: usize, end: usize) -> Self {
+                Self(self.0 & Self::__range_bits(start, end))
+            }
+
+            /// Returns the number of elements within `range`.
+            #[must_use]
+            pub fn count_in<R: ::core::ops::RangeBounds<usize>>(self, range: R) -> usize {
+                let (start, end) = Self::__range_bounds(&range);
+                self.count_in_const(start, end)
+            }
+
+            /// The `const` alternative to [`count_in`](Self::count_in), for the range
+            /// `start..end`.
+            #[must_use]
+            pub const fn count_in_const(self, start: usize, end: usize) -> usize {
+                self.masked_const(start, end).len()
+            }
+
+            /// Returns `true` if every index in `range` is in the set.
             ///
-            /// Panics if `index >=`[`Self::CAPACITY`].
+            /// Any part of `range` at or beyond [`Self::CAPACITY`] is ignored, so this is `true`
+            /// for a range that lies entirely outside of the set's capacity.
             #[must_use]
-            pub const fn cleared_0_to_i(mut self, index: usize) -> Self {
-                self.clear_0_to_i(index);
-                self
+            pub fn contains_all_in<R: ::core::ops::RangeBounds<usize>>(self, range: R) -> bool {
+                let (start, end) = Self::__range_bounds(&range);
+                self.contains_all_in_const(start, end)
             }
 
-            #[doc = "Clears bits `0..index`, keeping bits"]
-            #[doc = #index_to_size]
-            #[doc = "in their original states.\n"]
-            #[doc = "# Panics\n"]
-            #[doc = "Panics if `index >=`[`Self::CAPACITY`]."]
-            pub const fn clear_0_to_i(&mut self, index: usize) {
-                self.0 &= !((Self::__ONE << index) - 1);
+            /// The `const` alternative to [`contains_all_in`](Self::contains_all_in), for the
+            /// range `start..end`.
+            #[must_use]
+            pub const fn contains_all_in_const(self, start: usize, end: usize) -> bool {
+                let bits = Self::__range_bits(start, end);
+                self.0 & bits == bits
             }
 
-            #[doc = "Creates a copy of this set without the bits with indices in the range"]
-            #[doc = #index_to_size_with_period]
-            #[doc = "# Panics\n"]
-            #[doc = "Panics if `index >=`[`Self::CAPACITY`]."]
+            /// Returns `true` if none of the indices in `range` are in the set.
             #[must_use]
-            pub const fn #cleared_i_to_size(mut self, index: usize) -> Self {
-                Self::#clear_i_to_size(&mut self, index);
-                self
+            pub fn is_range_empty<R: ::core::ops::RangeBounds<usize>>(self, range: R) -> bool {
+                let (start, end) = Self::__range_bounds(&range);
+                self.is_range_empty_const(start, end)
             }
 
-            #[doc = "Clears bits"]
-            #[doc = #index_to_size_with_comma]
-            #[doc = "keeping bits `0..index` in their original states.\n"]
-            #[doc = "# Panics\n"]
-            #[doc = "Panics if `index >=`[`Self::CAPACITY`]."]
-            pub const fn #clear_i_to_size(&mut self, index: usize) {
-                self.0 &= (Self::__ONE << index) - 1;
+            /// The `const` alternative to [`is_range_empty`](Self::is_range_empty), for the range
+            /// `start..end`.
+            #[must_use]
+            pub const fn is_range_empty_const(self, start: usize, end: usize) -> bool {
+                self.0 & Self::__range_bits(start, end) == 0
+            }
+
+            /// Converts `range` into the equivalent `start..end`, saturating at [`usize::MAX`].
+            fn __range_bounds<R: ::core::ops::RangeBounds<usize>>(range: &R) -> (usize, usize) {
+                let start = match range.start_bound() {
+                    ::core::ops::Bound::Included(&start) => start,
+                    ::core::ops::Bound::Excluded(&start) => start.saturating_add(1),
+                    ::core::ops::Bound::Unbounded => 0,
+                };
+                let end = match range.end_bound() {
+                    ::core::ops::Bound::Included(&end) => end.saturating_add(1),
+                    ::core::ops::Bound::Excluded(&end) => end,
+                    ::core::ops::Bound::Unbounded => usize::MAX,
+                };
+                (start, end)
+            }
+
+            /// Returns the bits with indices in `start..end` that lie within [`Self::CAPACITY`].
+            const fn __range_bits(start: usize, end: usize) -> #uint {
+                if start >= end || start >= Self::CAPACITY {
+                    return 0;
+                }
+                let bits = (Self::__MASK << start) & Self::__MASK;
+                if end >= Self::CAPACITY {
+                    bits
+                } else {
+                    bits & ((Self::__ONE << end) - 1)
+                }
             }
 
             /// Sets the bit at `index` to `1`.
@@ -1151,6 +1835,62 @@ pub fn generate_code(ident: Ident, uint: Type) -> TokenStream {
                 self.remove_quiet(index);
                 old_set.is_not(*self)
             }
+
+            /// Removes the smallest element from the set and returns it, or returns [`None`] if
+            /// the set is empty.
+            pub const fn pop_first(&mut self) -> ::core::option::Option<usize> {
+                let first = self.first();
+                // Clearing the lowest set bit is a no-op on an empty set.
+                self.0 &= self.0.wrapping_sub(1);
+                first
+            }
+
+            /// Removes the largest element from the set and returns it, or returns [`None`] if the
+            /// set is empty.
+            pub const fn pop_last(&mut self) -> ::core::option::Option<usize> {
+                let last = self.last();
+                if let ::core::option::Option::Some(index) = last {
+                    self.0 ^= Self::__ONE << index;
+                }
+                last
+            }
+
+            /// Splits the set in two at `index`. The elements less than `index` stay in `self`,
+            /// and the rest are returned.
+            #split_off_must_use
+            pub const fn split_off(&mut self, index: usize) -> Self {
+                if index >= Self::CAPACITY {
+                    return Self::new();
+                }
+                let high = Self(self.0 & (Self::__MASK << index));
+                self.0 ^= high.0;
+                high
+            }
+
+            /// Creates a copy of this set that only has the elements within `range`.
+            ///
+            /// # Panics
+            ///
+            /// Panics if `range` starts after it ends, or if it starts and ends at the same
+            /// excluded bound, just like
+            /// [`BTreeSet::range`](https://doc.rust-lang.org/std/collections/struct.BTreeSet.html#method.range).
+            #[must_use]
+            pub fn range<R: ::core::ops::RangeBounds<usize>>(self, range: R) -> Self {
+                use ::core::ops::Bound::{Excluded, Included};
+                match (range.start_bound(), range.end_bound()) {
+                    (Excluded(start), Excluded(end)) if start == end => {
+                        ::core::panic!("range start and end are equal and excluded")
+                    }
+                    (Included(start) | Excluded(start), Included(end) | Excluded(end))
+                        if start > end =>
+                    {
+                        ::core::panic!("range start is greater than range end")
+                    }
+                    _ => {}
+                }
+                let (start, end) = Self::__range_bounds(&range);
+                self.masked_const(start, end)
+            }
         }
     }
 }
@@ -1,5 +1,11 @@
+pub mod atomic;
+pub mod bitset;
+pub mod combinations;
 pub mod debug;
 pub mod indices;
 pub mod iter;
 pub mod main;
+pub mod permutation;
+pub mod runs;
+pub mod subsets;
 pub mod tests;
@@ -0,0 +1,268 @@
+use crate::extras::Names;
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{Ident, Type};
+
+pub fn generate_code(ident: &Ident, uint: &Type, names: &Names) -> TokenStream {
+    let Names {
+        permutation,
+        doc_cfg,
+        ..
+    } = names;
+    let bitset_link = format!("[`{ident}`].");
+    quote! {
+        #[doc = "A permutation of the indices of a"]
+        #[doc = #bitset_link]
+        ///
+        /// The permutation is compiled into a Beneš network of delta swaps when it is created, so
+        /// applying it to a set takes a logarithmic number of steps in the capacity rather than one
+        /// per element.
+        #doc_cfg
+        #[derive(Clone, Copy, Debug)]
+        pub struct #permutation {
+            /// Where each index is sent.
+            mapping: [u8; #ident::CAPACITY],
+            /// The mask of each stage of the network. Every set bit swaps the bit at that index
+            /// with the one [`Self::shift`] above it.
+            masks: [#uint; #permutation::STAGES],
+        }
+
+        impl #permutation {
+            /// The number of stages in the network, which narrow in from swapping halves of the
+            /// integer down to swapping neighbors and then back out.
+            const STAGES: usize = 2 * <#uint>::BITS.trailing_zeros() as usize - 1;
+
+            /// Returns how far apart the bits swapped by `stage` are.
+            const fn shift(stage: usize) -> u32 {
+                let depth = if stage < Self::STAGES - 1 - stage {
+                    stage
+                } else {
+                    Self::STAGES - 1 - stage
+                };
+                <#uint>::BITS >> (depth + 1)
+            }
+
+            /// Creates the permutation that sends each index `i` to `mapping[i]`, or returns
+            /// [`None`] if `mapping` sends two indices to the same place or any index out of
+            /// bounds.
+            #[must_use]
+            pub const fn new(mapping: [u8; #ident::CAPACITY]) -> ::core::option::Option<Self> {
+                let mut seen = [false; #ident::CAPACITY];
+                let mut index = 0;
+                while index < #ident::CAPACITY {
+                    let target = mapping[index] as usize;
+                    if target >= #ident::CAPACITY || seen[target] {
+                        return ::core::option::Option::None;
+                    }
+                    seen[target] = true;
+                    index += 1;
+                }
+                ::core::option::Option::Some(Self::compile(mapping))
+            }
+
+            /// Creates the permutation that leaves every index where it is.
+            #[must_use]
+            pub const fn identity() -> Self {
+                let mut mapping = [0; #ident::CAPACITY];
+                let mut index = 0;
+                while index < #ident::CAPACITY {
+                    mapping[index] = index as u8;
+                    index += 1;
+                }
+                Self {
+                    mapping,
+                    masks: [0; Self::STAGES],
+                }
+            }
+
+            /// Returns where the permutation sends each index.
+            #[must_use]
+            pub const fn mapping(&self) -> [u8; #ident::CAPACITY] {
+                self.mapping
+            }
+
+            /// Builds the network for a mapping that is known to be a permutation.
+            ///
+            /// Each stage of the recursion splits every block of `2 * half` bits in two with a
+            /// column of swaps on the way in and another on the way out, choosing them so that
+            /// each half can finish routing its bits on its own. The indices past the capacity are
+            /// left where they are.
+            const fn compile(mapping: [u8; #ident::CAPACITY]) -> Self {
+                const BITS: usize = <#uint>::BITS as usize;
+                let mut targets = [0u8; BITS];
+                let mut index = 0;
+                while index < BITS {
+                    targets[index] = if index < #ident::CAPACITY {
+                        mapping[index]
+                    } else {
+                        index as u8
+                    };
+                    index += 1;
+                }
+
+                let mut masks: [#uint; Self::STAGES] = [0; Self::STAGES];
+                let mut stage = 0;
+                let mut half = BITS / 2;
+                while half > 0 {
+                    let mut sources = [0u8; BITS];
+                    let mut index = 0;
+                    while index < BITS {
+                        sources[targets[index] as usize] = index as u8;
+                        index += 1;
+                    }
+
+                    // Split each pair of bits `half` apart between the two halves, following the
+                    // chain of constraints until it loops: the bit bound for the other member of
+                    // an output pair has to come through the other half.
+                    let mut assigned = [false; BITS];
+                    let mut upper = [false; BITS];
+                    let mut start = 0;
+                    while start < BITS {
+                        let mut source = start;
+                        while start & half == 0 && !assigned[source] {
+                            let partner = source ^ half;
+                            assigned[source] = true;
+                            assigned[partner] = true;
+                            upper[partner] = true;
+                            source = sources[targets[partner] as usize ^ half] as usize;
+                        }
+                        start += 1;
+                    }
+
+                    let mut swaps_in: #uint = 0;
+                    let mut swaps_out: #uint = 0;
+                    let mut next_targets = [0u8; BITS];
+                    let mut index = 0;
+                    while index < BITS {
+                        let target = targets[index] as usize;
+                        if index & half == 0 && upper[index] {
+                            swaps_in |= 1 << index;
+                        }
+                        if target & half == 0 && upper[sources[target] as usize] {
+                            swaps_out |= 1 << target;
+                        }
+                        let (position, inner_target) = if upper[index] {
+                            (index | half, target | half)
+                        } else {
+                            (index & !half, target & !half)
+                        };
+                        next_targets[position] = inner_target as u8;
+                        index += 1;
+                    }
+                    targets = next_targets;
+
+                    if half == 1 {
+                        // Both columns swap neighbors, so they collapse into a single stage.
+                        masks[stage] = swaps_in ^ swaps_out;
+                    } else {
+                        masks[stage] = swaps_in;
+                        masks[Self::STAGES - 1 - stage] = swaps_out;
+                    }
+                    stage += 1;
+                    half /= 2;
+                }
+                Self { mapping, masks }
+            }
+
+            /// Applies the permutation to `set`, sending each element `i` to `mapping[i]`.
+            #[must_use]
+            pub const fn apply(&self, set: #ident) -> #ident {
+                let mut bits = set.bits();
+                let mut stage = 0;
+                while stage < Self::STAGES {
+                    let shift = Self::shift(stage);
+                    let swapped = (bits >> shift ^ bits) & self.masks[stage];
+                    bits ^= swapped ^ swapped << shift;
+                    stage += 1;
+                }
+                #ident::from_bits(bits)
+            }
+
+            /// Applies the permutation to `set` one element at a time, without the network.
+            ///
+            /// This gives the same result as [`apply`](Self::apply), only slower, which makes it
+            /// useful for checking it.
+            #[must_use]
+            pub const fn apply_naive(&self, set: #ident) -> #ident {
+                let mut permuted = #ident::new();
+                let mut index = 0;
+                while index < #ident::CAPACITY {
+                    if set.contains(index) {
+                        permuted.insert_quiet(self.mapping[index] as usize);
+                    }
+                    index += 1;
+                }
+                permuted
+            }
+
+            /// Creates the permutation that applies this one and then `next`.
+            #[must_use]
+            pub const fn then(&self, next: &Self) -> Self {
+                let mut mapping = [0; #ident::CAPACITY];
+                let mut index = 0;
+                while index < #ident::CAPACITY {
+                    mapping[index] = next.mapping[self.mapping[index] as usize];
+                    index += 1;
+                }
+                Self::compile(mapping)
+            }
+
+            /// Creates the permutation that undoes this one.
+            #[must_use]
+            pub const fn inverse(&self) -> Self {
+                let mut mapping = [0; #ident::CAPACITY];
+                let mut index = 0;
+                while index < #ident::CAPACITY {
+                    mapping[self.mapping[index] as usize] = index as u8;
+                    index += 1;
+                }
+                // A network run backwards undoes itself, since each stage is its own inverse.
+                let mut masks = self.masks;
+                let mut stage = 0;
+                while stage < Self::STAGES {
+                    masks[stage] = self.masks[Self::STAGES - 1 - stage];
+                    stage += 1;
+                }
+                Self { mapping, masks }
+            }
+        }
+
+        // A permutation can be routed through more than one network, so only the mapping decides
+        // whether two of them are equal.
+        impl ::core::cmp::PartialEq for #permutation {
+            fn eq(&self, other: &Self) -> bool {
+                self.mapping == other.mapping
+            }
+        }
+
+        impl ::core::cmp::Eq for #permutation {}
+
+        impl ::core::hash::Hash for #permutation {
+            fn hash<H: ::core::hash::Hasher>(&self, state: &mut H) {
+                ::core::hash::Hash::hash(&self.mapping, state);
+            }
+        }
+
+        impl ::core::default::Default for #permutation {
+            fn default() -> Self {
+                Self::identity()
+            }
+        }
+
+        impl #ident {
+            /// Creates a copy of this set with every element `i` sent to where `permutation`
+            /// sends it.
+            #[must_use]
+            #doc_cfg
+            pub const fn permuted(self, permutation: &#permutation) -> Self {
+                permutation.apply(self)
+            }
+
+            /// Sends every element `i` of this set to where `permutation` sends it.
+            #doc_cfg
+            pub const fn permute(&mut self, permutation: &#permutation) {
+                *self = permutation.apply(*self);
+            }
+        }
+    }
+}
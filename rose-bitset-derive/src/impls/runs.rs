@@ -0,0 +1,256 @@
+use crate::extras::Names;
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{Ident, Type};
+
+pub fn generate_code(ident: &Ident, uint: &Type, names: &Names) -> TokenStream {
+    let Names {
+        krate,
+        runs: iterator,
+        doc_cfg,
+        ..
+    } = names;
+    let bitset_link = format!("[`{ident}`].");
+    let ascending = generate_directional_impls(iterator, quote! { #krate::Ascending }, false);
+    let descending = generate_directional_impls(iterator, quote! { #krate::Descending }, true);
+    quote! {
+        #[doc = "An iterator over the maximal runs of consecutive elements in a"]
+        #[doc = #bitset_link]
+        ///
+        /// Each run is yielded as the range of indices it covers, so `{0, 1, 2, 3, 7}` yields
+        /// `0..=3` and then `7..=7`.
+        ///
+        /// A run isn't a single index for a `Direction` to give a key to, so `D` can only be
+        /// `Ascending` or `Descending`.
+        #doc_cfg
+        pub struct #iterator<D = #krate::Ascending> {
+            bits: #uint,
+            _direction: ::core::marker::PhantomData<D>,
+        }
+
+        #ascending
+
+        #descending
+
+        impl<D> #iterator<D> {
+            /// Creates an iterator over the runs of elements in `set`.
+            pub const fn new(set: #ident) -> Self {
+                Self {
+                    bits: set.bits(),
+                    _direction: ::core::marker::PhantomData,
+                }
+            }
+
+            fn pop_lowest(
+                &mut self,
+            ) -> ::core::option::Option<::core::ops::RangeInclusive<usize>> {
+                if self.bits == 0 {
+                    return ::core::option::Option::None;
+                }
+                let start = self.bits.trailing_zeros();
+                let len = (!(self.bits >> start)).trailing_zeros();
+                // Adding the lowest bit carries through the whole run, clearing it.
+                self.bits &= self.bits.wrapping_add(1 << start);
+                let start = start as usize;
+                ::core::option::Option::Some(start..=start + len as usize - 1)
+            }
+
+            fn pop_highest(
+                &mut self,
+            ) -> ::core::option::Option<::core::ops::RangeInclusive<usize>> {
+                if self.bits == 0 {
+                    return ::core::option::Option::None;
+                }
+                let above = self.bits.leading_zeros();
+                let len = (!(self.bits << above)).leading_zeros();
+                let end = (<#uint>::BITS - 1 - above) as usize;
+                let start = end + 1 - len as usize;
+                self.bits &= match (<#uint>::MAX).checked_shr(<#uint>::BITS - start as u32) {
+                    ::core::option::Option::Some(below) => below,
+                    ::core::option::Option::None => 0,
+                };
+                ::core::option::Option::Some(start..=end)
+            }
+        }
+
+        impl<D> ::core::clone::Clone for #iterator<D> {
+            fn clone(&self) -> Self {
+                Self {
+                    bits: self.bits,
+                    _direction: ::core::marker::PhantomData,
+                }
+            }
+        }
+
+        impl<D> ::core::fmt::Debug for #iterator<D>
+        where
+            Self: ::core::iter::Iterator<Item = ::core::ops::RangeInclusive<usize>>,
+        {
+            fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+                write!(f, "{}(", ::core::stringify!(#iterator))?;
+                f.debug_list().entries(self.clone()).finish()?;
+                write!(f, ")")
+            }
+        }
+
+        impl #ident {
+            /// Creates an iterator over the maximal runs of consecutive elements in the set.
+            #[must_use]
+            #doc_cfg
+            pub const fn iter_runs<D>(&self) -> #iterator<D>
+            where
+                #iterator<D>:
+                    ::core::iter::Iterator<Item = ::core::ops::RangeInclusive<usize>>,
+            {
+                #iterator::new(*self)
+            }
+
+            /// Creates an iterator over the maximal runs of consecutive indices that are not in
+            /// the set.
+            #[must_use]
+            #doc_cfg
+            pub const fn iter_gaps<D>(&self) -> #iterator<D>
+            where
+                #iterator<D>:
+                    ::core::iter::Iterator<Item = ::core::ops::RangeInclusive<usize>>,
+            {
+                #iterator::new(self.complement())
+            }
+
+            /// Returns the number of maximal runs of consecutive elements in the set.
+            #[must_use]
+            #doc_cfg
+            pub const fn run_count(self) -> usize {
+                // Every run has exactly one element without a neighbor below it.
+                (self.0 & !(self.0 << 1)).count_ones() as usize
+            }
+
+            /// Returns the longest run of consecutive elements in the set, or [`None`] if it is
+            /// empty. Ties go to the lowest run.
+            #[must_use]
+            #doc_cfg
+            pub const fn longest_run(
+                self,
+            ) -> ::core::option::Option<::core::ops::RangeInclusive<usize>> {
+                if self.0 == 0 {
+                    return ::core::option::Option::None;
+                }
+                // The bits left mark the ends of runs of at least `len` elements. Doubling `len`
+                // until nothing would be left brackets the longest run between `len` and
+                // `2 * len`, and a binary search over that finishes it off.
+                let mut ends = self.0;
+                let mut len = 1;
+                while len < Self::CAPACITY {
+                    let longer = ends & ends << len;
+                    if longer == 0 {
+                        break;
+                    }
+                    ends = longer;
+                    len *= 2;
+                }
+                let mut step = len / 2;
+                while step > 0 {
+                    let longer = ends & ends << step;
+                    if longer != 0 {
+                        ends = longer;
+                        len += step;
+                    }
+                    step /= 2;
+                }
+                let end = ends.trailing_zeros() as usize;
+                ::core::option::Option::Some(end + 1 - len..=end)
+            }
+
+            /// Returns the longest run of consecutive indices that are not in the set, or [`None`]
+            /// if it is full. Ties go to the lowest run.
+            #[must_use]
+            #doc_cfg
+            pub const fn longest_gap(
+                self,
+            ) -> ::core::option::Option<::core::ops::RangeInclusive<usize>> {
+                self.complement().longest_run()
+            }
+
+            /// Returns the smallest index that starts `len` consecutive elements of the set, or
+            /// [`None`] if there is no such run.
+            #[must_use]
+            #doc_cfg
+            pub const fn find_run(self, len: usize) -> ::core::option::Option<usize> {
+                if len == 0 {
+                    return ::core::option::Option::Some(0);
+                }
+                if len > Self::CAPACITY {
+                    return ::core::option::Option::None;
+                }
+                // The bits left mark the ends of runs of at least `covered` elements. Each step
+                // at most doubles that, so it takes a logarithmic number of steps to reach `len`.
+                let mut ends = self.0;
+                let mut covered = 1;
+                while covered < len {
+                    let step = if covered < len - covered {
+                        covered
+                    } else {
+                        len - covered
+                    };
+                    ends &= ends << step;
+                    covered += step;
+                }
+                if ends == 0 {
+                    ::core::option::Option::None
+                } else {
+                    ::core::option::Option::Some(ends.trailing_zeros() as usize + 1 - len)
+                }
+            }
+
+            /// Returns the smallest index that starts `len` consecutive indices that are not in
+            /// the set, or [`None`] if there is no such gap.
+            #[must_use]
+            #doc_cfg
+            pub const fn find_gap(self, len: usize) -> ::core::option::Option<usize> {
+                self.complement().find_run(len)
+            }
+        }
+    }
+}
+
+/// Generates the iterator traits for one direction, which differ only in which end counts as the
+/// front.
+fn generate_directional_impls(
+    iterator: &Ident,
+    direction: TokenStream,
+    descending: bool,
+) -> TokenStream {
+    let (pop_front, pop_back) = if descending {
+        (quote! { pop_highest }, quote! { pop_lowest })
+    } else {
+        (quote! { pop_lowest }, quote! { pop_highest })
+    };
+    quote! {
+        impl ::core::iter::Iterator for #iterator<#direction> {
+            type Item = ::core::ops::RangeInclusive<usize>;
+
+            fn next(&mut self) -> ::core::option::Option<Self::Item> {
+                self.#pop_front()
+            }
+
+            fn size_hint(&self) -> (usize, ::core::option::Option<usize>) {
+                let len = (self.bits & !(self.bits << 1)).count_ones() as usize;
+                (len, ::core::option::Option::Some(len))
+            }
+
+            fn last(mut self) -> ::core::option::Option<Self::Item> {
+                self.#pop_back()
+            }
+        }
+
+        impl ::core::iter::DoubleEndedIterator for #iterator<#direction> {
+            fn next_back(&mut self) -> ::core::option::Option<Self::Item> {
+                self.#pop_back()
+            }
+        }
+
+        impl ::core::iter::ExactSizeIterator for #iterator<#direction> {}
+
+        impl ::core::iter::FusedIterator for #iterator<#direction> {}
+    }
+}
@@ -0,0 +1,133 @@
+use crate::{extras::Names, type_utils::exact_size_cfg};
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{Ident, Type};
+
+pub fn generate_code(ident: &Ident, uint: &Type, names: &Names) -> TokenStream {
+    let Names {
+        subsets: iterator,
+        doc_cfg,
+        ..
+    } = names;
+    let bitset_link = format!("[`{ident}`].");
+    let exact_size_impl = exact_size_cfg(uint).map(|cfg| {
+        quote! {
+            #cfg
+            impl ::core::iter::ExactSizeIterator for #iterator {}
+        }
+    });
+    quote! {
+        #[doc = "An iterator over the subsets of a"]
+        #[doc = #bitset_link]
+        ///
+        /// The subsets are yielded in ascending order of their bits, starting with the empty set
+        /// and ending with the set itself.
+        #doc_cfg
+        #[derive(Clone, Debug)]
+        pub struct #iterator {
+            mask: #uint,
+            front: #uint,
+            back: #uint,
+            /// One less than the number of subsets left, or `None` once there are none.
+            left: ::core::option::Option<u128>,
+        }
+
+        impl #iterator {
+            /// Creates an iterator over the subsets of `set`.
+            pub const fn new(set: #ident) -> Self {
+                let len = set.len() as u32;
+                Self {
+                    mask: set.bits(),
+                    front: 0,
+                    back: set.bits(),
+                    // `2^len - 1` would overflow for `len == 128` if it were computed directly.
+                    left: ::core::option::Option::Some(match u128::MAX.checked_shr(128 - len) {
+                        ::core::option::Option::Some(left) => left,
+                        ::core::option::Option::None => 0,
+                    }),
+                }
+            }
+
+            /// Moves on to the next subset, returning the current one.
+            const fn take(&mut self, subset: #uint) -> ::core::option::Option<#ident> {
+                self.left = match self.left {
+                    ::core::option::Option::Some(left) => left.checked_sub(1),
+                    ::core::option::Option::None => return ::core::option::Option::None,
+                };
+                ::core::option::Option::Some(#ident::from_bits(subset))
+            }
+        }
+
+        impl ::core::iter::Iterator for #iterator {
+            type Item = #ident;
+
+            fn next(&mut self) -> ::core::option::Option<Self::Item> {
+                let subset = self.front;
+                // Subtracting the mask carries through the gaps between its bits, so this counts
+                // up through the subsets in order.
+                self.front = self.front.wrapping_sub(self.mask) & self.mask;
+                self.take(subset)
+            }
+
+            fn size_hint(&self) -> (usize, ::core::option::Option<usize>) {
+                match self.left {
+                    ::core::option::Option::Some(left) => {
+                        match usize::try_from(left).ok().and_then(|left| left.checked_add(1)) {
+                            ::core::option::Option::Some(len) => {
+                                (len, ::core::option::Option::Some(len))
+                            }
+                            ::core::option::Option::None => {
+                                (usize::MAX, ::core::option::Option::None)
+                            }
+                        }
+                    }
+                    ::core::option::Option::None => (0, ::core::option::Option::Some(0)),
+                }
+            }
+        }
+
+        impl ::core::iter::DoubleEndedIterator for #iterator {
+            fn next_back(&mut self) -> ::core::option::Option<Self::Item> {
+                let subset = self.back;
+                self.back = self.back.wrapping_sub(1) & self.mask;
+                self.take(subset)
+            }
+        }
+
+        #exact_size_impl
+
+        impl ::core::iter::FusedIterator for #iterator {}
+
+        impl #ident {
+            /// Creates an iterator over every subset of the set, including the empty set and the
+            /// set itself.
+            #[must_use]
+            #doc_cfg
+            pub const fn subsets(self) -> #iterator {
+                #iterator::new(self)
+            }
+
+            /// Creates an iterator over every set with this capacity.
+            #[must_use]
+            #doc_cfg
+            pub const fn power_set() -> #iterator {
+                #iterator::new(Self::all())
+            }
+
+            /// Creates an iterator over every subset of the set except for the empty set and the
+            /// set itself.
+            #[must_use]
+            #doc_cfg
+            pub const fn non_empty_proper_subsets(self) -> #iterator {
+                let mut subsets = #iterator::new(self);
+                subsets.left = match subsets.left {
+                    ::core::option::Option::Some(left) => left.checked_sub(2),
+                    ::core::option::Option::None => ::core::option::Option::None,
+                };
+                subsets.front = subsets.mask & subsets.mask.wrapping_neg();
+                subsets.back = subsets.mask.wrapping_sub(1) & subsets.mask;
+                subsets
+            }
+        }
+    }
+}
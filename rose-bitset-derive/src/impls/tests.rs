@@ -1,16 +1,15 @@
+use crate::extras::{Extras, Names};
 use proc_macro2::TokenStream;
-use quote::{format_ident, quote};
-use syn::Ident;
-
-pub fn generate_code(
-    ident: &Ident,
-    suffix: &str,
-    debug: bool,
-    indices: bool,
-    iter: bool,
-) -> TokenStream {
-    let mod_name = format_ident!("bitset_{suffix}_tests");
-    let extra_tests = generate_extra_tests(ident, debug, indices, iter);
+use quote::quote;
+use syn::{Ident, Path};
+
+pub fn generate_code(ident: &Ident, names: &Names, extras: &Extras) -> TokenStream {
+    let mod_name = &names.tests;
+    let extra_tests = generate_extra_tests(ident, names, extras);
+    let expected_capacity = match extras.capacity() {
+        Some(capacity) => quote! { #capacity },
+        None => quote! { ::core::mem::size_of::<#ident>() * 8 },
+    };
     quote! {
         #[cfg(test)]
         mod #mod_name {
@@ -50,7 +49,7 @@ pub fn generate_code(
 
             #[test]
             fn capacity() {
-                assert_eq!(#ident::CAPACITY, ::core::mem::size_of::<#ident>() * 8);
+                assert_eq!(#ident::CAPACITY, #expected_capacity);
             }
 
             // fn unit(usize) -> Self
@@ -1062,6 +1061,429 @@ pub fn generate_code(
                 assert!(!SET_D.is_strict_superset(SET_D));
             }
 
+            // fn first(Self) -> Option<usize>
+            // fn last(Self) -> Option<usize>
+
+            #[test]
+            fn first_and_last() {
+                for set in [SET_A, SET_B, SET_C, SET_D, #ident::all()] {
+                    assert_eq!(set.first(), (0..#ident::CAPACITY).find(|&i| set.contains(i)));
+                    assert_eq!(set.last(), (0..#ident::CAPACITY).rfind(|&i| set.contains(i)));
+                }
+                assert_eq!(#ident::new().first(), None);
+                assert_eq!(#ident::new().last(), None);
+            }
+
+            // fn next_after(Self, usize) -> Option<usize>
+            // fn prev_before(Self, usize) -> Option<usize>
+
+            #[test]
+            fn next_after_and_prev_before() {
+                for set in [SET_A, SET_B, SET_C, SET_D, #ident::all(), #ident::new()] {
+                    for index in 0..#ident::CAPACITY {
+                        let next = (index + 1..#ident::CAPACITY).find(|&i| set.contains(i));
+                        let prev = (0..index).rfind(|&i| set.contains(i));
+                        assert_eq!(set.next_after(index), next);
+                        assert_eq!(set.prev_before(index), prev);
+                    }
+                    assert_eq!(set.next_after(#ident::CAPACITY), None);
+                    assert_eq!(set.next_after(usize::MAX), None);
+                    assert_eq!(set.prev_before(#ident::CAPACITY), set.last());
+                    assert_eq!(set.prev_before(usize::MAX), set.last());
+                }
+            }
+
+            // fn pop_first(&mut Self) -> Option<usize>
+            // fn pop_last(&mut Self) -> Option<usize>
+
+            #[test]
+            fn pop_first_drains_in_ascending_order() {
+                let mut set = SET_A;
+                let mut expected = SET_A;
+                while let Some(index) = set.pop_first() {
+                    assert_eq!(Some(index), expected.first());
+                    expected.remove_quiet(index);
+                    assert!(set == expected);
+                }
+                assert!(set.is_empty());
+            }
+
+            #[test]
+            fn pop_last_drains_in_descending_order() {
+                let mut set = SET_B;
+                let mut expected = SET_B;
+                while let Some(index) = set.pop_last() {
+                    assert_eq!(Some(index), expected.last());
+                    expected.remove_quiet(index);
+                    assert!(set == expected);
+                }
+                assert!(set.is_empty());
+            }
+
+            // fn split_off(&mut Self, usize) -> Self
+
+            #[test]
+            fn split_off_partitions_the_set() {
+                for index in [0, 1, #ident::CAPACITY / 2, #ident::CAPACITY - 1, #ident::CAPACITY] {
+                    let mut low = SET_C;
+                    let high = low.split_off(index);
+                    assert!(low.union(high) == SET_C);
+                    assert!(low.is_disjoint(high));
+                    assert!(low.last().is_none_or(|last| last < index));
+                    assert!(high.first().is_none_or(|first| first >= index));
+                }
+            }
+
+            // fn range(Self, impl RangeBounds<usize>) -> Self
+
+            #[test]
+            fn range_keeps_elements_within_bounds() {
+                let capacity = #ident::CAPACITY;
+                let half = capacity / 2;
+                assert!(SET_D.range(..) == SET_D);
+                assert!(SET_D.range(0..capacity) == SET_D);
+                assert!(SET_D.range(..half).union(SET_D.range(half..)) == SET_D);
+                assert!(SET_D.range(half..half).is_empty());
+                assert!(SET_D.range(capacity..).is_empty());
+                assert!(#ident::all().range(1..=half).len() == half);
+                assert!(
+                    #ident::all().range((
+                        ::core::ops::Bound::Excluded(0),
+                        ::core::ops::Bound::Unbounded,
+                    )) == #ident::all().difference(#ident::unit(0))
+                );
+            }
+
+            #[test]
+            #[allow(clippy::reversed_empty_ranges)]
+            fn range_panics_when_backwards() {
+                let message = |result: ::std::thread::Result<#ident>| {
+                    result.err().and_then(|payload| payload.downcast_ref::<&str>().copied())
+                };
+                let backwards = ::std::panic::catch_unwind(|| SET_A.range(1..0));
+                assert_eq!(message(backwards), Some("range start is greater than range end"));
+                let backwards = ::std::panic::catch_unwind(|| SET_A.range(5..=4));
+                assert_eq!(message(backwards), Some("range start is greater than range end"));
+                let excluded = ::std::panic::catch_unwind(|| {
+                    SET_A.range((::core::ops::Bound::Excluded(3), ::core::ops::Bound::Excluded(3)))
+                });
+                assert_eq!(message(excluded), Some("range start and end are equal and excluded"));
+                assert!(SET_A.range(3..3).is_empty());
+                assert!(
+                    SET_A
+                        .range((::core::ops::Bound::Excluded(3), ::core::ops::Bound::Included(3)))
+                        .is_empty()
+                );
+            }
+
+            // fn masked_0_to_i(Self, usize) -> Self
+            // fn cleared_0_to_i(Self, usize) -> Self
+
+            #[test]
+            fn masks_at_every_index() {
+                for index in 0..=#ident::CAPACITY {
+                    let below = SET_A.masked_0_to_i(index);
+                    let above = SET_A.cleared_0_to_i(index);
+                    assert!(below.union(above) == SET_A);
+                    assert!(below.is_disjoint(above));
+                    assert_eq!(below.len(), SET_A.rank(index));
+                }
+                assert!(SET_A.masked_0_to_i(usize::MAX) == SET_A);
+                assert!(SET_A.cleared_0_to_i(usize::MAX).is_empty());
+            }
+
+            // fn from_range(impl RangeBounds<usize>) -> Self
+
+            #[test]
+            fn from_range_edges() {
+                let capacity = #ident::CAPACITY;
+                assert!(#ident::from_range(..) == #ident::all());
+                assert!(#ident::from_range(0..capacity) == #ident::all());
+                assert!(#ident::from_range(0..=capacity) == #ident::all());
+                assert!(#ident::from_range(capacity - 1..) == #ident::unit(capacity - 1));
+                assert!(#ident::from_range(capacity..).is_empty());
+                assert!(#ident::from_range(1..1).is_empty());
+                assert!(#ident::from_range_const(2, 1).is_empty());
+                assert!(#ident::from_range(..=usize::MAX) == #ident::all());
+                for start in 0..capacity {
+                    for end in start..=capacity {
+                        let set = #ident::from_range(start..end);
+                        assert_eq!(set.len(), end - start);
+                        assert_eq!(set.first(), (start < end).then_some(start));
+                    }
+                }
+            }
+
+            // fn insert_range(&mut Self, impl RangeBounds<usize>)
+            // fn remove_range(&mut Self, impl RangeBounds<usize>)
+            // fn toggle_range(&mut Self, impl RangeBounds<usize>)
+
+            #[test]
+            fn range_mutations() {
+                let half = #ident::CAPACITY / 2;
+                let range = #ident::from_range(half..);
+
+                let mut set = SET_B;
+                set.insert_range(half..);
+                assert!(set == SET_B.union(range));
+
+                let mut set = SET_B;
+                set.remove_range(half..);
+                assert!(set == SET_B.difference(range));
+
+                let mut set = SET_B;
+                set.toggle_range(half..);
+                assert!(set == SET_B.symmetric_difference(range));
+
+                let mut set = SET_B;
+                set.toggle_range(..);
+                assert!(set == SET_B.complement());
+            }
+
+            // fn masked(Self, impl RangeBounds<usize>) -> Self
+            // fn count_in(Self, impl RangeBounds<usize>) -> usize
+            // fn contains_all_in(Self, impl RangeBounds<usize>) -> bool
+            // fn is_range_empty(Self, impl RangeBounds<usize>) -> bool
+
+            #[test]
+            fn range_queries() {
+                let capacity = #ident::CAPACITY;
+                for set in [SET_A, SET_B, SET_C, SET_D] {
+                    for start in 0..capacity {
+                        for end in [start, start + 1, capacity, capacity + 1] {
+                            let count = (start..end.min(capacity))
+                                .filter(|&i| set.contains(i))
+                                .count();
+                            assert!(set.masked(start..end) == set.masked_const(start, end));
+                            assert_eq!(set.count_in(start..end), count);
+                            assert_eq!(
+                                set.contains_all_in(start..end),
+                                count == end.min(capacity) - start,
+                            );
+                            assert_eq!(set.is_range_empty(start..end), count == 0);
+                        }
+                    }
+                    assert!(set.masked(..) == set);
+                    assert!(set.masked(capacity..).is_empty());
+                    assert!(set.contains_all_in(capacity..));
+                    assert!(set.is_range_empty(capacity..));
+                }
+                assert!(#ident::all().contains_all_in(..));
+                assert!(#ident::new().is_range_empty(..));
+                #[allow(clippy::reversed_empty_ranges)]
+                let backwards = 2..1;
+                assert!(SET_A.masked(backwards).is_empty());
+            }
+
+            // fn rank(Self, usize) -> usize
+
+            #[test]
+            fn rank_counts_lower_elements() {
+                for set in [SET_A, SET_B, SET_C, SET_D, #ident::all()] {
+                    let mut expected = 0;
+                    for index in 0..#ident::CAPACITY {
+                        assert_eq!(set.rank(index), expected);
+                        expected += set.contains(index) as usize;
+                    }
+                    assert_eq!(set.rank(#ident::CAPACITY), set.len());
+                    assert_eq!(set.rank(usize::MAX), set.len());
+                }
+            }
+
+            #[test]
+            fn rank_of_new_set() {
+                assert_eq!(#ident::new().rank(0), 0);
+                assert_eq!(#ident::new().rank(#ident::CAPACITY - 1), 0);
+            }
+
+            // fn select(Self, usize) -> Option<usize>
+            // fn select_from_top(Self, usize) -> Option<usize>
+            // const fn select_const(Self, usize) -> Option<usize>
+            // const fn select_from_top_const(Self, usize) -> Option<usize>
+
+            #[test]
+            fn select_inverts_rank() {
+                for set in [SET_A, SET_B, SET_C, SET_D, #ident::all()] {
+                    let len = set.len();
+                    for index in 0..#ident::CAPACITY {
+                        if set.contains(index) {
+                            let rank = set.rank(index);
+                            assert_eq!(set.select(rank), Some(index));
+                            assert_eq!(set.select_const(rank), Some(index));
+                            assert_eq!(set.select_from_top(len - 1 - rank), Some(index));
+                            assert_eq!(set.select_from_top_const(len - 1 - rank), Some(index));
+                        }
+                    }
+                    assert_eq!(set.select(len), None);
+                    assert_eq!(set.select_const(len), None);
+                    assert_eq!(set.select_from_top(len), None);
+                    assert_eq!(set.select_from_top_const(len), None);
+                }
+            }
+
+            #[test]
+            fn select_in_const_context() {
+                const LAST: ::core::option::Option<usize> = #ident::all().select_from_top_const(0);
+                const FIRST: ::core::option::Option<usize> = #ident::all().select_const(0);
+                assert_eq!(LAST, Some(#ident::CAPACITY - 1));
+                assert_eq!(FIRST, Some(0));
+            }
+
+            #[test]
+            fn select_ends() {
+                assert_eq!(SET_A.select(0), Some(SET_A.min_index()));
+                assert_eq!(SET_A.select_from_top(0), SET_A.max_index_checked());
+                assert_eq!(#ident::all().select(#ident::CAPACITY - 1), Some(#ident::CAPACITY - 1));
+                assert_eq!(#ident::new().select(0), None);
+                assert_eq!(#ident::new().select_from_top(0), None);
+            }
+
+            // impl FromIterator<usize> for Self
+            // impl FromIterator<bool> for Self
+            // impl Extend<usize> for Self
+            // impl Extend<&usize> for Self
+
+            #[test]
+            fn collect_indices() {
+                let set: #ident = [0, 3, 5, #ident::CAPACITY - 1, #ident::CAPACITY, usize::MAX]
+                    .into_iter()
+                    .collect();
+                let mut expected = #ident::new();
+                for index in [0, 3, 5, #ident::CAPACITY - 1] {
+                    expected.insert_quiet(index);
+                }
+                assert!(set == expected);
+                assert!(::core::iter::empty::<usize>().collect::<#ident>() == #ident::new());
+            }
+
+            #[test]
+            fn collect_bits() {
+                let bits = (0..#ident::CAPACITY + 3).map(|index| SET_A.contains(index));
+                let set: #ident = bits.collect();
+                assert!(set == SET_A);
+                let set: #ident = [false, true, true].into_iter().collect();
+                assert!(set.bits() == 0b110);
+            }
+
+            #[test]
+            fn extend_with_indices() {
+                let mut set = SET_A;
+                set.extend([1, 2, #ident::CAPACITY]);
+                set.extend(&[4, 6]);
+                assert!(set == SET_A.union(#ident::from_bits(0b101_0110)));
+            }
+
+            // fn union_all(impl IntoIterator<Item = Self>) -> Self
+            // fn intersection_all(impl IntoIterator<Item = Self>) -> Self
+
+            #[test]
+            fn union_and_intersection_of_many() {
+                let sets = [SET_A, SET_B, SET_C, SET_D];
+                assert!(#ident::union_all(sets) == SET_A | SET_B | SET_C | SET_D);
+                assert!(#ident::intersection_all(sets) == SET_A & SET_B & SET_C & SET_D);
+                assert!(#ident::union_all([]) == #ident::new());
+                assert!(#ident::intersection_all([]) == #ident::all());
+            }
+
+            // fn rotated_up_by(Self, u32) -> Self
+            // fn rotated_down_by(Self, u32) -> Self
+            // fn rotated_up_by_within(Self, u32, usize) -> Self
+            // fn rotated_down_by_within(Self, u32, usize) -> Self
+
+            #[test]
+            fn rotations_wrap_around() {
+                let capacity = #ident::CAPACITY;
+                for set in [SET_A, SET_B, SET_C, SET_D] {
+                    for shift in [0, 1, 3, capacity as u32 - 1, capacity as u32, 1000] {
+                        let up = set.rotated_up_by(shift);
+                        let down = set.rotated_down_by(shift);
+                        assert_eq!(up.len(), set.len());
+                        assert!(up.rotated_down_by(shift) == set);
+                        for index in 0..capacity {
+                            let moved = (index + shift as usize) % capacity;
+                            assert_eq!(up.contains(moved), set.contains(index));
+                            assert_eq!(down.contains(index), set.contains(moved));
+                        }
+                    }
+                }
+            }
+
+            #[test]
+            fn rotations_within_leave_the_rest_alone() {
+                for modulus in [1, 5, #ident::CAPACITY - 1] {
+                    for set in [SET_A, SET_B, SET_C, SET_D] {
+                        let up = set.rotated_up_by_within(2, modulus);
+                        assert!(up.rotated_down_by_within(2, modulus) == set);
+                        assert!(up.cleared_0_to_i(modulus) == set.cleared_0_to_i(modulus));
+                        for index in 0..modulus {
+                            assert_eq!(up.contains((index + 2) % modulus), set.contains(index));
+                        }
+                    }
+                }
+                assert!(SET_A.rotated_up_by_within(3, 0) == SET_A);
+                assert!(SET_A.rotated_up_by_within(3, usize::MAX) == SET_A.rotated_up_by(3));
+            }
+
+            // fn reversed(Self) -> Self
+            // fn reflected_within(Self, usize) -> Self
+
+            #[test]
+            fn reversed_mirrors_indices() {
+                for set in [SET_A, SET_B, SET_C, SET_D, #ident::all(), #ident::new()] {
+                    let reversed = set.reversed();
+                    assert!(reversed.reversed() == set);
+                    assert!(set.reflected_within(#ident::CAPACITY) == reversed);
+                    for index in 0..#ident::CAPACITY {
+                        let mirrored = #ident::CAPACITY - 1 - index;
+                        assert_eq!(reversed.contains(mirrored), set.contains(index));
+                    }
+                }
+            }
+
+            #[test]
+            fn reflected_within_leaves_the_rest_alone() {
+                for n in [0, 1, 6, #ident::CAPACITY - 1] {
+                    for set in [SET_A, SET_B, SET_C, SET_D] {
+                        let reflected = set.reflected_within(n);
+                        assert!(reflected.reflected_within(n) == set);
+                        assert!(reflected.cleared_0_to_i(n) == set.cleared_0_to_i(n));
+                        for index in 0..n {
+                            assert_eq!(reflected.contains(n - 1 - index), set.contains(index));
+                        }
+                    }
+                }
+            }
+
+            // fn compress(Self, Self) -> Self
+            // fn expand(Self, Self) -> Self
+
+            #[test]
+            fn compress_renumbers_by_rank_in_mask() {
+                for mask in [SET_A, SET_B, SET_C, SET_D, #ident::all(), #ident::new()] {
+                    for set in [SET_A, SET_B, SET_C, SET_D] {
+                        let compressed = set.compress(mask);
+                        assert!(compressed == set.compress_const(mask));
+                        for rank in 0..#ident::CAPACITY {
+                            let expected = mask.select(rank).is_some_and(|i| set.contains(i));
+                            assert_eq!(compressed.contains(rank), expected);
+                        }
+                    }
+                }
+            }
+
+            #[test]
+            fn expand_inverts_compress() {
+                for mask in [SET_A, SET_B, SET_C, SET_D, #ident::all(), #ident::new()] {
+                    for set in [SET_A, SET_B, SET_C, SET_D] {
+                        let expanded = set.expand(mask);
+                        assert!(expanded == set.expand_const(mask));
+                        assert!(expanded.is_subset(mask));
+                        assert!(expanded.compress(mask) == set.masked_0_to_i(mask.len()));
+                        assert!(set.compress(mask).expand(mask) == set.intersection(mask));
+                    }
+                }
+            }
+
             // todo!("test fn len(Self) -> usize")
             // todo!("test fn contains(Self, usize) -> bool")
             // todo!("test fn get(Self, usize) -> bool")
@@ -1077,14 +1499,6 @@ pub fn generate_code(
             // todo!("test fn shifted_down_by_signed(Self, i32) -> Self")
             // todo!("test fn shift_down_by_signed(&mut Self, i32)")
             // todo!("test fn clear(&mut Self) -> ()")
-            // todo!("test fn masked_0_to_i(Self, usize) -> Self")
-            // todo!("test fn mask_0_to_i(&mut Self, usize)")
-            // todo!("test fn masked_i_to_N(Self, usize) -> Self")
-            // todo!("test fn mask_i_to_N(&mut Self, usize)")
-            // todo!("test fn cleared_0_to_i(Self, usize) -> Self")
-            // todo!("test fn clear_0_to_i(&mut Self, usize)")
-            // todo!("test fn cleared_i_to_N(Self, usize) -> Self")
-            // todo!("test fn clear_i_to_N(&mut Self, usize)")
             // todo!("test fn insert_quiet(&mut Self, usize)")
             // todo!("test fn insert(&mut Self, usize) -> bool")
             // todo!("test fn replace_quiet(&mut Self, usize, bool)")
@@ -1097,14 +1511,662 @@ pub fn generate_code(
     }
 }
 
-fn generate_extra_tests(ident: &Ident, debug: bool, _indices: bool, _iter: bool) -> TokenStream {
+fn generate_extra_tests(ident: &Ident, names: &Names, extras: &Extras) -> TokenStream {
     let mut tests = TokenStream::new();
-    if debug {
+    if extras.debug {
         tests.extend(generate_debug_tests(ident));
     }
+    if extras.indices && extras.iter {
+        tests.extend(generate_trait_tests(ident, &names.krate));
+        tests.extend(generate_iterator_tests(ident, names));
+    }
+    if extras.atomic {
+        tests.extend(generate_atomic_tests(ident, &names.atomic));
+    }
+    if extras.subsets {
+        tests.extend(generate_subsets_tests(ident));
+    }
+    if extras.combinations {
+        tests.extend(generate_combinations_tests(ident));
+    }
+    if extras.permutation {
+        tests.extend(generate_permutation_tests(ident, &names.permutation));
+    }
+    if extras.runs {
+        tests.extend(generate_runs_tests(ident, &names.krate));
+    }
     tests
 }
 
+fn generate_subsets_tests(ident: &Ident) -> TokenStream {
+    quote! {
+        /// A set with at most eight elements, so that its subsets can be checked one by one.
+        fn small_set() -> #ident {
+            SET_A.masked_0_to_i(SET_A.select(8).unwrap_or(#ident::CAPACITY))
+        }
+
+        #[test]
+        fn subsets_are_ascending_and_complete() {
+            let set = small_set();
+            let subsets = set.subsets();
+            assert_eq!(subsets.size_hint(), (1 << set.len(), Some(1 << set.len())));
+            let mut count = 0;
+            let mut previous = None;
+            for subset in subsets {
+                assert!(subset.is_subset(set));
+                assert!(previous.is_none_or(|previous: #ident| previous.bits() < subset.bits()));
+                previous = Some(subset);
+                count += 1;
+            }
+            assert_eq!(count, 1 << set.len());
+            assert!(previous == Some(set));
+        }
+
+        #[test]
+        fn subsets_from_both_ends() {
+            let set = small_set();
+            let mut subsets = set.subsets();
+            assert!(subsets.next_back() == Some(set));
+            assert!(subsets.next() == Some(#ident::new()));
+            let rest = subsets.clone().count();
+            assert_eq!(rest, (1 << set.len()) - 2);
+            let forwards: Vec<_> = subsets.clone().collect();
+            assert!(subsets.clone().rev().eq(forwards.into_iter().rev()));
+            let mut alternating = 0;
+            while subsets.next().is_some() && subsets.next_back().is_some() {
+                alternating += 2;
+            }
+            assert!(alternating >= rest - 1);
+            assert!(subsets.next().is_none() && subsets.next_back().is_none());
+        }
+
+        #[test]
+        fn subsets_of_empty_set() {
+            assert!(#ident::new().subsets().eq([#ident::new()]));
+            assert_eq!(#ident::new().non_empty_proper_subsets().count(), 0);
+            assert_eq!(#ident::unit(0).non_empty_proper_subsets().count(), 0);
+        }
+
+        #[test]
+        fn non_empty_proper_subsets() {
+            let set = small_set();
+            let expected = set.subsets().filter(|&subset| !subset.is_empty() && subset != set);
+            assert!(set.non_empty_proper_subsets().eq(expected));
+            assert!(set.non_empty_proper_subsets().rev().eq(
+                set.subsets().rev().filter(|&subset| !subset.is_empty() && subset != set)
+            ));
+        }
+
+        #[test]
+        fn power_set_starts_and_ends() {
+            let mut power_set = #ident::power_set();
+            assert!(power_set.next() == Some(#ident::new()));
+            assert!(power_set.next() == Some(#ident::unit(0)));
+            assert!(power_set.next_back() == Some(#ident::all()));
+            assert!(power_set.next_back() == Some(#ident::all().difference(#ident::unit(0))));
+        }
+    }
+}
+
+fn generate_runs_tests(ident: &Ident, krate: &Path) -> TokenStream {
+    quote! {
+        /// Finds the runs of `set` one index at a time.
+        fn naive_runs(
+            set: #ident,
+        ) -> impl ::core::iter::Iterator<Item = ::core::ops::RangeInclusive<usize>> {
+            let mut index = 0;
+            ::core::iter::from_fn(move || {
+                while index < #ident::CAPACITY && !set.contains(index) {
+                    index += 1;
+                }
+                let start = index;
+                while index < #ident::CAPACITY && set.contains(index) {
+                    index += 1;
+                }
+                (start < index).then(|| start..=index - 1)
+            })
+        }
+
+        const RUN_SETS: [#ident; 7] = [
+            SET_A,
+            SET_B,
+            SET_C,
+            SET_D,
+            #ident::from_bits((0b0001_1110_1110_0111 & MASK) as _),
+            #ident::new(),
+            #ident::all(),
+        ];
+
+        #[test]
+        fn runs_match_naive() {
+            for set in RUN_SETS {
+                assert!(set.iter_runs::<#krate::Ascending>().eq(naive_runs(set)));
+                assert!(set.iter_gaps::<#krate::Ascending>().eq(naive_runs(set.complement())));
+                let descending = set.iter_runs::<#krate::Descending>();
+                assert!(descending.rev().eq(naive_runs(set)));
+                let runs = set.iter_runs::<#krate::Ascending>();
+                assert_eq!(runs.len(), naive_runs(set).count());
+                assert_eq!(set.run_count(), naive_runs(set).count());
+            }
+        }
+
+        #[test]
+        fn runs_cover_the_set() {
+            for set in RUN_SETS {
+                let mut covered = #ident::new();
+                for run in set.iter_runs::<#krate::Descending>() {
+                    covered.insert_range(run);
+                }
+                assert!(covered == set);
+            }
+        }
+
+        #[test]
+        fn longest_run_is_the_first_longest() {
+            for set in RUN_SETS {
+                let expected = naive_runs(set).reduce(|longest, run| {
+                    if run.end() - run.start() > longest.end() - longest.start() {
+                        run
+                    } else {
+                        longest
+                    }
+                });
+                assert_eq!(set.longest_run(), expected);
+                assert_eq!(set.longest_gap(), set.complement().longest_run());
+            }
+        }
+
+        #[test]
+        fn longest_run_of_every_length() {
+            for len in 1..=#ident::CAPACITY {
+                let run = #ident::from_range(#ident::CAPACITY - len..);
+                // A shorter run below it, with a gap in between.
+                let gap_start = (len - 1).min((#ident::CAPACITY - len).saturating_sub(1));
+                let shorter = #ident::from_range(..gap_start);
+                let expected = #ident::CAPACITY - len..=#ident::CAPACITY - 1;
+                assert_eq!(run.longest_run(), Some(expected.clone()));
+                assert_eq!((run | shorter).longest_run(), Some(expected));
+            }
+        }
+
+        #[test]
+        fn find_run_matches_naive() {
+            for set in RUN_SETS {
+                for len in 1..=#ident::CAPACITY + 1 {
+                    let expected = naive_runs(set)
+                        .find(|run| run.end() + 1 - run.start() >= len)
+                        .map(|run| *run.start());
+                    assert_eq!(set.find_run(len), expected);
+                    assert_eq!(set.find_gap(len), set.complement().find_run(len));
+                }
+                assert_eq!(set.find_run(0), Some(0));
+            }
+        }
+    }
+}
+
+fn generate_permutation_tests(ident: &Ident, permutation: &Ident) -> TokenStream {
+    quote! {
+        /// Returns some permutations with very different shapes.
+        fn permutations() -> [#permutation; 4] {
+            let capacity = #ident::CAPACITY;
+            let mut reversal = [0; #ident::CAPACITY];
+            let mut rotation = [0; #ident::CAPACITY];
+            let mut shuffle = [0; #ident::CAPACITY];
+            for index in 0..capacity {
+                reversal[index] = (capacity - 1 - index) as u8;
+                rotation[index] = ((index + 3) % capacity) as u8;
+                shuffle[index] = index as u8;
+            }
+            // A Fisher-Yates shuffle driven by a fixed xorshift generator.
+            let mut state: u32 = 0x2545_f491;
+            for index in (1..capacity).rev() {
+                state ^= state << 13;
+                state ^= state >> 17;
+                state ^= state << 5;
+                shuffle.swap(index, state as usize % (index + 1));
+            }
+            [
+                #permutation::identity(),
+                #permutation::new(reversal).unwrap(),
+                #permutation::new(rotation).unwrap(),
+                #permutation::new(shuffle).unwrap(),
+            ]
+        }
+
+        #[test]
+        fn permutation_network_matches_naive() {
+            for permutation in permutations() {
+                for set in [SET_A, SET_B, SET_C, SET_D, #ident::all(), #ident::new()] {
+                    assert!(permutation.apply(set) == permutation.apply_naive(set));
+                    assert!(set.permuted(&permutation) == permutation.apply(set));
+                }
+                for index in 0..#ident::CAPACITY {
+                    let expected = #ident::unit(permutation.mapping()[index] as usize);
+                    assert!(permutation.apply(#ident::unit(index)) == expected);
+                }
+            }
+        }
+
+        #[test]
+        fn permutations_compose_and_invert() {
+            for first in permutations() {
+                let inverse = first.inverse();
+                assert!(first.then(&inverse) == #permutation::identity());
+                // Equal permutations compare equal however their networks were routed.
+                assert!(inverse == #permutation::new(inverse.mapping()).unwrap());
+                for second in permutations() {
+                    let composed = first.then(&second);
+                    for set in [SET_A, SET_B, SET_C, SET_D] {
+                        assert!(composed.apply(set) == second.apply(first.apply(set)));
+                        assert!(inverse.apply(first.apply(set)) == set);
+                    }
+                }
+            }
+        }
+
+        #[test]
+        fn permutation_rejects_non_permutations() {
+            let mut mapping = #permutation::identity().mapping();
+            mapping[0] = 1;
+            assert!(#permutation::new(mapping).is_none());
+            mapping[0] = #ident::CAPACITY as u8;
+            assert!(#permutation::new(mapping).is_none());
+        }
+    }
+}
+
+fn generate_combinations_tests(ident: &Ident) -> TokenStream {
+    quote! {
+        /// A set with at most ten elements, so that its combinations can be checked one by one.
+        fn medium_set() -> #ident {
+            SET_B.masked_0_to_i(SET_B.select(10).unwrap_or(#ident::CAPACITY))
+        }
+
+        #[test]
+        fn combinations_match_subsets_of_that_size() {
+            let set = medium_set();
+            for k in 0..=set.len() + 1 {
+                let mut expected = [#ident::new(); 1024];
+                let mut count = 0;
+                for bits in 0..1u32 << set.len() {
+                    if bits.count_ones() as usize == k {
+                        let mut subset = #ident::new();
+                        let indices = (0..#ident::CAPACITY).filter(|&i| set.contains(i));
+                        for (i, index) in indices.enumerate() {
+                            if bits & (1 << i) != 0 {
+                                subset.insert_quiet(index);
+                            }
+                        }
+                        expected[count] = subset;
+                        count += 1;
+                    }
+                }
+                // Colexicographic order is the same as ordering by the spread-out bits.
+                expected[..count].sort_unstable_by_key(|subset| subset.bits());
+                let combinations = set.combinations(k);
+                assert_eq!(combinations.size_hint(), (count, Some(count)));
+                assert!(combinations.eq(expected[..count].iter().copied()));
+                assert!(set.combinations(k).rev().eq(expected[..count].iter().rev().copied()));
+            }
+        }
+
+        #[test]
+        fn combinations_meet_in_the_middle() {
+            let set = medium_set();
+            let k = set.len() / 2;
+            let total = set.combinations(k).count();
+            let mut combinations = set.combinations(k);
+            let mut seen = 0;
+            while combinations.next().is_some() {
+                seen += 1;
+                if combinations.next_back().is_some() {
+                    seen += 1;
+                }
+            }
+            assert_eq!(seen, total);
+            assert!(combinations.next_back().is_none());
+        }
+
+        #[test]
+        fn combination_ranks_are_dense() {
+            let capacity = #ident::CAPACITY;
+            for k in [0, 1, 2, capacity / 2, capacity - 1, capacity] {
+                let mut set = #ident::unrank_combination(k, 0).unwrap();
+                assert!(set == #ident::from_range(..k));
+                assert!(set.prev_combination().is_none());
+                let mut expected = 0;
+                for combination in #ident::all().combinations(k).take(200) {
+                    assert!(set == combination);
+                    assert!(set.rank_combination() == expected);
+                    assert!(#ident::unrank_combination(k, expected) == Some(set));
+                    match set.next_combination() {
+                        Some(next) => {
+                            assert!(next.prev_combination() == Some(set));
+                            set = next;
+                        }
+                        None => break,
+                    }
+                    expected += 1;
+                }
+                let last = #ident::from_range(capacity - k..);
+                assert!(last.next_combination().is_none());
+                assert!(#ident::unrank_combination(k, last.rank_combination()) == Some(last));
+                if let Some(past_last) = last.rank_combination().checked_add(1) {
+                    assert!(#ident::unrank_combination(k, past_last).is_none());
+                }
+            }
+            assert!(#ident::unrank_combination(capacity + 1, 0).is_none());
+        }
+
+        #[test]
+        fn combinations_of_full_set() {
+            let capacity = #ident::CAPACITY;
+            assert_eq!(#ident::all().combinations(0).count(), 1);
+            assert!(#ident::all().combinations(capacity).eq([#ident::all()]));
+            assert_eq!(#ident::all().combinations(capacity + 1).count(), 0);
+            let mut ones = #ident::all().combinations(1);
+            assert!(ones.next() == Some(#ident::unit(0)));
+            assert!(ones.next_back() == Some(#ident::unit(capacity - 1)));
+            let mut all_but_one = #ident::all().combinations(capacity - 1);
+            let without = |index| Some(#ident::all().difference(#ident::unit(index)));
+            assert!(all_but_one.next() == without(capacity - 1));
+            assert!(all_but_one.next_back() == without(0));
+        }
+    }
+}
+
+fn generate_atomic_tests(ident: &Ident, atomic: &Ident) -> TokenStream {
+    quote! {
+        const SEQ_CST: ::core::sync::atomic::Ordering = ::core::sync::atomic::Ordering::SeqCst;
+
+        #[test]
+        fn atomic_load_store_swap() {
+            let set = #atomic::new(SET_A);
+            assert!(set.load(SEQ_CST) == SET_A);
+            set.store(SET_B, SEQ_CST);
+            assert!(set.swap(SET_C, SEQ_CST) == SET_B);
+            assert!(set.into_inner() == SET_C);
+            assert!(#atomic::default().load(SEQ_CST).is_empty());
+            assert!(#atomic::from(SET_D).load(SEQ_CST) == SET_D);
+        }
+
+        #[test]
+        fn atomic_insert_remove_replace() {
+            let set = #atomic::new(#ident::new());
+            assert!(set.insert(0, SEQ_CST).is_empty());
+            assert!(set.insert(0, SEQ_CST) == #ident::unit(0));
+            assert!(set.insert(#ident::CAPACITY, SEQ_CST) == #ident::unit(0));
+            assert!(set.contains(0, SEQ_CST));
+            assert!(!set.contains(#ident::CAPACITY, SEQ_CST));
+            assert!(set.replace(#ident::CAPACITY - 1, true, SEQ_CST) == #ident::unit(0));
+            assert!(set.remove(0, SEQ_CST).contains(0));
+            assert!(set.replace(#ident::CAPACITY - 1, false, SEQ_CST).contains(#ident::CAPACITY - 1));
+            assert!(set.load(SEQ_CST).is_empty());
+        }
+
+        #[test]
+        fn atomic_set_algebra() {
+            let set = #atomic::new(SET_A);
+            assert!(set.union_with(SET_B, SEQ_CST) == SET_A);
+            assert!(set.load(SEQ_CST) == SET_A.union(SET_B));
+            assert!(set.intersect_with(SET_C, SEQ_CST) == SET_A.union(SET_B));
+            assert!(set.load(SEQ_CST) == SET_A.union(SET_B).intersection(SET_C));
+            set.store(SET_A, SEQ_CST);
+            set.difference_with(SET_B, SEQ_CST);
+            assert!(set.load(SEQ_CST) == SET_A.difference(SET_B));
+            set.store(SET_A, SEQ_CST);
+            set.symmetric_difference_with(SET_B, SEQ_CST);
+            assert!(set.load(SEQ_CST) == SET_A.symmetric_difference(SET_B));
+        }
+
+        #[test]
+        fn atomic_compare_exchange() {
+            let set = #atomic::new(SET_A);
+            assert!(set.compare_exchange(SET_A, SET_B, SEQ_CST, SEQ_CST) == Ok(SET_A));
+            assert!(set.compare_exchange(SET_A, SET_C, SEQ_CST, SEQ_CST) == Err(SET_B));
+            let mut current = set.load(SEQ_CST);
+            while let Err(actual) = set.compare_exchange_weak(current, SET_D, SEQ_CST, SEQ_CST) {
+                current = actual;
+            }
+            assert!(set.load(SEQ_CST) == SET_D);
+        }
+    }
+}
+
+fn generate_trait_tests(ident: &Ident, krate: &Path) -> TokenStream {
+    quote! {
+        fn _generic_len<S: #krate::BitSet>(set: S) -> usize {
+            set.iter_indices().count()
+        }
+
+        fn _generic_bit_count<S: #krate::BitSet>(set: S) -> usize {
+            set.iter_bits().filter(|&bit| bit).count()
+        }
+
+        fn _generic_insert_all<S: #krate::BitSet>(indices: &[usize]) -> S {
+            let mut set = S::new();
+            for &index in indices {
+                set.insert_quiet(index);
+            }
+            set
+        }
+
+        #[test]
+        fn trait_capacity() {
+            assert_eq!(<#ident as #krate::BitSet>::CAPACITY, #ident::CAPACITY);
+        }
+
+        #[test]
+        fn trait_bits_round_trip() {
+            let bits = <#ident as #krate::BitSet>::bits(SET_A);
+            assert_eq!(<#ident as #krate::BitSet>::from_bits(bits), SET_A);
+        }
+
+        #[test]
+        fn trait_len_matches_indices() {
+            for set in [SET_A, SET_B, SET_C, SET_D, #ident::new(), #ident::all()] {
+                assert_eq!(_generic_len(set), set.len());
+                assert_eq!(_generic_bit_count(set), set.len());
+            }
+        }
+
+        #[test]
+        fn trait_insert_matches_inherent() {
+            let indices = [0, 3, 5, #ident::CAPACITY - 1, #ident::CAPACITY];
+            let mut expected = #ident::new();
+            for index in indices {
+                expected.insert_quiet(index);
+            }
+            assert_eq!(_generic_insert_all::<#ident>(&indices), expected);
+        }
+
+        #[test]
+        fn trait_set_algebra_matches_inherent() {
+            assert_eq!(
+                #krate::BitSet::union(SET_A, SET_B),
+                #ident::union(SET_A, SET_B),
+            );
+            assert_eq!(
+                #krate::BitSet::intersection(SET_A, SET_B),
+                #ident::intersection(SET_A, SET_B),
+            );
+            assert_eq!(
+                #krate::BitSet::symmetric_difference(SET_C, SET_D),
+                #ident::symmetric_difference(SET_C, SET_D),
+            );
+            assert!(#krate::BitSet::is_subset(SET_A.intersection(SET_B), SET_A));
+        }
+    }
+}
+
+fn generate_iterator_tests(ident: &Ident, names: &Names) -> TokenStream {
+    let Names {
+        krate,
+        indices,
+        iter,
+        ..
+    } = names;
+    let indices_debug = format!("{indices}([0, 2, 3])");
+    let iter_debug = format!("{iter}([true, false, true])");
+    quote! {
+        /// Checks the overridden methods of `iterator` against the ones built on `next` and
+        /// `next_back`.
+        fn _check_iterator<I>(iterator: I, len: usize)
+        where
+            I: ::core::iter::DoubleEndedIterator
+                + ::core::iter::ExactSizeIterator
+                + ::core::iter::FusedIterator
+                + ::core::clone::Clone,
+            I::Item: ::core::cmp::Ord + ::core::fmt::Debug,
+        {
+            assert_eq!(iterator.len(), len);
+            assert_eq!(iterator.size_hint(), (len, ::core::option::Option::Some(len)));
+            assert_eq!(iterator.clone().count(), len);
+            assert_eq!(iterator.clone().last(), iterator.clone().next_back());
+            assert_eq!(iterator.clone().min(), iterator.clone().reduce(::core::cmp::min));
+            assert_eq!(iterator.clone().max(), iterator.clone().reduce(::core::cmp::max));
+            let forwards: ::std::vec::Vec<_> = iterator.clone().collect();
+            assert!(iterator.clone().rev().eq(forwards.into_iter().rev()));
+            for n in [0, 1, 2, len / 2, len.saturating_sub(1), len, len + 1, usize::MAX] {
+                let (mut front, mut back) = (iterator.clone(), iterator.clone());
+                let (mut manual_front, mut manual_back) = (iterator.clone(), iterator.clone());
+                for _ in 0..n.min(len) {
+                    manual_front.next();
+                    manual_back.next_back();
+                }
+                assert_eq!(front.nth(n), manual_front.next());
+                assert_eq!(back.nth_back(n), manual_back.next_back());
+                assert!(front.eq(manual_front));
+                assert!(back.eq(manual_back));
+            }
+            // Taking from both ends meets in the middle without repeating anything.
+            let mut both_ends = iterator.clone();
+            let mut taken = 0;
+            while let ::core::option::Option::Some(_) = both_ends.next() {
+                taken += 1;
+                assert_eq!(both_ends.len(), len - taken);
+                if both_ends.next_back().is_some() {
+                    taken += 1;
+                }
+            }
+            assert_eq!(taken, len);
+            assert_eq!(both_ends.next(), ::core::option::Option::None);
+            assert_eq!(both_ends.next_back(), ::core::option::Option::None);
+        }
+
+        #[test]
+        fn indices_iterators_are_double_ended_and_exact() {
+            for set in [SET_A, SET_B, SET_C, SET_D, #ident::new(), #ident::all()] {
+                let ascending = set.iter_indices::<#krate::Ascending>();
+                let descending = set.iter_indices::<#krate::Descending>();
+                assert!(ascending.clone().rev().eq(descending.clone()));
+                _check_iterator(ascending, set.len());
+                _check_iterator(descending, set.len());
+            }
+        }
+
+        #[test]
+        fn bits_iterators_are_double_ended_and_exact() {
+            for set in [SET_A, SET_B, SET_C, SET_D, #ident::new(), #ident::all()] {
+                let ascending = set.iter_bits::<#krate::Ascending>();
+                let descending = set.iter_bits::<#krate::Descending>();
+                assert!(ascending.clone().rev().eq(descending.clone()));
+                _check_iterator(ascending, #ident::CAPACITY);
+                _check_iterator(descending, #ident::CAPACITY);
+            }
+        }
+
+        fn _check_order<D: #krate::Direction>(set: #ident) {
+            let mut expected: ::std::vec::Vec<usize> =
+                set.iter_indices::<#krate::Ascending>().collect();
+            expected.sort_by_key(|&index| D::key(index));
+            assert!(set.iter_indices::<D>().eq(expected.iter().copied()));
+            _check_iterator(set.iter_indices::<D>(), set.len());
+
+            let mut every_index: ::std::vec::Vec<usize> = (0..#ident::CAPACITY).collect();
+            every_index.sort_by_key(|&index| D::key(index));
+            assert!(set.iter_bits::<D>().eq(every_index.iter().map(|&index| set.contains(index))));
+            _check_iterator(set.iter_bits::<D>(), #ident::CAPACITY);
+        }
+
+        #[test]
+        fn iterators_follow_the_order_of_each_direction() {
+            for set in [SET_A, SET_B, SET_C, SET_D, #ident::new(), #ident::all()] {
+                _check_order::<#krate::Ascending>(set);
+                _check_order::<#krate::Descending>(set);
+                _check_order::<#krate::GrayCode>(set);
+                _check_order::<#krate::BitReversed>(set);
+                _check_order::<#krate::Shuffled<0x5EED>>(set);
+            }
+        }
+
+        #[test]
+        fn into_iterator_yields_indices_in_ascending_order() {
+            for set in [SET_A, SET_B, SET_C, SET_D] {
+                let ascending = set.iter_indices::<#krate::Ascending>();
+                assert!((&set).into_iter().eq(ascending.clone()));
+                let mut count = 0;
+                for (index, expected) in set.into_iter().zip(ascending) {
+                    assert_eq!(index, expected);
+                    count += 1;
+                }
+                assert_eq!(count, set.len());
+                assert!(set.into_iter().collect::<#ident>() == set);
+            }
+        }
+
+        #[test]
+        fn iter_indices_in_matches_filter() {
+            let capacity = #ident::CAPACITY;
+            for set in [SET_A, SET_B, SET_C, SET_D, #ident::all()] {
+                for (start, end) in [(0, capacity), (3, 7), (capacity / 2, capacity + 5), (5, 2)] {
+                    let ascending = set.iter_indices_in::<#krate::Ascending, _>(start..end);
+                    let expected = set.iter_indices::<#krate::Ascending>();
+                    assert!(ascending.eq(expected.filter(|index| (start..end).contains(index))));
+                    let descending = set.iter_indices_in::<#krate::Descending, _>(start..end);
+                    let expected = set.iter_indices::<#krate::Descending>();
+                    assert!(descending.eq(expected.filter(|index| (start..end).contains(index))));
+                }
+                assert!(set.iter_indices_in::<#krate::Ascending, _>(..).eq(set.into_iter()));
+            }
+        }
+
+        #[test]
+        fn iter_indices_step_matches_filter() {
+            let capacity = #ident::CAPACITY;
+            for set in [SET_A, SET_B, SET_C, SET_D, #ident::all()] {
+                let strides = [(0, 1), (0, 2), (1, 2), (4, 3), (2, capacity), (capacity, 1)];
+                for (start, step) in strides {
+                    let on_step = |index: &usize| *index >= start && (*index - start) % step == 0;
+                    let ascending = set.iter_indices_step::<#krate::Ascending>(start, step);
+                    let expected = set.iter_indices::<#krate::Ascending>();
+                    assert!(ascending.eq(expected.filter(on_step)));
+                    let descending = set.iter_indices_step::<#krate::Descending>(start, step);
+                    let expected = set.iter_indices::<#krate::Descending>();
+                    assert!(descending.eq(expected.filter(on_step)));
+                }
+            }
+        }
+
+        #[test]
+        #[should_panic(expected = "step must be non-zero")]
+        fn iter_indices_step_of_zero() {
+            let _ = SET_A.iter_indices_step::<#krate::Ascending>(0, 0);
+        }
+
+        #[test]
+        fn iterators_debug_what_is_left() {
+            let set = #ident::from_bits(0b1101);
+            let indices = set.iter_indices::<#krate::Ascending>();
+            assert_eq!(::std::format!("{indices:?}"), #indices_debug);
+            let mut bits = set.iter_bits::<#krate::Ascending>();
+            bits.nth_back(#ident::CAPACITY - 4);
+            assert_eq!(::std::format!("{bits:?}"), #iter_debug);
+        }
+    }
+}
+
 fn generate_debug_tests(ident: &Ident) -> TokenStream {
     quote! {
         struct _NotAHashSet(::std::vec::Vec<usize>);
@@ -1,6 +1,7 @@
 extern crate proc_macro;
 extern crate proc_macro2;
 
+mod enum_set;
 mod extras;
 mod impls;
 mod type_utils;
@@ -8,9 +9,40 @@ mod type_utils;
 use extras::Extras;
 use proc_macro::TokenStream;
 use proc_macro_error::{abort, proc_macro_error};
-use proc_macro2::TokenStream as TokenStream2;
 use syn::{DeriveInput, parse_macro_input, spanned::Spanned};
 
+/// Implements the bitset API for a tuple struct wrapping a primitive unsigned integer.
+///
+/// The core methods and operator overloads are always generated. Everything else is opted into
+/// with the `#[bitset(...)]` attribute, which accepts the following specifiers:
+///
+/// - `debug`: implements `Debug`, formatting the set like a `HashSet` of indices.
+/// - `indices`: generates an iterator over the indices of the set bits, along with the
+///   `iter_indices` method. The iterator can be named with `indices = Name`.
+/// - `iter`: generates an iterator over every bit, along with the `iter_bits` method. The iterator
+///   can be named with `iter = Name`.
+/// - `atomic`: generates `Atomic{Name}`, a counterpart to the set built on the matching type from
+///   `core::sync::atomic`. It can be named with `atomic = Name`. `u128` is not supported.
+/// - `subsets`: generates an iterator over the subsets of a set, along with the `subsets`,
+///   `power_set` and `non_empty_proper_subsets` methods. The iterator can be named with
+///   `subsets = Name`.
+/// - `combinations`: generates an iterator over the `k`-element subsets of a set, along with the
+///   `combinations` method and the colexicographic `rank_combination`, `unrank_combination`,
+///   `next_combination` and `prev_combination`. The iterator can be named with
+///   `combinations = Name`.
+/// - `permutation`: generates a precompiled permutation of a set's indices, along with the
+///   `permuted` and `permute` methods. It can be named with `permutation = Name`.
+/// - `runs`: generates an iterator over the maximal runs of consecutive elements in a set, along
+///   with the `iter_runs` and `iter_gaps` methods and the `run_count`, `longest_run`,
+///   `longest_gap`, `find_run` and `find_gap` queries. The iterator can be named with
+///   `runs = Name`.
+/// - `tests`: generates unit tests for everything above.
+/// - `capacity = N`: limits the set to the indices `0..N`, where `N` may be smaller than the
+///   number of bits in the integer. The bits at or above `N` are always kept at `0`.
+/// - `crate = path`: the path to the `rose_bitsets` crate, in case it has been renamed.
+///
+/// When both `indices` and `iter` are given, the `BitSet` trait is implemented as well, which
+/// requires the struct to also implement `Clone`, `Copy`, `Eq` and `Hash`.
 #[proc_macro_error]
 #[proc_macro_derive(BitSet, attributes(bitset))]
 pub fn bitset_derive(input: TokenStream) -> TokenStream {
@@ -29,15 +61,31 @@ pub fn bitset_derive(input: TokenStream) -> TokenStream {
     }
     let uint = impls::main::get_uint(data);
 
-    let iter_impls = if let Some(bitset_attribute) = attrs
+    let extras = attrs
         .iter()
         .find(|attribute| attribute.meta.path().is_ident("bitset"))
-    {
-        Extras::generate_code_from_attribute(bitset_attribute, &ident, &uint)
-    } else {
-        TokenStream2::new()
-    };
-    let mut impls = impls::main::generate_code(ident, uint);
-    impls.extend(iter_impls);
+        .map(Extras::from_attribute)
+        .unwrap_or_default();
+    let mut impls = impls::main::generate_code(&ident, &uint, extras.capacity());
+    impls.extend(extras.generate_code(&ident, &uint));
     impls.into()
 }
+
+#[proc_macro_error]
+#[proc_macro_derive(EnumSetType)]
+pub fn enum_set_type_derive(input: TokenStream) -> TokenStream {
+    let DeriveInput {
+        ident,
+        data,
+        generics,
+        ..
+    } = parse_macro_input!(input as DeriveInput);
+    if !generics.params.is_empty() {
+        abort!(
+            generics.span(), "unexpected generics";
+            note = "`EnumSetType` cannot be derived for generic types";
+        );
+    }
+    let variants = enum_set::get_variants(&ident, data);
+    enum_set::generate_code(ident, variants).into()
+}
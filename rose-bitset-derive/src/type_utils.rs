@@ -1,31 +1,7 @@
-use proc_macro2::Span;
+use proc_macro2::TokenStream;
+use quote::quote;
 use std::{fmt::Write, ops::Deref};
-use syn::{Ident, Path, PathArguments, PathSegment, Token, Type, TypePath, punctuated::Punctuated};
-
-pub fn signed_counterpart(uint: &Type) -> Option<Type> {
-    let uint_repr = repr(uint);
-    let int_repr = match uint_repr.as_str() {
-        "u8" => "i8",
-        "u16" => "i16",
-        "u32" => "i32",
-        "u64" => "i64",
-        "u128" => "i128",
-        "usize" => "isize",
-        _ => return None,
-    };
-    let mut path_segments: Punctuated<PathSegment, Token![::]> = Punctuated::new();
-    path_segments.push_value(PathSegment {
-        ident: Ident::new(int_repr, Span::call_site()),
-        arguments: PathArguments::None,
-    });
-    Some(Type::Path(TypePath {
-        qself: None,
-        path: Path {
-            leading_colon: None,
-            segments: path_segments,
-        },
-    }))
-}
+use syn::{PathSegment, Token, Type, punctuated::Punctuated};
 
 pub fn repr(r#type: &Type) -> String {
     match r#type {
@@ -51,3 +27,14 @@ fn repr_path(segments: &Punctuated<PathSegment, Token![::]>) -> String {
     }
     out
 }
+
+/// Returns the `cfg` (if any) under which every count up to `2^BITS` fits in a `usize`, or `None`
+/// if there is no such target.
+pub fn exact_size_cfg(uint: &Type) -> Option<TokenStream> {
+    match repr(uint).as_str() {
+        "u8" => Some(TokenStream::new()),
+        "u16" => Some(quote! { #[cfg(not(target_pointer_width = "16"))] }),
+        "u32" => Some(quote! { #[cfg(target_pointer_width = "64")] }),
+        _ => None,
+    }
+}
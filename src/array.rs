@@ -0,0 +1,1198 @@
+use crate::{Ascending, BitSet, Descending};
+use core::{
+    fmt,
+    marker::PhantomData,
+    ops::{
+        BitAnd, BitAndAssign, BitOr, BitOrAssign, BitXor, BitXorAssign, Neg, Not, Shl, ShlAssign,
+        Shr, ShrAssign, Sub, SubAssign,
+    },
+};
+
+/// A set of `64 * WORDS` bits, stored as an array of [`u64`] words.
+///
+/// This exists for universes that outgrow the largest primitive bitset (`BitSet128`). Its API
+/// mirrors the one shared by the `BitSetN` types, with the words ordered from least to most
+/// significant (i.e., bit `i` lives in `words[i / 64]`).
+///
+/// ```
+/// use rose_bitsets::{Ascending, BitArray};
+///
+/// let mut set = BitArray::<4>::new();
+/// set.insert_quiet(3);
+/// set.insert_quiet(200);
+///
+/// assert_eq!(BitArray::<4>::CAPACITY, 256);
+/// assert_eq!(set.len(), 2);
+/// assert!(set.iter_indices::<Ascending>().eq([3, 200]));
+/// ```
+#[derive(Clone, Copy, Eq, Hash, PartialEq)]
+pub struct BitArray<const WORDS: usize>([u64; WORDS]);
+
+impl<const WORDS: usize> BitArray<WORDS> {
+    /// The capacity of a [`BitArray`].
+    pub const CAPACITY: usize = 64 * WORDS;
+
+    /// Creates an empty set.
+    #[doc(alias = "empty")]
+    #[must_use]
+    pub const fn new() -> Self {
+        Self([0; WORDS])
+    }
+
+    /// Creates a set containing with only the bit at `index` set.
+    ///
+    /// If `index >=`[`Self::CAPACITY`], the resulting set will be empty.
+    #[must_use]
+    pub const fn unit(index: usize) -> Self {
+        let mut set = Self::new();
+        if index < Self::CAPACITY {
+            set.0[index / 64] = 1 << (index % 64);
+        }
+        set
+    }
+
+    /// Creates a set with all bits set.
+    #[must_use]
+    pub const fn all() -> Self {
+        Self([u64::MAX; WORDS])
+    }
+
+    /// Creates a set with the given words, ordered from least to most significant.
+    #[must_use]
+    pub const fn from_bits(bits: [u64; WORDS]) -> Self {
+        Self(bits)
+    }
+
+    /// Returns the underlying words of the set, ordered from least to most significant.
+    #[must_use]
+    pub const fn bits(self) -> [u64; WORDS] {
+        self.0
+    }
+
+    /// Creates a new set that complements `self`.
+    ///
+    /// This is the `const` alternative to [`Not::not`] for [`BitArray`]s.
+    #[doc(alias = "inverse")]
+    #[must_use]
+    pub const fn complement(mut self) -> Self {
+        let mut i = 0;
+        while i < WORDS {
+            self.0[i] = !self.0[i];
+            i += 1;
+        }
+        self
+    }
+
+    /// Creates a new set with values that are in both `self` and `rhs`.
+    ///
+    /// This is the `const` alternative to [`BitAnd::bitand`] for [`BitArray`]s.
+    #[must_use]
+    pub const fn intersection(mut self, rhs: Self) -> Self {
+        let mut i = 0;
+        while i < WORDS {
+            self.0[i] &= rhs.0[i];
+            i += 1;
+        }
+        self
+    }
+
+    /// Creates a new set with values that are in `self` or `rhs`.
+    ///
+    /// This is the `const` alternative to [`BitOr::bitor`] for [`BitArray`]s.
+    #[must_use]
+    pub const fn union(mut self, rhs: Self) -> Self {
+        let mut i = 0;
+        while i < WORDS {
+            self.0[i] |= rhs.0[i];
+            i += 1;
+        }
+        self
+    }
+
+    /// Creates a new set with values that are in `self`, but not in `rhs`.
+    ///
+    /// This is the `const` alternative to [`Sub::sub`] for [`BitArray`]s.
+    #[must_use]
+    pub const fn difference(mut self, rhs: Self) -> Self {
+        let mut i = 0;
+        while i < WORDS {
+            self.0[i] &= !rhs.0[i];
+            i += 1;
+        }
+        self
+    }
+
+    /// Creates a new set with values that are in `self` or `rhs`, but not in both.
+    ///
+    /// This is the `const` alternative to [`BitXor::bitxor`] for [`BitArray`]s.
+    #[must_use]
+    pub const fn symmetric_difference(mut self, rhs: Self) -> Self {
+        let mut i = 0;
+        while i < WORDS {
+            self.0[i] ^= rhs.0[i];
+            i += 1;
+        }
+        self
+    }
+
+    /// Returns `true` if `self` is equal to `rhs`, i.e., both sets have the same exact values.
+    ///
+    /// This is the `const` alternative to [`PartialEq::eq`] for [`BitArray`]s.
+    #[must_use]
+    pub const fn is(self, rhs: Self) -> bool {
+        let mut i = 0;
+        while i < WORDS {
+            if self.0[i] != rhs.0[i] {
+                return false;
+            }
+            i += 1;
+        }
+        true
+    }
+
+    /// Returns `true` if `self` is not equal to `rhs`, i.e., the sets do not have exactly the same
+    /// values.
+    ///
+    /// This is the `const` alternative to [`PartialEq::ne`] for [`BitArray`]s.
+    #[must_use]
+    pub const fn is_not(self, rhs: Self) -> bool {
+        !self.is(rhs)
+    }
+
+    /// Returns `true` if `self` has no elements in common with `rhs`. This is equivalent to
+    /// checking for an empty intersection.
+    #[must_use]
+    pub const fn is_disjoint(self, rhs: Self) -> bool {
+        self.intersection(rhs).is_empty()
+    }
+
+    /// Returns `true` if the set is a subset of another, i.e., `rhs` contains at least all the
+    /// values in `self`.
+    #[must_use]
+    pub const fn is_subset(self, rhs: Self) -> bool {
+        self.difference(rhs).is_empty()
+    }
+
+    /// Returns `true` if the set is a strict subset of another, i.e., `rhs` contains all the
+    /// values in `self` **and** is larger than `self`.
+    #[must_use]
+    pub const fn is_strict_subset(self, rhs: Self) -> bool {
+        self.is_subset(rhs) && self.is_not(rhs)
+    }
+
+    /// Returns `true` if the set is a superset of another, i.e., `self` contains at least all the
+    /// values in `rhs`.
+    #[must_use]
+    pub const fn is_superset(self, rhs: Self) -> bool {
+        rhs.is_subset(self)
+    }
+
+    /// Returns `true` if the set is a strict superset of another, i.e., `self` contains all the
+    /// values in `rhs` **and** is larger than `rhs`.
+    #[must_use]
+    pub const fn is_strict_superset(self, rhs: Self) -> bool {
+        self.is_superset(rhs) && self.is_not(rhs)
+    }
+
+    /// Returns `true` if the set contains no elements.
+    #[must_use]
+    pub const fn is_empty(self) -> bool {
+        self.is(Self::new())
+    }
+
+    /// Returns `true` if the set contains all [`Self::CAPACITY`] elements.
+    #[must_use]
+    pub const fn is_full(self) -> bool {
+        self.is(Self::all())
+    }
+
+    /// Returns the number of elements in the set.
+    #[must_use]
+    pub const fn len(self) -> usize {
+        let mut len = 0;
+        let mut i = 0;
+        while i < WORDS {
+            len += self.0[i].count_ones() as usize;
+            i += 1;
+        }
+        len
+    }
+
+    /// Returns `true` if the bit at `index` is set.
+    #[must_use]
+    pub const fn contains(self, index: usize) -> bool {
+        index < Self::CAPACITY && self.0[index / 64] & (1 << (index % 64)) != 0
+    }
+
+    /// Gets the bit at `index`.
+    ///
+    /// If `index >=`[`Self::CAPACITY`], this will simply return `false`.
+    #[must_use]
+    pub const fn get(self, index: usize) -> bool {
+        self.contains(index)
+    }
+
+    /// Returns the index of the least significant bit that is set.
+    ///
+    /// If no bits are set, this returns [`Self::CAPACITY`].
+    #[must_use]
+    pub const fn min_index(self) -> usize {
+        let mut i = 0;
+        while i < WORDS {
+            if self.0[i] != 0 {
+                return 64 * i + self.0[i].trailing_zeros() as usize;
+            }
+            i += 1;
+        }
+        Self::CAPACITY
+    }
+
+    /// Returns the index of the most significant bit that is set.
+    ///
+    /// # Panics
+    ///
+    /// Panics if no bits are set. For a non-panicking alternative, see
+    /// [`max_index_checked`](Self::max_index_checked).
+    #[must_use]
+    pub const fn max_index(self) -> usize {
+        match self.max_index_checked() {
+            Some(max) => max,
+            None => panic!("cannot take the maximum of an empty set"),
+        }
+    }
+
+    /// Returns the index of the most significant bit that is set, or [`None`] if no bits are set.
+    #[must_use]
+    pub const fn max_index_checked(self) -> Option<usize> {
+        let mut i = WORDS;
+        while i > 0 {
+            i -= 1;
+            if self.0[i] != 0 {
+                return Some(64 * i + self.0[i].ilog2() as usize);
+            }
+        }
+        None
+    }
+
+    /// Creates a copy of this set with all values incremented by `shift`.
+    ///
+    /// Any values that reach or exceed [`Self::CAPACITY`] will be lost.
+    ///
+    /// This is the `const` alternative to [`Shl::shl`] for [`BitArray`]s.
+    #[must_use]
+    pub const fn shifted_up_by(mut self, shift: u32) -> Self {
+        self.shift_up_by(shift);
+        self
+    }
+
+    /// Increments all values in this set by `shift`.
+    ///
+    /// Any values that reach or exceed [`Self::CAPACITY`] will be lost.
+    ///
+    /// This is the `const` alternative to [`ShlAssign::shl_assign`] for [`BitArray`]s.
+    pub const fn shift_up_by(&mut self, shift: u32) {
+        let shift = shift as usize;
+        if shift >= Self::CAPACITY {
+            self.clear();
+            return;
+        }
+        let (word_shift, bit_shift) = (shift / 64, (shift % 64) as u32);
+        let mut i = WORDS;
+        while i > word_shift {
+            i -= 1;
+            let source = i - word_shift;
+            let mut word = self.0[source] << bit_shift;
+            if bit_shift != 0 && source > 0 {
+                word |= self.0[source - 1] >> (64 - bit_shift);
+            }
+            self.0[i] = word;
+        }
+        while i > 0 {
+            i -= 1;
+            self.0[i] = 0;
+        }
+    }
+
+    /// Creates a copy of this set will all values incremented by `shift`.
+    ///
+    /// Any values that exit the bounds of the set will be lost.
+    #[must_use]
+    pub const fn shifted_up_by_signed(mut self, shift: i32) -> Self {
+        self.shift_up_by_signed(shift);
+        self
+    }
+
+    /// Increments all values in this set by `shift`.
+    ///
+    /// Any values that exit the bounds of the set will be lost.
+    pub const fn shift_up_by_signed(&mut self, shift: i32) {
+        if shift >= 0 {
+            self.shift_up_by(shift as u32);
+        } else {
+            self.shift_down_by(shift.unsigned_abs());
+        }
+    }
+
+    /// Creates a copy of this set with all values decremented by `shift`.
+    ///
+    /// Any values that dip into the negatives will be lost.
+    ///
+    /// This is the `const` alternative to [`Shr::shr`] for [`BitArray`]s.
+    #[must_use]
+    pub const fn shifted_down_by(mut self, shift: u32) -> Self {
+        self.shift_down_by(shift);
+        self
+    }
+
+    /// Decrements all values in this set by `shift`.
+    ///
+    /// Any values that dip into the negatives will be lost.
+    ///
+    /// This is the `const` alternative to [`ShrAssign::shr_assign`] for [`BitArray`]s.
+    pub const fn shift_down_by(&mut self, shift: u32) {
+        let shift = shift as usize;
+        if shift >= Self::CAPACITY {
+            self.clear();
+            return;
+        }
+        let (word_shift, bit_shift) = (shift / 64, (shift % 64) as u32);
+        let mut i = 0;
+        while i + word_shift < WORDS {
+            let source = i + word_shift;
+            let mut word = self.0[source] >> bit_shift;
+            if bit_shift != 0 && source + 1 < WORDS {
+                word |= self.0[source + 1] << (64 - bit_shift);
+            }
+            self.0[i] = word;
+            i += 1;
+        }
+        while i < WORDS {
+            self.0[i] = 0;
+            i += 1;
+        }
+    }
+
+    /// Creates a copy of this set with all values decremented by `shift`.
+    ///
+    /// Any values that exit the bounds of the set will be lost.
+    #[must_use]
+    pub const fn shifted_down_by_signed(mut self, shift: i32) -> Self {
+        self.shift_down_by_signed(shift);
+        self
+    }
+
+    /// Decrements all values in this set by `shift`.
+    ///
+    /// Any values that exit the bounds of the set will be lost.
+    pub const fn shift_down_by_signed(&mut self, shift: i32) {
+        if shift >= 0 {
+            self.shift_down_by(shift as u32);
+        } else {
+            self.shift_up_by(shift.unsigned_abs());
+        }
+    }
+
+    /// Clears the set, removing all values.
+    pub const fn clear(&mut self) {
+        *self = Self::new();
+    }
+
+    /// Creates a copy of this set that only has values less than `index`.
+    #[must_use]
+    pub const fn masked_0_to_i(mut self, index: usize) -> Self {
+        self.mask_0_to_i(index);
+        self
+    }
+
+    /// Removes any bits with indices outside the range `0..index`.
+    pub const fn mask_0_to_i(&mut self, index: usize) {
+        let mut i = 0;
+        while i < WORDS {
+            let start = 64 * i;
+            if index <= start {
+                self.0[i] = 0;
+            } else if index - start < 64 {
+                self.0[i] &= (1 << (index - start)) - 1;
+            }
+            i += 1;
+        }
+    }
+
+    /// Creates a copy of this set without the bits with indices in the range `0..index`.
+    #[must_use]
+    pub const fn cleared_0_to_i(mut self, index: usize) -> Self {
+        self.clear_0_to_i(index);
+        self
+    }
+
+    /// Clears bits `0..index`, keeping all other bits in their original states.
+    pub const fn clear_0_to_i(&mut self, index: usize) {
+        let mut i = 0;
+        while i < WORDS {
+            let start = 64 * i;
+            if index >= start + 64 {
+                self.0[i] = 0;
+            } else if index > start {
+                self.0[i] &= !((1 << (index - start)) - 1);
+            }
+            i += 1;
+        }
+    }
+
+    /// Sets the bit at `index` to `1`.
+    ///
+    /// If you would like to know if the insertion succeeded, use [`insert`](Self::insert)
+    /// instead.
+    pub const fn insert_quiet(&mut self, index: usize) {
+        if index < Self::CAPACITY {
+            self.0[index / 64] |= 1 << (index % 64);
+        }
+    }
+
+    /// Sets the bit at `index` to `1`. Returns whether the bit was not already set.
+    ///
+    /// If the return value is not needed, use [`insert_quiet`](Self::insert_quiet) instead.
+    #[must_use = "consider using the return value or calling `insert_quiet` instead"]
+    pub const fn insert(&mut self, index: usize) -> bool {
+        let was_set = self.contains(index);
+        self.insert_quiet(index);
+        index < Self::CAPACITY && !was_set
+    }
+
+    /// Sets the bit at `index` to `bit`.
+    ///
+    /// If you would like to know the old value of the bit, use [`replace`](Self::replace)
+    /// instead.
+    pub const fn replace_quiet(&mut self, index: usize, bit: bool) {
+        if bit {
+            self.insert_quiet(index)
+        } else {
+            self.remove_quiet(index)
+        }
+    }
+
+    /// Sets the bit at `index` to `bit`.
+    ///
+    /// If the return value is not needed, use [`replace_quiet`](Self::replace_quiet) instead.
+    #[must_use = "consider using the return value or calling `replace_quiet` instead"]
+    pub const fn replace(&mut self, index: usize, bit: bool) -> bool {
+        let old_bit = self.contains(index);
+        self.replace_quiet(index, bit);
+        old_bit
+    }
+
+    /// Sets the bit at `index` to `0`.
+    ///
+    /// If you would like to know if the removal succeeded, use [`remove`](Self::remove)
+    /// instead.
+    pub const fn remove_quiet(&mut self, index: usize) {
+        if index < Self::CAPACITY {
+            self.0[index / 64] &= !(1 << (index % 64));
+        }
+    }
+
+    /// Sets the bit at `index` to `0`. Returns whether the bit was set.
+    ///
+    /// If the return value is not needed, use [`remove_quiet`](Self::remove_quiet) instead.
+    #[must_use = "consider using the return value or calling `remove_quiet` instead"]
+    pub const fn remove(&mut self, index: usize) -> bool {
+        let was_set = self.contains(index);
+        self.remove_quiet(index);
+        was_set
+    }
+
+    /// Creates an iterator over the indices of the bits that are set in the set.
+    #[must_use]
+    pub const fn iter_indices<D>(&self) -> BitArrayIndices<'_, WORDS, D>
+    where
+        for<'a> BitArrayIndices<'a, WORDS, D>: Iterator<Item = usize>,
+    {
+        BitArrayIndices::new(self)
+    }
+
+    /// Creates an iterator over the bits of the set.
+    #[must_use]
+    pub const fn iter_bits<D>(&self) -> BitArrayIter<'_, WORDS, D>
+    where
+        for<'a> BitArrayIter<'a, WORDS, D>: Iterator<Item = bool>,
+    {
+        BitArrayIter::new(self)
+    }
+}
+
+/// An iterator over the indices of the bits that are set in a [`BitArray`].
+///
+/// `D` can only be [`Ascending`] or [`Descending`]. Other [`Direction`](crate::Direction)s work on
+/// indices below 128, which a [`BitArray`] can go past.
+pub struct BitArrayIndices<'a, const WORDS: usize, D = Ascending> {
+    words: [u64; WORDS],
+    _markers: (PhantomData<&'a ()>, PhantomData<D>),
+}
+
+impl<'a, const WORDS: usize, D> BitArrayIndices<'a, WORDS, D> {
+    /// Creates an iterator over the indices of the bits that are set in `set`.
+    pub const fn new(set: &'a BitArray<WORDS>) -> Self {
+        Self {
+            words: set.0,
+            _markers: (PhantomData, PhantomData),
+        }
+    }
+}
+
+impl<const WORDS: usize> Iterator for BitArrayIndices<'_, WORDS, Ascending> {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (i, word) = self
+            .words
+            .iter_mut()
+            .enumerate()
+            .find(|(_, word)| **word != 0)?;
+        let index = 64 * i + word.trailing_zeros() as usize;
+        *word &= *word - 1;
+        Some(index)
+    }
+}
+
+impl<const WORDS: usize> Iterator for BitArrayIndices<'_, WORDS, Descending> {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (i, word) = self
+            .words
+            .iter_mut()
+            .enumerate()
+            .rfind(|(_, word)| **word != 0)?;
+        let bit = word.ilog2();
+        *word ^= 1 << bit;
+        Some(64 * i + bit as usize)
+    }
+}
+
+/// An iterator over the bits of a [`BitArray`].
+///
+/// Like [`BitArrayIndices`], it only takes [`Ascending`] or [`Descending`] as `D`.
+pub struct BitArrayIter<'a, const WORDS: usize, D = Ascending> {
+    words: [u64; WORDS],
+    i: usize,
+    _markers: (PhantomData<&'a ()>, PhantomData<D>),
+}
+
+impl<'a, const WORDS: usize, D> BitArrayIter<'a, WORDS, D> {
+    /// Creates an iterator over the bits of `set`.
+    pub const fn new(set: &'a BitArray<WORDS>) -> Self {
+        Self {
+            words: set.0,
+            i: 0,
+            _markers: (PhantomData, PhantomData),
+        }
+    }
+}
+
+impl<const WORDS: usize> Iterator for BitArrayIter<'_, WORDS, Ascending> {
+    type Item = bool;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let index = self.i;
+        (index < BitArray::<WORDS>::CAPACITY).then(|| {
+            self.i += 1;
+            self.words[index / 64] & (1 << (index % 64)) != 0
+        })
+    }
+}
+
+impl<const WORDS: usize> Iterator for BitArrayIter<'_, WORDS, Descending> {
+    type Item = bool;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        (self.i < BitArray::<WORDS>::CAPACITY).then(|| {
+            self.i += 1;
+            let index = BitArray::<WORDS>::CAPACITY - self.i;
+            self.words[index / 64] & (1 << (index % 64)) != 0
+        })
+    }
+}
+
+impl<const WORDS: usize> BitSet for BitArray<WORDS> {
+    type Bits = [u64; WORDS];
+    type Indices<'a> = BitArrayIndices<'a, WORDS, Ascending>;
+    type Iter<'a> = BitArrayIter<'a, WORDS, Ascending>;
+
+    const CAPACITY: usize = Self::CAPACITY;
+
+    fn new() -> Self {
+        Self::new()
+    }
+
+    fn unit(index: usize) -> Self {
+        Self::unit(index)
+    }
+
+    fn all() -> Self {
+        Self::all()
+    }
+
+    fn from_bits(bits: [u64; WORDS]) -> Self {
+        Self::from_bits(bits)
+    }
+
+    fn bits(self) -> [u64; WORDS] {
+        self.bits()
+    }
+
+    fn complement(self) -> Self {
+        self.complement()
+    }
+
+    fn intersection(self, rhs: Self) -> Self {
+        self.intersection(rhs)
+    }
+
+    fn union(self, rhs: Self) -> Self {
+        self.union(rhs)
+    }
+
+    fn difference(self, rhs: Self) -> Self {
+        self.difference(rhs)
+    }
+
+    fn symmetric_difference(self, rhs: Self) -> Self {
+        self.symmetric_difference(rhs)
+    }
+
+    fn is_subset(self, rhs: Self) -> bool {
+        self.is_subset(rhs)
+    }
+
+    fn is_empty(self) -> bool {
+        self.is_empty()
+    }
+
+    fn is_full(self) -> bool {
+        self.is_full()
+    }
+
+    fn len(self) -> usize {
+        self.len()
+    }
+
+    fn contains(self, index: usize) -> bool {
+        self.contains(index)
+    }
+
+    fn min_index(self) -> usize {
+        self.min_index()
+    }
+
+    fn max_index_checked(self) -> Option<usize> {
+        self.max_index_checked()
+    }
+
+    fn shifted_up_by(self, shift: u32) -> Self {
+        self.shifted_up_by(shift)
+    }
+
+    fn shifted_up_by_signed(self, shift: i32) -> Self {
+        self.shifted_up_by_signed(shift)
+    }
+
+    fn shifted_down_by(self, shift: u32) -> Self {
+        self.shifted_down_by(shift)
+    }
+
+    fn shifted_down_by_signed(self, shift: i32) -> Self {
+        self.shifted_down_by_signed(shift)
+    }
+
+    fn masked_0_to_i(self, index: usize) -> Self {
+        self.masked_0_to_i(index)
+    }
+
+    fn cleared_0_to_i(self, index: usize) -> Self {
+        self.cleared_0_to_i(index)
+    }
+
+    fn insert_quiet(&mut self, index: usize) {
+        self.insert_quiet(index)
+    }
+
+    fn remove_quiet(&mut self, index: usize) {
+        self.remove_quiet(index)
+    }
+
+    fn iter_indices(&self) -> Self::Indices<'_> {
+        BitArrayIndices::new(self)
+    }
+
+    fn iter_bits(&self) -> Self::Iter<'_> {
+        BitArrayIter::new(self)
+    }
+}
+
+impl<const WORDS: usize> Default for BitArray<WORDS> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const WORDS: usize> fmt::Debug for BitArray<WORDS> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set()
+            .entries(self.iter_indices::<Ascending>())
+            .finish()
+    }
+}
+
+impl<const WORDS: usize> BitArray<WORDS> {
+    /// Writes the words as one big number in base `2^digit_bits`, most significant digit first,
+    /// honoring the formatter's flags the way the formatting of a primitive integer does.
+    fn fmt_digits(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+        prefix: &str,
+        digit_bits: usize,
+        digits: &[u8; 16],
+    ) -> fmt::Result {
+        // Even in binary, there are never more digits than bits.
+        let mut buffer = [[0u8; 64]; WORDS];
+        let buffer = buffer.as_flattened_mut();
+        let len = Self::CAPACITY.div_ceil(digit_bits);
+        for i in 0..len {
+            buffer[len - 1 - i] = digits[self.digit_at(i * digit_bits, digit_bits)];
+        }
+        let written = match buffer[..len].iter().position(|&digit| digit != b'0') {
+            Some(first) => &buffer[first..len],
+            None => b"0",
+        };
+        // Every digit is ASCII.
+        let written = core::str::from_utf8(written).unwrap();
+        write!(f, "BitArray(")?;
+        f.pad_integral(true, prefix, written)?;
+        write!(f, ")")
+    }
+
+    /// Returns the `len` bits starting at `start` as a number, treating bits past the end as `0`.
+    fn digit_at(&self, start: usize, len: usize) -> usize {
+        let (word, offset) = (start / 64, start % 64);
+        let mut bits = self.0[word] >> offset;
+        if offset + len > 64 && word + 1 < WORDS {
+            bits |= self.0[word + 1] << (64 - offset);
+        }
+        (bits & ((1 << len) - 1)) as usize
+    }
+}
+
+impl<const WORDS: usize> fmt::Binary for BitArray<WORDS> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.fmt_digits(f, "0b", 1, b"0123456789abcdef")
+    }
+}
+
+impl<const WORDS: usize> fmt::LowerHex for BitArray<WORDS> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.fmt_digits(f, "0x", 4, b"0123456789abcdef")
+    }
+}
+
+impl<const WORDS: usize> fmt::Octal for BitArray<WORDS> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.fmt_digits(f, "0o", 3, b"0123456789abcdef")
+    }
+}
+
+impl<const WORDS: usize> fmt::UpperHex for BitArray<WORDS> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.fmt_digits(f, "0x", 4, b"0123456789ABCDEF")
+    }
+}
+
+impl<const WORDS: usize> BitAnd for BitArray<WORDS> {
+    type Output = Self;
+
+    fn bitand(self, rhs: Self) -> Self::Output {
+        self.intersection(rhs)
+    }
+}
+
+impl<const WORDS: usize> BitAndAssign for BitArray<WORDS> {
+    fn bitand_assign(&mut self, rhs: Self) {
+        *self = *self & rhs;
+    }
+}
+
+impl<const WORDS: usize> BitOr for BitArray<WORDS> {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self::Output {
+        self.union(rhs)
+    }
+}
+
+impl<const WORDS: usize> BitOrAssign for BitArray<WORDS> {
+    fn bitor_assign(&mut self, rhs: Self) {
+        *self = *self | rhs;
+    }
+}
+
+impl<const WORDS: usize> BitXor for BitArray<WORDS> {
+    type Output = Self;
+
+    fn bitxor(self, rhs: Self) -> Self::Output {
+        self.symmetric_difference(rhs)
+    }
+}
+
+impl<const WORDS: usize> BitXorAssign for BitArray<WORDS> {
+    fn bitxor_assign(&mut self, rhs: Self) {
+        *self = *self ^ rhs;
+    }
+}
+
+impl<const WORDS: usize> From<[u64; WORDS]> for BitArray<WORDS> {
+    fn from(value: [u64; WORDS]) -> Self {
+        Self::from_bits(value)
+    }
+}
+
+impl<const WORDS: usize> From<BitArray<WORDS>> for [u64; WORDS] {
+    fn from(value: BitArray<WORDS>) -> Self {
+        value.bits()
+    }
+}
+
+impl<const WORDS: usize> Neg for BitArray<WORDS> {
+    type Output = Self;
+
+    fn neg(self) -> Self::Output {
+        self.complement()
+    }
+}
+
+impl<const WORDS: usize> Not for BitArray<WORDS> {
+    type Output = Self;
+
+    fn not(self) -> Self::Output {
+        self.complement()
+    }
+}
+
+/// Implements `Shl` and `Shr` for an integer type and references to it. A shift too large for the
+/// `shifted_*` methods moves every bit out of the set.
+macro_rules! impl_shifts {
+    ($($rhs:ty => |$shift:ident| $convert:expr, $up:ident, $down:ident;)*) => {$(
+        impl<const WORDS: usize> Shl<$rhs> for BitArray<WORDS> {
+            type Output = Self;
+
+            fn shl(self, $shift: $rhs) -> Self::Output {
+                match $convert {
+                    Some(shift) => self.$up(shift),
+                    None => Self::new(),
+                }
+            }
+        }
+
+        impl<const WORDS: usize> Shl<&$rhs> for BitArray<WORDS> {
+            type Output = Self;
+
+            fn shl(self, rhs: &$rhs) -> Self::Output {
+                self << *rhs
+            }
+        }
+
+        impl<const WORDS: usize> Shr<$rhs> for BitArray<WORDS> {
+            type Output = Self;
+
+            fn shr(self, $shift: $rhs) -> Self::Output {
+                match $convert {
+                    Some(shift) => self.$down(shift),
+                    None => Self::new(),
+                }
+            }
+        }
+
+        impl<const WORDS: usize> Shr<&$rhs> for BitArray<WORDS> {
+            type Output = Self;
+
+            fn shr(self, rhs: &$rhs) -> Self::Output {
+                self >> *rhs
+            }
+        }
+    )*};
+}
+
+impl_shifts! {
+    u8 => |rhs| Some(u32::from(rhs)), shifted_up_by, shifted_down_by;
+    u16 => |rhs| Some(u32::from(rhs)), shifted_up_by, shifted_down_by;
+    u32 => |rhs| Some(rhs), shifted_up_by, shifted_down_by;
+    u64 => |rhs| u32::try_from(rhs).ok(), shifted_up_by, shifted_down_by;
+    u128 => |rhs| u32::try_from(rhs).ok(), shifted_up_by, shifted_down_by;
+    usize => |rhs| u32::try_from(rhs).ok(), shifted_up_by, shifted_down_by;
+    i8 => |rhs| Some(i32::from(rhs)), shifted_up_by_signed, shifted_down_by_signed;
+    i16 => |rhs| Some(i32::from(rhs)), shifted_up_by_signed, shifted_down_by_signed;
+    i32 => |rhs| Some(rhs), shifted_up_by_signed, shifted_down_by_signed;
+    i64 => |rhs| i32::try_from(rhs).ok(), shifted_up_by_signed, shifted_down_by_signed;
+    i128 => |rhs| i32::try_from(rhs).ok(), shifted_up_by_signed, shifted_down_by_signed;
+    isize => |rhs| i32::try_from(rhs).ok(), shifted_up_by_signed, shifted_down_by_signed;
+}
+
+impl<const WORDS: usize> ShlAssign<u32> for BitArray<WORDS> {
+    fn shl_assign(&mut self, rhs: u32) {
+        self.shift_up_by(rhs);
+    }
+}
+
+impl<const WORDS: usize> ShrAssign<u32> for BitArray<WORDS> {
+    fn shr_assign(&mut self, rhs: u32) {
+        self.shift_down_by(rhs);
+    }
+}
+
+impl<const WORDS: usize> Sub for BitArray<WORDS> {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        self.difference(rhs)
+    }
+}
+
+impl<const WORDS: usize> SubAssign for BitArray<WORDS> {
+    fn sub_assign(&mut self, rhs: Self) {
+        *self = *self - rhs;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    type BitArray256 = BitArray<4>;
+
+    const SET_A: BitArray256 = BitArray256::from_bits([
+        0x739c6f3022c6abbd,
+        0x745cdc820c3e3e59,
+        0xb77f9320423feb4a,
+        0x446c4e86f71986f4,
+    ]);
+    const SET_B: BitArray256 = BitArray256::from_bits([
+        0x17f66ce7a17376e9,
+        0x6d5c852727edd964,
+        0,
+        0x89ba968fb8d94509,
+    ]);
+
+    fn indices_of(set: BitArray256) -> Vec<usize> {
+        (0..BitArray256::CAPACITY)
+            .filter(|&index| set.contains(index))
+            .collect()
+    }
+
+    #[test]
+    fn capacity() {
+        assert_eq!(BitArray256::CAPACITY, 256);
+        assert_eq!(BitArray::<16>::CAPACITY, 1024);
+    }
+
+    #[test]
+    fn new_set_is_empty() {
+        assert!(BitArray256::new().is_empty());
+        assert!(!BitArray256::new().is_full());
+        assert_eq!(BitArray256::default(), BitArray256::new());
+    }
+
+    #[test]
+    fn all_is_full() {
+        assert!(BitArray256::all().is_full());
+        assert_eq!(BitArray256::all().len(), 256);
+    }
+
+    #[rstest]
+    #[case(0)]
+    #[case(63)]
+    #[case(64)]
+    #[case(200)]
+    #[case(255)]
+    fn unit(#[case] index: usize) {
+        let set = BitArray256::unit(index);
+        assert_eq!(set.len(), 1);
+        assert!(set.contains(index));
+        assert_eq!(set.min_index(), index);
+        assert_eq!(set.max_index(), index);
+    }
+
+    #[test]
+    fn unit_capacity_is_empty() {
+        assert!(BitArray256::unit(256).is_empty());
+    }
+
+    #[test]
+    fn set_algebra() {
+        for i in 0..4 {
+            let (a, b) = (SET_A.bits()[i], SET_B.bits()[i]);
+            assert_eq!(SET_A.union(SET_B).bits()[i], a | b);
+            assert_eq!(SET_A.intersection(SET_B).bits()[i], a & b);
+            assert_eq!(SET_A.difference(SET_B).bits()[i], a & !b);
+            assert_eq!(SET_A.symmetric_difference(SET_B).bits()[i], a ^ b);
+            assert_eq!(SET_A.complement().bits()[i], !a);
+        }
+    }
+
+    #[test]
+    fn subsets() {
+        let intersection = SET_A & SET_B;
+        assert!(intersection.is_subset(SET_A));
+        assert!(intersection.is_strict_subset(SET_B));
+        assert!(SET_A.is_superset(intersection));
+        assert!(!SET_A.is_subset(SET_B));
+        assert!(SET_A.is_disjoint(SET_A.complement()));
+    }
+
+    #[test]
+    fn len() {
+        assert_eq!(SET_A.len(), indices_of(SET_A).len());
+        assert_eq!(SET_B.len(), indices_of(SET_B).len());
+    }
+
+    #[test]
+    fn min_and_max() {
+        let set = BitArray256::from_bits([0, 0b1000, 0, 0]);
+        assert_eq!(set.min_index(), 67);
+        assert_eq!(set.max_index_checked(), Some(67));
+        assert_eq!(BitArray256::new().min_index(), 256);
+        assert_eq!(BitArray256::new().max_index_checked(), None);
+    }
+
+    #[rstest]
+    #[case(0)]
+    #[case(1)]
+    #[case(63)]
+    #[case(64)]
+    #[case(65)]
+    #[case(130)]
+    #[case(255)]
+    #[case(256)]
+    fn shifts_cross_word_boundaries(#[case] shift: usize) {
+        let up: Vec<_> = indices_of(SET_A)
+            .into_iter()
+            .map(|index| index + shift)
+            .filter(|&index| index < 256)
+            .collect();
+        assert_eq!(indices_of(SET_A.shifted_up_by(shift as u32)), up);
+        assert_eq!(indices_of(SET_A << shift), up);
+
+        let down: Vec<_> = indices_of(SET_A)
+            .into_iter()
+            .filter_map(|index| index.checked_sub(shift))
+            .collect();
+        assert_eq!(indices_of(SET_A.shifted_down_by(shift as u32)), down);
+        assert_eq!(indices_of(SET_A >> shift), down);
+
+        let signed = shift as i32;
+        assert_eq!(SET_A.shifted_up_by_signed(-signed), SET_A.shifted_down_by(shift as u32));
+        assert_eq!(SET_A.shifted_down_by_signed(-signed), SET_A.shifted_up_by(shift as u32));
+    }
+
+    #[rstest]
+    #[case(0)]
+    #[case(10)]
+    #[case(64)]
+    #[case(100)]
+    #[case(256)]
+    fn masks(#[case] index: usize) {
+        let below: Vec<_> = indices_of(SET_A)
+            .into_iter()
+            .filter(|&i| i < index)
+            .collect();
+        let above: Vec<_> = indices_of(SET_A)
+            .into_iter()
+            .filter(|&i| i >= index)
+            .collect();
+        assert_eq!(indices_of(SET_A.masked_0_to_i(index)), below);
+        assert_eq!(indices_of(SET_A.cleared_0_to_i(index)), above);
+    }
+
+    #[test]
+    fn insert_and_remove() {
+        let mut set = BitArray256::new();
+        assert!(set.insert(130));
+        assert!(!set.insert(130));
+        assert!(!set.insert(256));
+        assert!(set.replace(130, false));
+        assert!(!set.replace(5, true));
+        assert!(set.remove(5));
+        assert!(!set.remove(5));
+        assert!(set.is_empty());
+    }
+
+    #[test]
+    fn iter_indices() {
+        let indices = indices_of(SET_A);
+        assert!(SET_A.iter_indices::<Ascending>().eq(indices.iter().copied()));
+        assert!(SET_A.iter_indices::<Descending>().eq(indices.iter().rev().copied()));
+    }
+
+    #[test]
+    fn iter_bits() {
+        let bits: Vec<_> = (0..256).map(|index| SET_B.contains(index)).collect();
+        assert!(SET_B.iter_bits::<Ascending>().eq(bits.iter().copied()));
+        assert!(SET_B.iter_bits::<Descending>().eq(bits.iter().rev().copied()));
+    }
+
+    #[test]
+    fn debug() {
+        let set = BitArray256::from_bits([0b101, 0, 0, 1 << 63]);
+        assert_eq!(format!("{set:?}"), "{0, 2, 255}");
+    }
+
+    #[test]
+    fn binary() {
+        let set = BitArray::<2>::from_bits([0b101, 0b11]);
+        assert_eq!(
+            format!("{set:b}"),
+            format!("BitArray(11{:064b})", 0b101),
+        );
+        assert_eq!(format!("{:#b}", BitArray::<2>::new()), "BitArray(0b0)");
+    }
+
+    #[test]
+    fn hex() {
+        let set = BitArray::<2>::from_bits([0xabc, 0xf]);
+        assert_eq!(format!("{set:x}"), "BitArray(f0000000000000abc)");
+        assert_eq!(format!("{set:#X}"), "BitArray(0xF0000000000000ABC)");
+    }
+
+    #[test]
+    fn octal() {
+        // The digit at bits 63..66 straddles the two words.
+        let set = BitArray::<2>::from_bits([1 << 63 | 0o17, 0b11]);
+        let bits = 0b11 << 64 | 1 << 63 | 0o17_u128;
+        assert_eq!(format!("{set:o}"), format!("BitArray({bits:o})"));
+        assert_eq!(format!("{:#o}", BitArray::<1>::new()), "BitArray(0o0)");
+    }
+
+    #[test]
+    fn formatting_honors_flags() {
+        let set = BitArray::<2>::from_bits([0b101, 0]);
+        assert_eq!(format!("{set:08b}"), "BitArray(00000101)");
+        assert_eq!(format!("{set:#08b}"), "BitArray(0b000101)");
+        assert_eq!(format!("{set:>6x}"), "BitArray(     5)");
+        assert_eq!(format!("{set:*<4o}"), "BitArray(5***)");
+    }
+
+    #[test]
+    fn shifts_by_every_integer_type() {
+        let set = BitArray256::from_bits([0b11, 0, 0, 0]);
+        let expected = set.shifted_up_by(65);
+        assert_eq!(set << 65u8, expected);
+        assert_eq!(Shl::shl(set, &65u16), expected);
+        assert_eq!(set << 65u64, expected);
+        assert_eq!(set << 65u128, expected);
+        assert_eq!(set << 65i8, expected);
+        assert_eq!(expected << -65i64, set);
+        assert_eq!(set >> -65isize, expected);
+        assert_eq!(expected >> 65i128, set);
+        assert_eq!(Shr::shr(expected, &65usize), set);
+        assert!((set << u64::MAX).is_empty());
+        assert!((set >> i128::MIN).is_empty());
+    }
+}
@@ -0,0 +1,329 @@
+use core::{
+    fmt,
+    hash::Hash,
+    ops::{BitAnd, BitAndAssign, BitOr, BitOrAssign, BitXor, BitXorAssign, Not, Sub, SubAssign},
+};
+
+/// Functionality shared by every bitset in this crate.
+///
+/// Each of the `BitSetN` types implements this trait by forwarding to its inherent methods, so
+/// generic code can be written once and used with sets of any width. Prefer the inherent methods
+/// when the concrete type is known, since most of them are also usable in `const` contexts.
+///
+/// # Example
+///
+/// ```
+/// use rose_bitsets::{BitSet, BitSet16, BitSet64};
+///
+/// fn evens<S: BitSet>() -> S {
+///     let mut set = S::new();
+///     for index in (0..S::CAPACITY).step_by(2) {
+///         set.insert_quiet(index);
+///     }
+///     set
+/// }
+///
+/// assert_eq!(evens::<BitSet16>().len(), 8);
+/// assert_eq!(evens::<BitSet64>().len(), 32);
+/// ```
+pub trait BitSet:
+    Copy
+    + Eq
+    + Hash
+    + BitAnd<Output = Self>
+    + BitAndAssign
+    + BitOr<Output = Self>
+    + BitOrAssign
+    + BitXor<Output = Self>
+    + BitXorAssign
+    + Not<Output = Self>
+    + Sub<Output = Self>
+    + SubAssign
+    + From<Self::Bits>
+    + Into<Self::Bits>
+{
+    /// The underlying representation of the set.
+    type Bits: Copy + Eq + Hash + fmt::Debug;
+
+    /// An iterator over the indices of the bits that are set, in ascending order.
+    type Indices<'a>: Iterator<Item = usize>
+    where
+        Self: 'a;
+
+    /// An iterator over every bit of the set, in ascending order.
+    type Iter<'a>: Iterator<Item = bool>
+    where
+        Self: 'a;
+
+    /// The maximum number of elements the set can hold.
+    const CAPACITY: usize;
+
+    /// Creates an empty set.
+    #[must_use]
+    fn new() -> Self;
+
+    /// Creates a set containing with only the bit at `index` set.
+    ///
+    /// If `index >=`[`Self::CAPACITY`], the resulting set will be empty.
+    #[must_use]
+    fn unit(index: usize) -> Self;
+
+    /// Creates a set with all bits set.
+    #[must_use]
+    fn all() -> Self;
+
+    /// Creates a set with the given bits.
+    #[must_use]
+    fn from_bits(bits: Self::Bits) -> Self;
+
+    /// Returns the underlying bits of the set.
+    #[must_use]
+    fn bits(self) -> Self::Bits;
+
+    /// Creates a new set that complements `self`.
+    #[must_use]
+    fn complement(self) -> Self;
+
+    /// Creates a new set with values that are in both `self` and `rhs`.
+    #[must_use]
+    fn intersection(self, rhs: Self) -> Self;
+
+    /// Creates a new set with values that are in `self` or `rhs`.
+    #[must_use]
+    fn union(self, rhs: Self) -> Self;
+
+    /// Creates a new set with values that are in `self`, but not in `rhs`.
+    #[must_use]
+    fn difference(self, rhs: Self) -> Self;
+
+    /// Creates a new set with values that are in `self` or `rhs`, but not in both.
+    #[must_use]
+    fn symmetric_difference(self, rhs: Self) -> Self;
+
+    /// Returns `true` if `self` has no elements in common with `rhs`. This is equivalent to
+    /// checking for an empty intersection.
+    #[must_use]
+    fn is_disjoint(self, rhs: Self) -> bool {
+        self.intersection(rhs).is_empty()
+    }
+
+    /// Returns `true` if the set is a subset of another, i.e., `rhs` contains at least all the
+    /// values in `self`.
+    #[must_use]
+    fn is_subset(self, rhs: Self) -> bool {
+        self.union(rhs) == rhs
+    }
+
+    /// Returns `true` if the set is a strict subset of another, i.e., `rhs` contains all the
+    /// values in `self` **and** is larger than `self`.
+    #[must_use]
+    fn is_strict_subset(self, rhs: Self) -> bool {
+        self.is_subset(rhs) && self != rhs
+    }
+
+    /// Returns `true` if the set is a superset of another, i.e., `self` contains at least all the
+    /// values in `rhs`.
+    #[must_use]
+    fn is_superset(self, rhs: Self) -> bool {
+        rhs.is_subset(self)
+    }
+
+    /// Returns `true` if the set is a strict superset of another, i.e., `self` contains all the
+    /// values in `rhs` **and** is larger than `rhs`.
+    #[must_use]
+    fn is_strict_superset(self, rhs: Self) -> bool {
+        self.is_superset(rhs) && self != rhs
+    }
+
+    /// Returns `true` if the set contains no elements.
+    #[must_use]
+    fn is_empty(self) -> bool;
+
+    /// Returns `true` if the set contains all [`Self::CAPACITY`] elements.
+    #[must_use]
+    fn is_full(self) -> bool;
+
+    /// Returns the number of elements in the set.
+    #[must_use]
+    fn len(self) -> usize;
+
+    /// Returns `true` if the bit at `index` is set.
+    #[must_use]
+    fn contains(self, index: usize) -> bool;
+
+    /// Gets the bit at `index`.
+    ///
+    /// If `index >=`[`Self::CAPACITY`], this will simply return `false`.
+    #[must_use]
+    fn get(self, index: usize) -> bool {
+        self.contains(index)
+    }
+
+    /// Returns the index of the least significant bit that is set.
+    ///
+    /// If no bits are set, this returns [`Self::CAPACITY`].
+    #[must_use]
+    fn min_index(self) -> usize;
+
+    /// Returns the index of the most significant bit that is set.
+    ///
+    /// # Panics
+    ///
+    /// Panics if no bits are set. For a non-panicking alternative, see
+    /// [`max_index_checked`](Self::max_index_checked).
+    #[must_use]
+    fn max_index(self) -> usize {
+        self.max_index_checked()
+            .expect("cannot take the maximum of an empty set")
+    }
+
+    /// Returns the index of the most significant bit that is set, or [`None`] if no bits are set.
+    #[must_use]
+    fn max_index_checked(self) -> Option<usize>;
+
+    /// Creates a copy of this set with all values incremented by `shift`.
+    ///
+    /// Any values that reach or exceed [`Self::CAPACITY`] will be lost.
+    #[must_use]
+    fn shifted_up_by(self, shift: u32) -> Self;
+
+    /// Increments all values in this set by `shift`.
+    ///
+    /// Any values that reach or exceed [`Self::CAPACITY`] will be lost.
+    fn shift_up_by(&mut self, shift: u32) {
+        *self = self.shifted_up_by(shift);
+    }
+
+    /// Creates a copy of this set will all values incremented by `shift`.
+    ///
+    /// Any values that exit the bounds of the set will be lost.
+    #[must_use]
+    fn shifted_up_by_signed(self, shift: i32) -> Self;
+
+    /// Increments all values in this set by `shift`.
+    ///
+    /// Any values that exit the bounds of the set will be lost.
+    fn shift_up_by_signed(&mut self, shift: i32) {
+        *self = self.shifted_up_by_signed(shift);
+    }
+
+    /// Creates a copy of this set with all values decremented by `shift`.
+    ///
+    /// Any values that dip into the negatives will be lost.
+    #[must_use]
+    fn shifted_down_by(self, shift: u32) -> Self;
+
+    /// Decrements all values in this set by `shift`.
+    ///
+    /// Any values that dip into the negatives will be lost.
+    fn shift_down_by(&mut self, shift: u32) {
+        *self = self.shifted_down_by(shift);
+    }
+
+    /// Creates a copy of this set with all values decremented by `shift`.
+    ///
+    /// Any values that exit the bounds of the set will be lost.
+    #[must_use]
+    fn shifted_down_by_signed(self, shift: i32) -> Self;
+
+    /// Decrements all values in this set by `shift`.
+    ///
+    /// Any values that exit the bounds of the set will be lost.
+    fn shift_down_by_signed(&mut self, shift: i32) {
+        *self = self.shifted_down_by_signed(shift);
+    }
+
+    /// Clears the set, removing all values.
+    fn clear(&mut self) {
+        *self = Self::new();
+    }
+
+    /// Creates a copy of this set that only has values less than `index`.
+    #[must_use]
+    fn masked_0_to_i(self, index: usize) -> Self;
+
+    /// Removes any bits with indices outside the range `0..index`.
+    fn mask_0_to_i(&mut self, index: usize) {
+        *self = self.masked_0_to_i(index);
+    }
+
+    /// Creates a copy of this set without the bits with indices in the range `0..index`.
+    #[must_use]
+    fn cleared_0_to_i(self, index: usize) -> Self;
+
+    /// Clears bits `0..index`, keeping all other bits in their original states.
+    fn clear_0_to_i(&mut self, index: usize) {
+        *self = self.cleared_0_to_i(index);
+    }
+
+    /// Sets the bit at `index` to `1`.
+    ///
+    /// If you would like to know if the insertion succeeded, use [`insert`](Self::insert)
+    /// instead.
+    fn insert_quiet(&mut self, index: usize) {
+        *self = self.union(Self::unit(index));
+    }
+
+    /// Sets the bit at `index` to `1`. Returns whether the bit was not already set.
+    ///
+    /// If the return value is not needed, use [`insert_quiet`](Self::insert_quiet) instead.
+    #[must_use = "consider using the return value or calling `insert_quiet` instead"]
+    fn insert(&mut self, index: usize) -> bool {
+        let old_set = *self;
+        self.insert_quiet(index);
+        old_set != *self
+    }
+
+    /// Sets the bit at `index` to `bit`.
+    ///
+    /// If you would like to know the old value of the bit, use [`replace`](Self::replace)
+    /// instead.
+    fn replace_quiet(&mut self, index: usize, bit: bool) {
+        if bit {
+            self.insert_quiet(index)
+        } else {
+            self.remove_quiet(index)
+        }
+    }
+
+    /// Sets the bit at `index` to `bit`.
+    ///
+    /// If the return value is not needed, use [`replace_quiet`](Self::replace_quiet) instead.
+    #[must_use = "consider using the return value or calling `replace_quiet` instead"]
+    fn replace(&mut self, index: usize, bit: bool) -> bool {
+        let old_set = *self;
+        if bit {
+            self.insert_quiet(index);
+            old_set == *self
+        } else {
+            self.remove_quiet(index);
+            old_set != *self
+        }
+    }
+
+    /// Sets the bit at `index` to `0`.
+    ///
+    /// If you would like to know if the removal succeeded, use [`remove`](Self::remove)
+    /// instead.
+    fn remove_quiet(&mut self, index: usize) {
+        *self = self.difference(Self::unit(index));
+    }
+
+    /// Sets the bit at `index` to `0`. Returns whether the bit was set.
+    ///
+    /// If the return value is not needed, use [`remove_quiet`](Self::remove_quiet) instead.
+    #[must_use = "consider using the return value or calling `remove_quiet` instead"]
+    fn remove(&mut self, index: usize) -> bool {
+        let old_set = *self;
+        self.remove_quiet(index);
+        old_set != *self
+    }
+
+    /// Creates an iterator over the indices of the bits that are set, in ascending order.
+    #[must_use]
+    fn iter_indices(&self) -> Self::Indices<'_>;
+
+    /// Creates an iterator over the bits of the set, in ascending order.
+    #[must_use]
+    fn iter_bits(&self) -> Self::Iter<'_>;
+}
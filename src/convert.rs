@@ -0,0 +1,307 @@
+//! Conversions between the fixed-width bitsets.
+//!
+//! Widening is lossless, so it goes through [`From`]. Narrowing goes through [`TryFrom`], which
+//! fails if the set has any members the narrower type can't hold; the `truncated_to_*` methods
+//! discard them instead. A set can also be cut into an array of equally-sized lanes, with the
+//! lowest indices in the first lane.
+
+/// An array of equally-sized narrower sets that a `W` can be cut into with `to_lanes` and joined
+/// back from with `from_lanes`.
+///
+/// This trait is sealed, and is only implemented for the arrays that exactly cover `W`, such as
+/// `[BitSet8; 8]` and `[BitSet32; 2]` for `BitSet64`.
+///
+/// ```compile_fail
+/// use rose_bitsets::BitSet64;
+///
+/// let bits: u64 = BitSet64::all().to_lanes();
+/// ```
+pub trait Lanes<W>: From<W> + Into<W> + sealed::Sealed<W> {}
+
+mod sealed {
+    pub trait Sealed<W> {}
+}
+
+macro_rules! impl_width_conversions {
+    (
+        $wide_feature:literal => $wide:ident($wide_uint:ty) {
+            $($narrow_feature:literal => $narrow:ident($narrow_uint:ty), $truncated:ident;)*
+        }
+    ) => {
+        $(
+            #[cfg(all(feature = $wide_feature, feature = $narrow_feature))]
+            impl From<crate::$narrow> for crate::$wide {
+                fn from(set: crate::$narrow) -> Self {
+                    Self::from_bits(set.bits() as $wide_uint)
+                }
+            }
+
+            #[cfg(all(feature = $wide_feature, feature = $narrow_feature))]
+            impl TryFrom<crate::$wide> for crate::$narrow {
+                type Error = crate::CapacityError;
+
+                fn try_from(set: crate::$wide) -> Result<Self, Self::Error> {
+                    match set.max_index_checked() {
+                        Some(max) if max >= Self::CAPACITY => {
+                            Err(crate::CapacityError::new(max, Self::CAPACITY))
+                        }
+                        _ => Ok(Self::from_bits(set.bits() as $narrow_uint)),
+                    }
+                }
+            }
+
+            #[cfg(all(feature = $wide_feature, feature = $narrow_feature))]
+            impl crate::$wide {
+                #[doc = concat!(
+                    "Returns the members of the set that fit in a [`",
+                    stringify!($narrow),
+                    "`](crate::",
+                    stringify!($narrow),
+                    "), discarding the rest.",
+                )]
+                #[must_use]
+                pub const fn $truncated(self) -> crate::$narrow {
+                    crate::$narrow::from_bits(self.bits() as $narrow_uint)
+                }
+            }
+
+            #[cfg(all(feature = $wide_feature, feature = $narrow_feature))]
+            impl sealed::Sealed<crate::$wide>
+                for [crate::$narrow; crate::$wide::CAPACITY / crate::$narrow::CAPACITY]
+            {
+            }
+
+            #[cfg(all(feature = $wide_feature, feature = $narrow_feature))]
+            impl Lanes<crate::$wide>
+                for [crate::$narrow; crate::$wide::CAPACITY / crate::$narrow::CAPACITY]
+            {
+            }
+
+            #[cfg(all(feature = $wide_feature, feature = $narrow_feature))]
+            impl From<crate::$wide>
+                for [crate::$narrow; crate::$wide::CAPACITY / crate::$narrow::CAPACITY]
+            {
+                fn from(set: crate::$wide) -> Self {
+                    core::array::from_fn(|lane| {
+                        crate::$narrow::from_bits(
+                            (set.bits() >> (lane * crate::$narrow::CAPACITY)) as $narrow_uint,
+                        )
+                    })
+                }
+            }
+
+            #[cfg(all(feature = $wide_feature, feature = $narrow_feature))]
+            impl From<[crate::$narrow; crate::$wide::CAPACITY / crate::$narrow::CAPACITY]>
+                for crate::$wide
+            {
+                fn from(
+                    lanes: [crate::$narrow; crate::$wide::CAPACITY / crate::$narrow::CAPACITY],
+                ) -> Self {
+                    let bits = lanes.iter().enumerate().fold(0, |bits, (lane, set)| {
+                        bits | (set.bits() as $wide_uint) << (lane * crate::$narrow::CAPACITY)
+                    });
+                    Self::from_bits(bits)
+                }
+            }
+        )*
+
+        #[cfg(feature = $wide_feature)]
+        impl crate::$wide {
+            /// Cuts the set into an array of narrower sets, the first of which holds the lowest
+            /// indices. The lane type is usually inferred from context.
+            #[must_use]
+            pub fn to_lanes<L: Lanes<Self>>(self) -> L {
+                L::from(self)
+            }
+
+            /// Joins an array of narrower sets, the first of which holds the lowest indices. This
+            /// is the inverse of [`to_lanes`](Self::to_lanes).
+            #[must_use]
+            pub fn from_lanes<L: Lanes<Self>>(lanes: L) -> Self {
+                lanes.into()
+            }
+        }
+    };
+}
+
+macro_rules! impl_halves {
+    ($(
+        $wide_feature:literal => $wide:ident($wide_uint:ty),
+        $narrow_feature:literal => $narrow:ident;
+    )*) => {$(
+        #[cfg(all(feature = $wide_feature, feature = $narrow_feature))]
+        impl crate::$wide {
+            /// Splits the set into its lower and upper halves, in that order.
+            #[must_use]
+            pub const fn split_halves(self) -> (crate::$narrow, crate::$narrow) {
+                let bits = self.bits();
+                (
+                    crate::$narrow::from_bits(bits as _),
+                    crate::$narrow::from_bits((bits >> crate::$narrow::CAPACITY) as _),
+                )
+            }
+
+            /// Joins two halves into one set, with `low` holding the lower indices. This is the
+            /// inverse of [`split_halves`](Self::split_halves).
+            #[must_use]
+            pub const fn concat(low: crate::$narrow, high: crate::$narrow) -> Self {
+                let high = (high.bits() as $wide_uint) << crate::$narrow::CAPACITY;
+                Self::from_bits(low.bits() as $wide_uint | high)
+            }
+        }
+    )*};
+}
+
+/// Conversions where neither side is guaranteed to fit in the other, because one of them is
+/// [`BitSetSize`](crate::BitSetSize).
+macro_rules! impl_fallible_conversions {
+    ($($from_feature:literal => $from:ident, $to_feature:literal => $to:ident;)*) => {$(
+        #[cfg(all(feature = $from_feature, feature = $to_feature))]
+        impl TryFrom<crate::$from> for crate::$to {
+            type Error = crate::CapacityError;
+
+            fn try_from(set: crate::$from) -> Result<Self, Self::Error> {
+                match set.max_index_checked() {
+                    Some(max) if max >= Self::CAPACITY => {
+                        Err(crate::CapacityError::new(max, Self::CAPACITY))
+                    }
+                    _ => Ok(Self::from_bits(set.bits() as _)),
+                }
+            }
+        }
+    )*};
+}
+
+impl_width_conversions! {
+    "b16" => BitSet16(u16) {
+        "b8" => BitSet8(u8), truncated_to_8;
+    }
+}
+
+impl_width_conversions! {
+    "b32" => BitSet32(u32) {
+        "b8" => BitSet8(u8), truncated_to_8;
+        "b16" => BitSet16(u16), truncated_to_16;
+    }
+}
+
+impl_width_conversions! {
+    "b64" => BitSet64(u64) {
+        "b8" => BitSet8(u8), truncated_to_8;
+        "b16" => BitSet16(u16), truncated_to_16;
+        "b32" => BitSet32(u32), truncated_to_32;
+    }
+}
+
+impl_width_conversions! {
+    "b128" => BitSet128(u128) {
+        "b8" => BitSet8(u8), truncated_to_8;
+        "b16" => BitSet16(u16), truncated_to_16;
+        "b32" => BitSet32(u32), truncated_to_32;
+        "b64" => BitSet64(u64), truncated_to_64;
+    }
+}
+
+// A pointer is always at least 16 bits wide, so these are the only widths `BitSetSize` can be
+// widened from.
+impl_width_conversions! {
+    "bsize" => BitSetSize(usize) {
+        "b8" => BitSet8(u8), truncated_to_8;
+        "b16" => BitSet16(u16), truncated_to_16;
+    }
+}
+
+impl_halves! {
+    "b16" => BitSet16(u16), "b8" => BitSet8;
+    "b32" => BitSet32(u32), "b16" => BitSet16;
+    "b64" => BitSet64(u64), "b32" => BitSet32;
+    "b128" => BitSet128(u128), "b64" => BitSet64;
+}
+
+impl_fallible_conversions! {
+    "bsize" => BitSetSize, "b32" => BitSet32;
+    "bsize" => BitSetSize, "b64" => BitSet64;
+    "bsize" => BitSetSize, "b128" => BitSet128;
+    "b32" => BitSet32, "bsize" => BitSetSize;
+    "b64" => BitSet64, "bsize" => BitSetSize;
+    "b128" => BitSet128, "bsize" => BitSetSize;
+}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(all(feature = "b8", feature = "b32"))]
+    #[test]
+    fn widening_and_narrowing() {
+        use crate::{BitSet8, BitSet32};
+
+        let small = BitSet8::from_bits(0b1000_0101);
+        let wide = BitSet32::from(small);
+        assert_eq!(wide, BitSet32::from_bits(0b1000_0101));
+        assert_eq!(BitSet8::try_from(wide), Ok(small));
+        assert_eq!(BitSet8::try_from(BitSet32::new()), Ok(BitSet8::new()));
+
+        let error = BitSet8::try_from(wide | BitSet32::unit(20)).unwrap_err();
+        assert_eq!(error.index(), 20);
+        assert_eq!(error.capacity(), 8);
+        assert_eq!((wide | BitSet32::unit(20)).truncated_to_8(), small);
+    }
+
+    #[cfg(all(feature = "b32", feature = "b64"))]
+    #[test]
+    fn halves() {
+        use crate::{BitSet32, BitSet64};
+
+        let set = BitSet64::from_bits(0x8000_0001_0000_0002);
+        let (low, high) = set.split_halves();
+        assert_eq!(low, BitSet32::from_bits(2));
+        assert_eq!(high, BitSet32::from_bits(0x8000_0001));
+        assert_eq!(BitSet64::concat(low, high), set);
+    }
+
+    #[cfg(all(feature = "b64", feature = "b128"))]
+    #[test]
+    fn halves_128() {
+        use crate::{BitSet64, BitSet128};
+
+        let set = BitSet128::unit(127) | BitSet128::unit(3);
+        assert_eq!(set.split_halves(), (BitSet64::unit(3), BitSet64::unit(63)));
+        assert_eq!(
+            BitSet128::concat(BitSet64::unit(3), BitSet64::unit(63)),
+            set
+        );
+    }
+
+    #[cfg(all(feature = "b8", feature = "b16", feature = "b64"))]
+    #[test]
+    fn lanes() {
+        use crate::{BitSet8, BitSet16, BitSet64};
+
+        let set = BitSet64::from_bits(0x0807_0605_0403_0201);
+        let lanes: [BitSet8; 8] = set.to_lanes();
+        assert!(lanes.iter().zip(1..).all(|(lane, i)| lane.bits() == i));
+        assert_eq!(BitSet64::from_lanes(lanes), set);
+
+        let lanes: [BitSet16; 4] = set.to_lanes();
+        assert_eq!(lanes[3], BitSet16::from_bits(0x0807));
+        assert_eq!(BitSet64::from_lanes(lanes), set);
+    }
+
+    #[cfg(all(feature = "b16", feature = "b64", feature = "bsize"))]
+    #[test]
+    fn pointer_width() {
+        use crate::{BitSet16, BitSet64, BitSetSize};
+
+        assert_eq!(BitSetSize::from(BitSet16::unit(15)), BitSetSize::unit(15));
+        assert_eq!(
+            BitSetSize::try_from(BitSet64::unit(5)),
+            Ok(BitSetSize::unit(5))
+        );
+        assert_eq!(
+            BitSet64::try_from(BitSetSize::all()).map(BitSet64::len),
+            Ok(usize::BITS as _)
+        );
+        if BitSetSize::CAPACITY < 64 {
+            assert!(BitSetSize::try_from(BitSet64::unit(63)).is_err());
+        }
+    }
+}
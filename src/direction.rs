@@ -0,0 +1,423 @@
+/// An order in which the iterators of the `BitSetN` types visit indices.
+///
+/// Every order gives each index a key, and indices are visited from the smallest key to the
+/// largest. Only [`key`](Self::key) has to be implemented; the other methods work out which of the
+/// indices left comes first or last by comparing their keys, and can be overridden when an order
+/// allows for something faster.
+///
+/// The indices left are passed around as the bits of a [`u128`], whatever the width of the set.
+///
+/// The default methods compare the keys of all the indices left every time they take one, so
+/// going through `n` indices calls `key` `O(n^2)` times, and skipping ahead costs as much as
+/// stepping. Orders that can find their first or last index directly should override them, as
+/// all of the orders in this crate do.
+///
+/// ```
+/// use rose_bitsets::{BitSet8, Direction};
+///
+/// /// Visits the even indices before the odd ones.
+/// struct EvensFirst;
+///
+/// impl Direction for EvensFirst {
+///     fn key(index: usize) -> u64 {
+///         ((index as u64 & 1) << 32) | index as u64
+///     }
+/// }
+///
+/// let set = BitSet8::from_bits(0b1011_0110);
+/// assert!(set.iter_indices::<EvensFirst>().eq([2, 4, 1, 5, 7]));
+/// assert!(set.iter_indices::<EvensFirst>().rev().eq([7, 5, 1, 4, 2]));
+/// ```
+pub trait Direction {
+    /// Returns the key that places `index` within the order. No two indices may share a key.
+    fn key(index: usize) -> u64;
+
+    /// Removes the index with the smallest key from `remaining` and returns it, or returns [`None`]
+    /// if `remaining` is empty.
+    fn pop_first(remaining: &mut u128) -> Option<usize> {
+        pop_by_key(remaining, |index| Self::key(index), |key, best| key < best)
+    }
+
+    /// Removes the index with the largest key from `remaining` and returns it, or returns [`None`]
+    /// if `remaining` is empty.
+    fn pop_last(remaining: &mut u128) -> Option<usize> {
+        pop_by_key(remaining, |index| Self::key(index), |key, best| key > best)
+    }
+
+    /// Removes the `n` indices with the smallest keys from `remaining`, or every index if there are
+    /// fewer than `n`.
+    fn skip_first(remaining: &mut u128, n: usize) {
+        for _ in 0..n {
+            if Self::pop_first(remaining).is_none() {
+                break;
+            }
+        }
+    }
+
+    /// Removes the `n` indices with the largest keys from `remaining`, or every index if there are
+    /// fewer than `n`.
+    fn skip_last(remaining: &mut u128, n: usize) {
+        for _ in 0..n {
+            if Self::pop_last(remaining).is_none() {
+                break;
+            }
+        }
+    }
+}
+
+/// An iteration order that starts with the smallest end/items and ends with the largest.
+pub struct Ascending;
+
+impl Direction for Ascending {
+    fn key(index: usize) -> u64 {
+        index as u64
+    }
+
+    #[inline]
+    fn pop_first(remaining: &mut u128) -> Option<usize> {
+        pop_lowest(remaining)
+    }
+
+    #[inline]
+    fn pop_last(remaining: &mut u128) -> Option<usize> {
+        pop_highest(remaining)
+    }
+
+    #[inline]
+    fn skip_first(remaining: &mut u128, n: usize) {
+        skip_lowest(remaining, n);
+    }
+
+    #[inline]
+    fn skip_last(remaining: &mut u128, n: usize) {
+        skip_highest(remaining, n);
+    }
+}
+
+/// An iteration order that starts with the largest end/items and ends with the smallest.
+pub struct Descending;
+
+impl Direction for Descending {
+    fn key(index: usize) -> u64 {
+        !(index as u64)
+    }
+
+    #[inline]
+    fn pop_first(remaining: &mut u128) -> Option<usize> {
+        pop_highest(remaining)
+    }
+
+    #[inline]
+    fn pop_last(remaining: &mut u128) -> Option<usize> {
+        pop_lowest(remaining)
+    }
+
+    #[inline]
+    fn skip_first(remaining: &mut u128, n: usize) {
+        skip_highest(remaining, n);
+    }
+
+    #[inline]
+    fn skip_last(remaining: &mut u128, n: usize) {
+        skip_lowest(remaining, n);
+    }
+}
+
+/// An iteration order that follows the reflected Gray code, so that the binary representations of
+/// consecutive indices in a full set differ in exactly one bit.
+///
+/// ```
+/// use rose_bitsets::{BitSet8, GrayCode};
+///
+/// assert!(BitSet8::all().iter_indices::<GrayCode>().eq([0, 1, 3, 2, 6, 7, 5, 4]));
+/// ```
+pub struct GrayCode;
+
+impl Direction for GrayCode {
+    fn key(index: usize) -> u64 {
+        // An index's position in the sequence is the prefix XOR of its bits, from the top down.
+        let mut key = index as u64;
+        let mut shift = 1;
+        while shift < u64::BITS {
+            key ^= key >> shift;
+            shift *= 2;
+        }
+        key
+    }
+
+    fn pop_first(remaining: &mut u128) -> Option<usize> {
+        pop_gray_code(remaining, false)
+    }
+
+    fn pop_last(remaining: &mut u128) -> Option<usize> {
+        pop_gray_code(remaining, true)
+    }
+}
+
+/// An iteration order that compares indices by their bits in reverse, so each pass visits the
+/// indices halfway between the ones before it.
+///
+/// ```
+/// use rose_bitsets::{BitReversed, BitSet8};
+///
+/// assert!(BitSet8::all().iter_indices::<BitReversed>().eq([0, 4, 2, 6, 1, 5, 3, 7]));
+/// ```
+pub struct BitReversed;
+
+impl Direction for BitReversed {
+    fn key(index: usize) -> u64 {
+        (index as u64).reverse_bits()
+    }
+
+    fn pop_first(remaining: &mut u128) -> Option<usize> {
+        pop_bit_reversed(remaining, false)
+    }
+
+    fn pop_last(remaining: &mut u128) -> Option<usize> {
+        pop_bit_reversed(remaining, true)
+    }
+}
+
+/// A pseudo-random iteration order, which is the same every time for the same `SEED`.
+///
+/// The order comes from hashing each index with a bijective mixer, so it is cheap to set up but
+/// not suitable for anything that has to be unpredictable.
+///
+/// ```
+/// use rose_bitsets::{BitSet8, Shuffled};
+///
+/// let shuffled: Vec<_> = BitSet8::all().iter_indices::<Shuffled<42>>().collect();
+/// assert!(BitSet8::all().iter_indices::<Shuffled<42>>().eq(shuffled.iter().copied()));
+/// assert_eq!(shuffled.iter().copied().collect::<BitSet8>(), BitSet8::all());
+/// ```
+pub struct Shuffled<const SEED: u64>;
+
+impl<const SEED: u64> Direction for Shuffled<SEED> {
+    fn key(index: usize) -> u64 {
+        // The finalizer of SplitMix64. Every step can be undone, so no two indices collide.
+        let mut key = SEED ^ (index as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15);
+        key = (key ^ (key >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        key = (key ^ (key >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        key ^ (key >> 31)
+    }
+}
+
+/// Removes and returns the index in `remaining` whose key beats every other one.
+fn pop_by_key(
+    remaining: &mut u128,
+    key: impl Fn(usize) -> u64,
+    beats: impl Fn(u64, u64) -> bool,
+) -> Option<usize> {
+    let mut bits = *remaining;
+    let mut best: Option<(usize, u64)> = None;
+    while bits != 0 {
+        let index = bits.trailing_zeros() as usize;
+        bits &= bits - 1;
+        let key = key(index);
+        if best.is_none_or(|(_, best)| beats(key, best)) {
+            best = Some((index, key));
+        }
+    }
+    let (index, _) = best?;
+    *remaining ^= 1 << index;
+    Some(index)
+}
+
+fn pop_lowest(remaining: &mut u128) -> Option<usize> {
+    if *remaining == 0 {
+        return None;
+    }
+    let index = remaining.trailing_zeros() as usize;
+    *remaining &= *remaining - 1;
+    Some(index)
+}
+
+fn pop_highest(remaining: &mut u128) -> Option<usize> {
+    if *remaining == 0 {
+        return None;
+    }
+    let index = (u128::BITS - 1 - remaining.leading_zeros()) as usize;
+    *remaining ^= 1 << index;
+    Some(index)
+}
+
+/// For each bit of an index below 128, the indices that have it set.
+const INDEX_BITS: [u128; 7] = {
+    let mut masks = [0; 7];
+    let mut bit = 0;
+    while bit < masks.len() {
+        let width = 1 << bit;
+        masks[bit] = (u128::MAX / ((1 << width) + 1)) << width;
+        bit += 1;
+    }
+    masks
+};
+
+/// Keeps the indices in `candidates` whose `bit` is `set`, or the others if there are none.
+fn narrow(candidates: u128, bit: usize, set: bool) -> u128 {
+    let (ones, zeros) = (candidates & INDEX_BITS[bit], candidates & !INDEX_BITS[bit]);
+    let (wanted, other) = if set { (ones, zeros) } else { (zeros, ones) };
+    if wanted != 0 { wanted } else { other }
+}
+
+/// Removes the index with the smallest Gray code position from `remaining`, or the largest if
+/// `last`, and returns it.
+fn pop_gray_code(remaining: &mut u128, last: bool) -> Option<usize> {
+    if *remaining == 0 {
+        return None;
+    }
+    // Each bit of the position is the parity of the index's bits from there up, so settling the
+    // index's bits from the top down settles the position's bits in order of significance.
+    let mut candidates = *remaining;
+    let mut parity = false;
+    for bit in (0..INDEX_BITS.len()).rev() {
+        candidates = narrow(candidates, bit, parity != last);
+        parity ^= candidates & INDEX_BITS[bit] != 0;
+    }
+    let index = candidates.trailing_zeros() as usize;
+    *remaining ^= 1 << index;
+    Some(index)
+}
+
+/// Removes the index whose reversed bits are the smallest from `remaining`, or the largest if
+/// `last`, and returns it.
+fn pop_bit_reversed(remaining: &mut u128, last: bool) -> Option<usize> {
+    if *remaining == 0 {
+        return None;
+    }
+    // Reversed, the lowest bit of an index is the most significant one.
+    let mut candidates = *remaining;
+    for bit in 0..INDEX_BITS.len() {
+        candidates = narrow(candidates, bit, last);
+    }
+    let index = candidates.trailing_zeros() as usize;
+    *remaining ^= 1 << index;
+    Some(index)
+}
+
+/// Drops the `n` lowest indices.
+fn skip_lowest(remaining: &mut u128, n: usize) {
+    *remaining = match select(*remaining, n) {
+        Some(index) => *remaining & u128::MAX << index,
+        None => 0,
+    };
+}
+
+/// Drops the `n` highest indices.
+fn skip_highest(remaining: &mut u128, n: usize) {
+    let len = remaining.count_ones() as usize;
+    let rank = if n < len { len - 1 - n } else { len };
+    *remaining = match select(*remaining, rank) {
+        Some(index) => *remaining & u128::MAX >> (u128::BITS as usize - 1 - index),
+        None => 0,
+    };
+}
+
+/// Returns the index of the set bit with `rank` set bits below it, or [`None`] if there are not
+/// that many.
+fn select(mut bits: u128, mut rank: usize) -> Option<usize> {
+    if rank >= bits.count_ones() as usize {
+        return None;
+    }
+    // Narrow in on the bit by halves, moving past the lower half whenever it runs out of rank.
+    let mut index = 0;
+    let mut width = u128::BITS / 2;
+    while width > 0 {
+        let below = (bits & (u128::MAX >> (u128::BITS - width))).count_ones() as usize;
+        if rank >= below {
+            rank -= below;
+            bits >>= width;
+            index += width as usize;
+        }
+        width /= 2;
+    }
+    Some(index)
+}
+
+#[cfg(all(test, feature = "b8"))]
+mod tests {
+    use super::*;
+    use crate::BitSet8;
+    use core::marker::PhantomData;
+
+    /// Visits indices in the same order as `D`, but only through the default methods.
+    struct Keyed<D>(PhantomData<D>);
+
+    impl<D: Direction> Direction for Keyed<D> {
+        fn key(index: usize) -> u64 {
+            D::key(index)
+        }
+    }
+
+    /// Checks every method of `D` against the defaults built on the same keys.
+    fn check_overrides<D: Direction>() {
+        let patterns = [0, 1, 0b1011_0110, 1 << 127 | 1 << 64 | 0xff00, u128::MAX];
+        for bits in patterns {
+            for n in [0, 1, 5, 64, 200, usize::MAX] {
+                let (mut expected, mut actual) = (bits, bits);
+                Keyed::<D>::skip_first(&mut expected, n);
+                D::skip_first(&mut actual, n);
+                assert_eq!(actual, expected);
+                Keyed::<D>::skip_last(&mut expected, n);
+                D::skip_last(&mut actual, n);
+                assert_eq!(actual, expected);
+            }
+            let (mut expected, mut actual) = (bits, bits);
+            loop {
+                assert_eq!(
+                    D::pop_last(&mut actual),
+                    Keyed::<D>::pop_last(&mut expected)
+                );
+                let first = D::pop_first(&mut actual);
+                assert_eq!(first, Keyed::<D>::pop_first(&mut expected));
+                if first.is_none() {
+                    break;
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn builtin_overrides_match_defaults() {
+        check_overrides::<Ascending>();
+        check_overrides::<Descending>();
+        check_overrides::<GrayCode>();
+        check_overrides::<BitReversed>();
+    }
+
+    #[test]
+    fn iterators_match_default_methods() {
+        for bits in 0..=u8::MAX {
+            let set = BitSet8::from_bits(bits);
+            let keyed = set.iter_indices::<Keyed<Descending>>();
+            assert!(keyed.clone().eq(set.iter_indices::<Descending>()));
+            assert!(keyed.rev().eq(set.iter_indices::<Ascending>()));
+            for n in 0..=9 {
+                let mut keyed = set.iter_indices::<Keyed<Descending>>();
+                let mut descending = set.iter_indices::<Descending>();
+                assert_eq!(keyed.nth(n), descending.nth(n));
+                assert_eq!(keyed.nth_back(n), descending.nth_back(n));
+                assert!(keyed.eq(descending));
+            }
+        }
+    }
+
+    #[test]
+    fn select_finds_each_rank() {
+        let bits = 0x8000_0000_0001_0000_0000_0000_0000_0105_u128;
+        let indices = [0, 2, 8, 80, 127];
+        for (rank, &index) in indices.iter().enumerate() {
+            assert_eq!(select(bits, rank), Some(index));
+        }
+        assert_eq!(select(bits, indices.len()), None);
+        assert_eq!(select(0, 0), None);
+    }
+
+    #[test]
+    fn gray_code_keys_are_positions() {
+        for position in 0..128_u64 {
+            let index = position ^ (position >> 1);
+            assert_eq!(GrayCode::key(index as usize), position);
+        }
+    }
+}
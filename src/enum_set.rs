@@ -0,0 +1,505 @@
+use crate::BitSet;
+use core::{
+    fmt,
+    hash::{Hash, Hasher},
+    iter::FusedIterator,
+    ops::{BitAnd, BitAndAssign, BitOr, BitOrAssign, BitXor, BitXorAssign, Not, Sub, SubAssign},
+};
+
+/// A fieldless enum whose variants can be stored in an [`EnumSet`].
+///
+/// This should be implemented with `#[derive(EnumSetType)]`, which numbers the variants in
+/// declaration order and picks the smallest `BitSetN` that can hold all of them. The chosen
+/// width's feature must be enabled (e.g., an enum with more than 64 variants requires `b128`).
+///
+/// ```
+/// use rose_bitsets::{EnumSet, EnumSetType};
+///
+/// #[derive(Clone, Copy, Debug, EnumSetType, Eq, PartialEq)]
+/// enum Weekday {
+///     Monday,
+///     Tuesday,
+///     Wednesday,
+///     Thursday,
+///     Friday,
+/// }
+///
+/// let mut days = EnumSet::new();
+/// days.insert_quiet(Weekday::Friday);
+/// days.insert_quiet(Weekday::Monday);
+///
+/// assert!(days.contains(Weekday::Monday));
+/// assert!(!days.contains(Weekday::Tuesday));
+/// assert!(days.iter().eq([Weekday::Monday, Weekday::Friday]));
+/// assert_eq!(format!("{days:?}"), "{Monday, Friday}");
+/// ```
+pub trait EnumSetType: Copy + Eq {
+    /// The bitset used to store the variants.
+    type Repr: BitSet;
+
+    /// The number of variants in the enum.
+    const VARIANT_COUNT: usize;
+
+    /// Returns the index of the variant within the enum's declaration.
+    fn to_index(self) -> usize;
+
+    /// Returns the variant declared at `index`, or [`None`] if there is no such variant.
+    fn from_index(index: usize) -> Option<Self>;
+}
+
+/// A set of variants of a fieldless enum, stored as a bitset.
+pub struct EnumSet<T: EnumSetType> {
+    repr: T::Repr,
+}
+
+impl<T: EnumSetType> EnumSet<T> {
+    /// Returns the set of all indices that correspond to a variant.
+    fn universe() -> T::Repr {
+        <T::Repr as BitSet>::all().masked_0_to_i(T::VARIANT_COUNT)
+    }
+
+    /// Creates an empty set.
+    #[doc(alias = "empty")]
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            repr: T::Repr::new(),
+        }
+    }
+
+    /// Creates a set containing only `variant`.
+    #[must_use]
+    pub fn unit(variant: T) -> Self {
+        Self {
+            repr: T::Repr::unit(variant.to_index()),
+        }
+    }
+
+    /// Creates a set containing every variant of the enum.
+    #[must_use]
+    pub fn all() -> Self {
+        Self {
+            repr: Self::universe(),
+        }
+    }
+
+    /// Creates a set from the underlying bitset, discarding any bits that don't correspond to a
+    /// variant.
+    #[must_use]
+    pub fn from_repr(repr: T::Repr) -> Self {
+        Self {
+            repr: repr.intersection(Self::universe()),
+        }
+    }
+
+    /// Returns the underlying bitset, where each variant is stored at its index.
+    #[must_use]
+    pub fn repr(self) -> T::Repr {
+        self.repr
+    }
+
+    /// Creates a new set with every variant that is not in `self`.
+    #[doc(alias = "inverse")]
+    #[must_use]
+    pub fn complement(self) -> Self {
+        Self {
+            repr: Self::universe().difference(self.repr),
+        }
+    }
+
+    /// Creates a new set with variants that are in both `self` and `rhs`.
+    #[must_use]
+    pub fn intersection(self, rhs: Self) -> Self {
+        Self {
+            repr: self.repr.intersection(rhs.repr),
+        }
+    }
+
+    /// Creates a new set with variants that are in `self` or `rhs`.
+    #[must_use]
+    pub fn union(self, rhs: Self) -> Self {
+        Self {
+            repr: self.repr.union(rhs.repr),
+        }
+    }
+
+    /// Creates a new set with variants that are in `self`, but not in `rhs`.
+    #[must_use]
+    pub fn difference(self, rhs: Self) -> Self {
+        Self {
+            repr: self.repr.difference(rhs.repr),
+        }
+    }
+
+    /// Creates a new set with variants that are in `self` or `rhs`, but not in both.
+    #[must_use]
+    pub fn symmetric_difference(self, rhs: Self) -> Self {
+        Self {
+            repr: self.repr.symmetric_difference(rhs.repr),
+        }
+    }
+
+    /// Returns `true` if `self` has no variants in common with `rhs`.
+    #[must_use]
+    pub fn is_disjoint(self, rhs: Self) -> bool {
+        self.repr.is_disjoint(rhs.repr)
+    }
+
+    /// Returns `true` if `rhs` contains at least all the variants in `self`.
+    #[must_use]
+    pub fn is_subset(self, rhs: Self) -> bool {
+        self.repr.is_subset(rhs.repr)
+    }
+
+    /// Returns `true` if `rhs` contains all the variants in `self` **and** is larger than `self`.
+    #[must_use]
+    pub fn is_strict_subset(self, rhs: Self) -> bool {
+        self.repr.is_strict_subset(rhs.repr)
+    }
+
+    /// Returns `true` if `self` contains at least all the variants in `rhs`.
+    #[must_use]
+    pub fn is_superset(self, rhs: Self) -> bool {
+        self.repr.is_superset(rhs.repr)
+    }
+
+    /// Returns `true` if `self` contains all the variants in `rhs` **and** is larger than `rhs`.
+    #[must_use]
+    pub fn is_strict_superset(self, rhs: Self) -> bool {
+        self.repr.is_strict_superset(rhs.repr)
+    }
+
+    /// Returns `true` if the set contains no variants.
+    #[must_use]
+    pub fn is_empty(self) -> bool {
+        self.repr.is_empty()
+    }
+
+    /// Returns `true` if the set contains every variant of the enum.
+    #[must_use]
+    pub fn is_full(self) -> bool {
+        self.repr == Self::universe()
+    }
+
+    /// Returns the number of variants in the set.
+    #[must_use]
+    pub fn len(self) -> usize {
+        self.repr.len()
+    }
+
+    /// Returns `true` if the set contains `variant`.
+    #[must_use]
+    pub fn contains(self, variant: T) -> bool {
+        self.repr.contains(variant.to_index())
+    }
+
+    /// Clears the set, removing all variants.
+    pub fn clear(&mut self) {
+        self.repr.clear();
+    }
+
+    /// Adds `variant` to the set.
+    ///
+    /// If you would like to know if the insertion succeeded, use [`insert`](Self::insert)
+    /// instead.
+    pub fn insert_quiet(&mut self, variant: T) {
+        self.repr.insert_quiet(variant.to_index());
+    }
+
+    /// Adds `variant` to the set. Returns whether the variant was not already present.
+    ///
+    /// If the return value is not needed, use [`insert_quiet`](Self::insert_quiet) instead.
+    #[must_use = "consider using the return value or calling `insert_quiet` instead"]
+    pub fn insert(&mut self, variant: T) -> bool {
+        self.repr.insert(variant.to_index())
+    }
+
+    /// Removes `variant` from the set.
+    ///
+    /// If you would like to know if the removal succeeded, use [`remove`](Self::remove)
+    /// instead.
+    pub fn remove_quiet(&mut self, variant: T) {
+        self.repr.remove_quiet(variant.to_index());
+    }
+
+    /// Removes `variant` from the set. Returns whether the variant was present.
+    ///
+    /// If the return value is not needed, use [`remove_quiet`](Self::remove_quiet) instead.
+    #[must_use = "consider using the return value or calling `remove_quiet` instead"]
+    pub fn remove(&mut self, variant: T) -> bool {
+        self.repr.remove(variant.to_index())
+    }
+
+    /// Creates an iterator over the variants in the set, in declaration order.
+    #[must_use]
+    pub fn iter(self) -> EnumSetIter<T> {
+        EnumSetIter {
+            remaining: self.repr,
+        }
+    }
+}
+
+/// An iterator over the variants in an [`EnumSet`], in declaration order.
+pub struct EnumSetIter<T: EnumSetType> {
+    remaining: T::Repr,
+}
+
+impl<T: EnumSetType> Iterator for EnumSetIter<T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining.is_empty() {
+            return None;
+        }
+        let index = self.remaining.min_index();
+        self.remaining.remove_quiet(index);
+        T::from_index(index)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.remaining.len();
+        (len, Some(len))
+    }
+}
+
+impl<T: EnumSetType> ExactSizeIterator for EnumSetIter<T> {}
+
+impl<T: EnumSetType> FusedIterator for EnumSetIter<T> {}
+
+impl<T: EnumSetType> Clone for EnumSetIter<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T: EnumSetType> Copy for EnumSetIter<T> {}
+
+impl<T: EnumSetType> IntoIterator for EnumSet<T> {
+    type Item = T;
+    type IntoIter = EnumSetIter<T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<T: EnumSetType> FromIterator<T> for EnumSet<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut set = Self::new();
+        set.extend(iter);
+        set
+    }
+}
+
+impl<T: EnumSetType> Extend<T> for EnumSet<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for variant in iter {
+            self.insert_quiet(variant);
+        }
+    }
+}
+
+impl<T: EnumSetType> From<T> for EnumSet<T> {
+    fn from(variant: T) -> Self {
+        Self::unit(variant)
+    }
+}
+
+impl<T: EnumSetType> Clone for EnumSet<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T: EnumSetType> Copy for EnumSet<T> {}
+
+impl<T: EnumSetType> Default for EnumSet<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: EnumSetType> PartialEq for EnumSet<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.repr == other.repr
+    }
+}
+
+impl<T: EnumSetType> Eq for EnumSet<T> {}
+
+impl<T: EnumSetType> Hash for EnumSet<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.repr.hash(state);
+    }
+}
+
+impl<T: EnumSetType + fmt::Debug> fmt::Debug for EnumSet<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.iter()).finish()
+    }
+}
+
+impl<T: EnumSetType> BitAnd for EnumSet<T> {
+    type Output = Self;
+
+    fn bitand(self, rhs: Self) -> Self::Output {
+        self.intersection(rhs)
+    }
+}
+
+impl<T: EnumSetType> BitAndAssign for EnumSet<T> {
+    fn bitand_assign(&mut self, rhs: Self) {
+        *self = *self & rhs;
+    }
+}
+
+impl<T: EnumSetType> BitOr for EnumSet<T> {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self::Output {
+        self.union(rhs)
+    }
+}
+
+impl<T: EnumSetType> BitOrAssign for EnumSet<T> {
+    fn bitor_assign(&mut self, rhs: Self) {
+        *self = *self | rhs;
+    }
+}
+
+impl<T: EnumSetType> BitXor for EnumSet<T> {
+    type Output = Self;
+
+    fn bitxor(self, rhs: Self) -> Self::Output {
+        self.symmetric_difference(rhs)
+    }
+}
+
+impl<T: EnumSetType> BitXorAssign for EnumSet<T> {
+    fn bitxor_assign(&mut self, rhs: Self) {
+        *self = *self ^ rhs;
+    }
+}
+
+impl<T: EnumSetType> Not for EnumSet<T> {
+    type Output = Self;
+
+    fn not(self) -> Self::Output {
+        self.complement()
+    }
+}
+
+impl<T: EnumSetType> Sub for EnumSet<T> {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        self.difference(rhs)
+    }
+}
+
+impl<T: EnumSetType> SubAssign for EnumSet<T> {
+    fn sub_assign(&mut self, rhs: Self) {
+        *self = *self - rhs;
+    }
+}
+
+#[cfg(all(test, feature = "b8", feature = "b16"))]
+mod tests {
+    use super::*;
+    use crate::EnumSetType;
+
+    #[derive(Clone, Copy, Debug, EnumSetType, Eq, PartialEq)]
+    enum Color {
+        Red,
+        Green,
+        Blue,
+    }
+
+    #[derive(Clone, Copy, Debug, EnumSetType, Eq, PartialEq)]
+    enum Byte {
+        B0,
+        B1,
+        B2,
+        B3,
+        B4,
+        B5,
+        B6,
+        B7,
+    }
+
+    #[derive(Clone, Copy, Debug, EnumSetType, Eq, PartialEq)]
+    enum Nine {
+        N0 = 10,
+        N1,
+        N2,
+        N3,
+        N4,
+        N5,
+        N6,
+        N7,
+        N8 = 100,
+    }
+
+    #[test]
+    fn smallest_repr_is_chosen() {
+        assert_eq!(size_of::<EnumSet<Color>>(), 1);
+        assert_eq!(size_of::<EnumSet<Byte>>(), 1);
+        assert_eq!(size_of::<EnumSet<Nine>>(), 2);
+        assert_eq!(Nine::VARIANT_COUNT, 9);
+    }
+
+    #[test]
+    fn indices_follow_declaration_order() {
+        assert_eq!(Nine::N0.to_index(), 0);
+        assert_eq!(Nine::N8.to_index(), 8);
+        assert_eq!(Nine::from_index(1), Some(Nine::N1));
+        assert_eq!(Nine::from_index(9), None);
+    }
+
+    #[test]
+    fn insert_and_remove() {
+        let mut set = EnumSet::new();
+        assert!(set.insert(Color::Blue));
+        assert!(!set.insert(Color::Blue));
+        assert!(set.contains(Color::Blue));
+        assert!(!set.contains(Color::Red));
+        assert!(set.remove(Color::Blue));
+        assert!(!set.remove(Color::Blue));
+        assert!(set.is_empty());
+    }
+
+    #[test]
+    fn all_only_has_variants() {
+        assert_eq!(EnumSet::<Color>::all().len(), 3);
+        assert!(EnumSet::<Color>::all().is_full());
+        assert_eq!(EnumSet::<Byte>::all().len(), 8);
+        assert!(EnumSet::<Byte>::new().complement().is_full());
+        assert_eq!(EnumSet::<Nine>::from_repr(crate::BitSet16::all()).len(), 9);
+    }
+
+    #[test]
+    fn set_algebra() {
+        let warm: EnumSet<Color> = [Color::Red, Color::Green].into_iter().collect();
+        let cool: EnumSet<Color> = [Color::Green, Color::Blue].into_iter().collect();
+        assert!((warm & cool).iter().eq([Color::Green]));
+        assert!((warm | cool).is_full());
+        assert!((warm - cool).iter().eq([Color::Red]));
+        assert!((warm ^ cool).iter().eq([Color::Red, Color::Blue]));
+        assert_eq!(!warm, EnumSet::from(Color::Blue));
+        assert!(EnumSet::from(Color::Green).is_strict_subset(warm));
+    }
+
+    #[test]
+    fn iteration() {
+        let set: EnumSet<Nine> = [Nine::N8, Nine::N0, Nine::N4].into_iter().collect();
+        let iter = set.iter();
+        assert_eq!(iter.len(), 3);
+        assert!(iter.eq([Nine::N0, Nine::N4, Nine::N8]));
+    }
+
+    #[test]
+    fn debug() {
+        let set: EnumSet<Color> = [Color::Blue, Color::Red].into_iter().collect();
+        assert_eq!(format!("{set:?}"), "{Red, Blue}");
+    }
+}
@@ -0,0 +1,121 @@
+use core::{fmt, ops::Range};
+
+/// The error returned when converting a set into a type that cannot hold all of its members.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct CapacityError {
+    index: usize,
+    capacity: usize,
+}
+
+impl CapacityError {
+    /// Creates an error for a set whose member `index` does not fit within `capacity`.
+    #[allow(dead_code, reason = "only used once two widths or `alloc` are enabled")]
+    pub(crate) const fn new(index: usize, capacity: usize) -> Self {
+        Self { index, capacity }
+    }
+
+    /// Returns the largest member of the set that failed to convert.
+    #[must_use]
+    pub const fn index(&self) -> usize {
+        self.index
+    }
+
+    /// Returns the capacity of the type the set was being converted into.
+    #[must_use]
+    pub const fn capacity(&self) -> usize {
+        self.capacity
+    }
+}
+
+impl fmt::Display for CapacityError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "index {} does not fit in a set with a capacity of {}",
+            self.index, self.capacity,
+        )
+    }
+}
+
+impl core::error::Error for CapacityError {}
+
+/// The error returned when a value falls outside of the range an
+/// [`OffsetBitSet`](crate::OffsetBitSet) can hold.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct OutOfRangeError {
+    value: i64,
+    start: i64,
+    end: i64,
+}
+
+impl OutOfRangeError {
+    /// Creates an error for a `value` that is not in `range`.
+    pub(crate) const fn new(value: i64, range: Range<i64>) -> Self {
+        Self {
+            value,
+            start: range.start,
+            end: range.end,
+        }
+    }
+
+    /// Returns the value that was out of range.
+    #[must_use]
+    pub const fn value(&self) -> i64 {
+        self.value
+    }
+
+    /// Returns the range of values the set can hold.
+    #[must_use]
+    pub const fn range(&self) -> Range<i64> {
+        self.start..self.end
+    }
+}
+
+impl fmt::Display for OutOfRangeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "value {} is outside of the range {}..{}",
+            self.value, self.start, self.end,
+        )
+    }
+}
+
+impl core::error::Error for OutOfRangeError {}
+
+/// The error returned when bytes are not a valid [`RoaringBitmap`](crate::RoaringBitmap) in the
+/// portable serialization format.
+#[cfg(feature = "roaring")]
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[non_exhaustive]
+pub enum RoaringFormatError {
+    /// The bytes ended partway through the set.
+    UnexpectedEnd,
+    /// The bytes did not start with either of the cookies the format allows.
+    UnknownCookie(u32),
+    /// There were too many containers, or their keys were not in ascending order.
+    InvalidHeader,
+    /// The contents of the container with the given key did not match its header, or were not in
+    /// ascending order.
+    InvalidContainer(u16),
+    /// There were this many bytes left over after the set.
+    TrailingBytes(usize),
+}
+
+#[cfg(feature = "roaring")]
+impl fmt::Display for RoaringFormatError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnexpectedEnd => write!(f, "unexpected end of serialized bitmap"),
+            Self::UnknownCookie(cookie) => write!(f, "unknown cookie {cookie:#010x}"),
+            Self::InvalidHeader => write!(f, "invalid container header"),
+            Self::InvalidContainer(key) => write!(f, "invalid container with key {key}"),
+            Self::TrailingBytes(count) => {
+                write!(f, "{count} trailing bytes after serialized bitmap")
+            }
+        }
+    }
+}
+
+#[cfg(feature = "roaring")]
+impl core::error::Error for RoaringFormatError {}
@@ -0,0 +1,606 @@
+use crate::{Ascending, BitSet64, Descending};
+use alloc::vec::Vec;
+use core::{
+    fmt,
+    hash::{Hash, Hasher},
+    marker::PhantomData,
+};
+
+/// The number of levels in a hierarchical bitset, including the one holding the members.
+const LEVELS: usize = 4;
+/// The level made of a single word, which every search starts from.
+const TOP: usize = LEVELS - 1;
+
+/// A set whose members are summarized by layers of [`BitSet64`]s, in the style of a
+/// [hierarchical bitset].
+///
+/// Level 0 is made of the words holding the members themselves. Every other level has one bit
+/// for each word in the level below it, and the top level is a single word. A bit that is clear
+/// above level 0 promises that the word it stands for is empty, so searches can skip over it.
+///
+/// Implementing this trait gives a type the same searching and iteration as a
+/// [`HierarchicalBitSet`], which is what lets lazy views like [`LazyIntersection`] and
+/// [`LazyUnion`] be combined without materializing them.
+pub trait HierarchicalLayers {
+    /// Returns the word at `position` within `level`.
+    ///
+    /// Above level 0, bits may be set for words that turn out to be empty, but never the other
+    /// way around.
+    fn word(&self, level: usize, position: usize) -> BitSet64;
+
+    /// Returns `true` if `index` is in the set.
+    #[must_use]
+    fn contains(&self, index: usize) -> bool {
+        index < HierarchicalBitSet::CAPACITY && self.word(0, index / 64).contains(index % 64)
+    }
+
+    /// Returns `true` if the set contains no elements.
+    #[must_use]
+    fn is_empty(&self) -> bool {
+        self.min_index_checked().is_none()
+    }
+
+    /// Returns the smallest member of the set, or [`None`] if the set is empty.
+    #[must_use]
+    fn min_index_checked(&self) -> Option<usize> {
+        first_at_or_after(self, TOP, 0, Some(0))
+    }
+
+    /// Returns the largest member of the set, or [`None`] if the set is empty.
+    #[must_use]
+    fn max_index_checked(&self) -> Option<usize> {
+        last_at_or_before(self, TOP, 0, Some(HierarchicalBitSet::CAPACITY - 1))
+    }
+
+    /// Returns the smallest member of the set that is greater than `index`, if there is one.
+    #[must_use]
+    fn next_after(&self, index: usize) -> Option<usize> {
+        let start = index
+            .checked_add(1)
+            .filter(|&start| start < HierarchicalBitSet::CAPACITY)?;
+        first_at_or_after(self, TOP, 0, Some(start))
+    }
+
+    /// Returns the largest member of the set that is less than `index`, if there is one.
+    #[must_use]
+    fn prev_before(&self, index: usize) -> Option<usize> {
+        let end = index.checked_sub(1)?.min(HierarchicalBitSet::CAPACITY - 1);
+        last_at_or_before(self, TOP, 0, Some(end))
+    }
+
+    /// Creates an iterator over the members of the set.
+    #[must_use]
+    fn iter_indices<D>(&self) -> HierarchicalIndices<'_, Self, D>
+    where
+        for<'a> HierarchicalIndices<'a, Self, D>: Iterator<Item = usize>,
+    {
+        HierarchicalIndices::new(self)
+    }
+
+    /// Creates a view of the values that are in both `self` and `rhs`, without computing it up
+    /// front.
+    #[must_use]
+    fn lazy_intersection<'a, R>(&'a self, rhs: &'a R) -> LazyIntersection<&'a Self, &'a R>
+    where
+        Self: Sized,
+        R: HierarchicalLayers,
+    {
+        LazyIntersection::new(self, rhs)
+    }
+
+    /// Creates a view of the values that are in `self` or `rhs`, without computing it up front.
+    #[must_use]
+    fn lazy_union<'a, R>(&'a self, rhs: &'a R) -> LazyUnion<&'a Self, &'a R>
+    where
+        Self: Sized,
+        R: HierarchicalLayers,
+    {
+        LazyUnion::new(self, rhs)
+    }
+}
+
+impl<T: HierarchicalLayers + ?Sized> HierarchicalLayers for &T {
+    fn word(&self, level: usize, position: usize) -> BitSet64 {
+        T::word(self, level, position)
+    }
+}
+
+/// Returns the smallest member below the bit at `position` within `level` that is at least
+/// `start`, where `start` is [`None`] if the whole subtree is in range.
+fn first_at_or_after<S: HierarchicalLayers + ?Sized>(
+    set: &S,
+    level: usize,
+    position: usize,
+    start: Option<usize>,
+) -> Option<usize> {
+    let min_bit = start.map_or(0, |start| (start >> (6 * level)) % 64);
+    let mut bits = set.word(level, position).bits() & (u64::MAX << min_bit);
+    while bits != 0 {
+        let bit = bits.trailing_zeros() as usize;
+        bits &= bits - 1;
+        let child = 64 * position + bit;
+        if level == 0 {
+            return Some(child);
+        }
+        let child_start = start.filter(|_| bit == min_bit);
+        // Upper levels may overestimate, so an empty child just means moving on to the next one.
+        if let Some(index) = first_at_or_after(set, level - 1, child, child_start) {
+            return Some(index);
+        }
+    }
+    None
+}
+
+/// Returns the largest member below the bit at `position` within `level` that is at most `end`,
+/// where `end` is [`None`] if the whole subtree is in range.
+fn last_at_or_before<S: HierarchicalLayers + ?Sized>(
+    set: &S,
+    level: usize,
+    position: usize,
+    end: Option<usize>,
+) -> Option<usize> {
+    let max_bit = end.map_or(63, |end| (end >> (6 * level)) % 64);
+    let mut bits = set.word(level, position).bits() & (u64::MAX >> (63 - max_bit));
+    while bits != 0 {
+        let bit = bits.ilog2() as usize;
+        bits ^= 1 << bit;
+        let child = 64 * position + bit;
+        if level == 0 {
+            return Some(child);
+        }
+        let child_end = end.filter(|_| bit == max_bit);
+        if let Some(index) = last_at_or_before(set, level - 1, child, child_end) {
+            return Some(index);
+        }
+    }
+    None
+}
+
+/// A set of up to 2<sup>24</sup> indices that can skip over empty regions, in the style of a
+/// [hierarchical bitset].
+///
+/// On top of the words holding the members, the set keeps three levels of [`BitSet64`]s that
+/// record which words below them are non-empty. Inserting, removing, and looking up an index each
+/// touch at most one word per level, and searching for the next member skips any empty stretch
+/// of 64, 4096, or 262144 indices in one step. Storage only grows as far as the largest index
+/// inserted so far.
+///
+/// ```
+/// use rose_bitsets::{Ascending, HierarchicalBitSet, HierarchicalLayers};
+///
+/// let mut set = HierarchicalBitSet::new();
+/// set.insert_quiet(3);
+/// set.insert_quiet(5_000_000);
+///
+/// assert_eq!(set.next_after(3), Some(5_000_000));
+/// assert!(set.iter_indices::<Ascending>().eq([3, 5_000_000]));
+///
+/// let other: HierarchicalBitSet = [5, 5_000_000].into_iter().collect();
+/// assert!(set.lazy_intersection(&other).iter_indices::<Ascending>().eq([5_000_000]));
+/// ```
+///
+/// [hierarchical bitset]: https://docs.rs/hibitset
+#[derive(Clone, Default)]
+pub struct HierarchicalBitSet {
+    /// The words of each level, from the members up to the top.
+    levels: [Vec<BitSet64>; LEVELS],
+    len: usize,
+}
+
+impl HierarchicalBitSet {
+    /// The number of indices the set can hold.
+    pub const CAPACITY: usize = 1 << (6 * LEVELS);
+
+    /// Creates an empty set.
+    #[doc(alias = "empty")]
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            levels: [Vec::new(), Vec::new(), Vec::new(), Vec::new()],
+            len: 0,
+        }
+    }
+
+    /// Returns the number of elements in the set.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if the set contains no elements.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns `true` if `index` is in the set.
+    #[must_use]
+    pub fn contains(&self, index: usize) -> bool {
+        HierarchicalLayers::contains(self, index)
+    }
+
+    /// Clears the set, removing all values.
+    ///
+    /// This keeps the allocated storage around for later use.
+    pub fn clear(&mut self) {
+        for level in &mut self.levels {
+            level.fill(BitSet64::new());
+        }
+        self.len = 0;
+    }
+
+    /// Adds `index` to the set.
+    ///
+    /// If you would like to know if the insertion succeeded, use [`insert`](Self::insert)
+    /// instead.
+    pub fn insert_quiet(&mut self, index: usize) {
+        let _ = self.insert(index);
+    }
+
+    /// Adds `index` to the set. Returns whether the index was not already present.
+    ///
+    /// Like the fixed-size bitsets, this does nothing if `index` is out of range.
+    ///
+    /// If the return value is not needed, use [`insert_quiet`](Self::insert_quiet) instead.
+    #[must_use = "consider using the return value or calling `insert_quiet` instead"]
+    pub fn insert(&mut self, index: usize) -> bool {
+        if index >= Self::CAPACITY || self.contains(index) {
+            return false;
+        }
+        for (level, words) in self.levels.iter_mut().enumerate() {
+            let position = index >> (6 * (level + 1));
+            if position >= words.len() {
+                words.resize(position + 1, BitSet64::new());
+            }
+            // Once a word was already non-empty, every level above it already knows.
+            if !words[position].insert((index >> (6 * level)) % 64) && level > 0 {
+                break;
+            }
+        }
+        self.len += 1;
+        true
+    }
+
+    /// Removes `index` from the set.
+    ///
+    /// If you would like to know if the removal succeeded, use [`remove`](Self::remove)
+    /// instead.
+    pub fn remove_quiet(&mut self, index: usize) {
+        let _ = self.remove(index);
+    }
+
+    /// Removes `index` from the set. Returns whether the index was present.
+    ///
+    /// If the return value is not needed, use [`remove_quiet`](Self::remove_quiet) instead.
+    #[must_use = "consider using the return value or calling `remove_quiet` instead"]
+    pub fn remove(&mut self, index: usize) -> bool {
+        if !self.contains(index) {
+            return false;
+        }
+        for (level, words) in self.levels.iter_mut().enumerate() {
+            let word = &mut words[index >> (6 * (level + 1))];
+            word.remove_quiet((index >> (6 * level)) % 64);
+            if !word.is_empty() {
+                break;
+            }
+        }
+        self.len -= 1;
+        true
+    }
+
+    /// Returns the smallest member of the set that is greater than `index`, if there is one.
+    #[must_use]
+    pub fn next_after(&self, index: usize) -> Option<usize> {
+        HierarchicalLayers::next_after(self, index)
+    }
+
+    /// Returns the largest member of the set that is less than `index`, if there is one.
+    #[must_use]
+    pub fn prev_before(&self, index: usize) -> Option<usize> {
+        HierarchicalLayers::prev_before(self, index)
+    }
+
+    /// Creates an iterator over the members of the set.
+    #[must_use]
+    pub fn iter_indices<D>(&self) -> HierarchicalIndices<'_, Self, D>
+    where
+        for<'a> HierarchicalIndices<'a, Self, D>: Iterator<Item = usize>,
+    {
+        HierarchicalIndices::new(self)
+    }
+
+    /// Returns the members' words without any trailing empty ones.
+    fn trimmed(&self) -> &[BitSet64] {
+        let words = &self.levels[0];
+        let len = words
+            .iter()
+            .rposition(|word| !word.is_empty())
+            .map_or(0, |i| i + 1);
+        &words[..len]
+    }
+}
+
+impl HierarchicalLayers for HierarchicalBitSet {
+    fn word(&self, level: usize, position: usize) -> BitSet64 {
+        self.levels[level]
+            .get(position)
+            .copied()
+            .unwrap_or_default()
+    }
+}
+
+/// A view of the values that are in both of two hierarchical sets.
+///
+/// Each level is the intersection of the sets' levels, so nothing is computed until the view is
+/// searched or iterated.
+#[derive(Clone, Copy, Debug)]
+pub struct LazyIntersection<A, B> {
+    lhs: A,
+    rhs: B,
+}
+
+impl<A: HierarchicalLayers, B: HierarchicalLayers> LazyIntersection<A, B> {
+    /// Creates a view of the values that are in both `lhs` and `rhs`.
+    pub const fn new(lhs: A, rhs: B) -> Self {
+        Self { lhs, rhs }
+    }
+}
+
+impl<A: HierarchicalLayers, B: HierarchicalLayers> HierarchicalLayers for LazyIntersection<A, B> {
+    fn word(&self, level: usize, position: usize) -> BitSet64 {
+        self.lhs.word(level, position) & self.rhs.word(level, position)
+    }
+}
+
+/// A view of the values that are in either of two hierarchical sets.
+///
+/// Each level is the union of the sets' levels, so nothing is computed until the view is
+/// searched or iterated.
+#[derive(Clone, Copy, Debug)]
+pub struct LazyUnion<A, B> {
+    lhs: A,
+    rhs: B,
+}
+
+impl<A: HierarchicalLayers, B: HierarchicalLayers> LazyUnion<A, B> {
+    /// Creates a view of the values that are in `lhs` or `rhs`.
+    pub const fn new(lhs: A, rhs: B) -> Self {
+        Self { lhs, rhs }
+    }
+}
+
+impl<A: HierarchicalLayers, B: HierarchicalLayers> HierarchicalLayers for LazyUnion<A, B> {
+    fn word(&self, level: usize, position: usize) -> BitSet64 {
+        self.lhs.word(level, position) | self.rhs.word(level, position)
+    }
+}
+
+/// An iterator over the members of a [`HierarchicalLayers`] implementor, such as a
+/// [`HierarchicalBitSet`].
+///
+/// It walks the layers from one end, so `D` has to be [`Ascending`] or [`Descending`] rather than
+/// a custom [`Direction`](crate::Direction).
+pub struct HierarchicalIndices<'a, S: ?Sized, D = Ascending> {
+    set: &'a S,
+    /// The indices that have yet to be searched, from `front` up to but excluding `back`.
+    front: usize,
+    back: usize,
+    _marker: PhantomData<D>,
+}
+
+impl<'a, S: HierarchicalLayers + ?Sized, D> HierarchicalIndices<'a, S, D> {
+    /// Creates an iterator over the members of `set`.
+    pub fn new(set: &'a S) -> Self {
+        Self {
+            set,
+            front: 0,
+            back: HierarchicalBitSet::CAPACITY,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<S: HierarchicalLayers + ?Sized> Iterator for HierarchicalIndices<'_, S, Ascending> {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.front >= self.back {
+            return None;
+        }
+        let index = first_at_or_after(self.set, TOP, 0, Some(self.front))
+            .filter(|&index| index < self.back);
+        self.front = index.map_or(self.back, |index| index + 1);
+        index
+    }
+}
+
+impl<S: HierarchicalLayers + ?Sized> Iterator for HierarchicalIndices<'_, S, Descending> {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.back <= self.front {
+            return None;
+        }
+        let index = last_at_or_before(self.set, TOP, 0, Some(self.back - 1))
+            .filter(|&index| index >= self.front);
+        self.back = index.unwrap_or(self.front);
+        index
+    }
+}
+
+impl fmt::Debug for HierarchicalBitSet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set()
+            .entries(self.iter_indices::<Ascending>())
+            .finish()
+    }
+}
+
+impl PartialEq for HierarchicalBitSet {
+    fn eq(&self, other: &Self) -> bool {
+        // The upper levels follow from the members, so they don't need to be compared.
+        self.trimmed() == other.trimmed()
+    }
+}
+
+impl Eq for HierarchicalBitSet {}
+
+impl Hash for HierarchicalBitSet {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.trimmed().hash(state);
+    }
+}
+
+impl FromIterator<usize> for HierarchicalBitSet {
+    fn from_iter<I: IntoIterator<Item = usize>>(iter: I) -> Self {
+        let mut set = Self::new();
+        set.extend(iter);
+        set
+    }
+}
+
+impl Extend<usize> for HierarchicalBitSet {
+    fn extend<I: IntoIterator<Item = usize>>(&mut self, iter: I) {
+        for index in iter {
+            self.insert_quiet(index);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeSet;
+
+    /// A small, deterministic pseudo-random sequence, so the tests don't need any dependencies.
+    fn pseudo_random(seed: u64, count: usize, modulus: usize) -> Vec<usize> {
+        let mut state = seed;
+        (0..count)
+            .map(|_| {
+                state = state
+                    .wrapping_mul(6364136223846793005)
+                    .wrapping_add(1442695040888963407);
+                (state >> 32) as usize % modulus
+            })
+            .collect()
+    }
+
+    fn assert_matches<S: HierarchicalLayers>(set: &S, reference: &BTreeSet<usize>) {
+        assert!(
+            set.iter_indices::<Ascending>()
+                .eq(reference.iter().copied())
+        );
+        assert!(
+            set.iter_indices::<Descending>()
+                .eq(reference.iter().rev().copied())
+        );
+        assert_eq!(set.min_index_checked(), reference.first().copied());
+        assert_eq!(set.max_index_checked(), reference.last().copied());
+    }
+
+    #[test]
+    fn new_set_is_empty() {
+        let set = HierarchicalBitSet::new();
+        assert!(set.is_empty());
+        assert_eq!(set.len(), 0);
+        assert_eq!(set.min_index_checked(), None);
+        assert_eq!(set.max_index_checked(), None);
+        assert_eq!(set.next_after(0), None);
+        assert_eq!(set.prev_before(HierarchicalBitSet::CAPACITY), None);
+    }
+
+    #[test]
+    fn insert_and_remove() {
+        let mut set = HierarchicalBitSet::new();
+        assert!(set.insert(0));
+        assert!(!set.insert(0));
+        assert!(set.insert(HierarchicalBitSet::CAPACITY - 1));
+        assert!(!set.insert(HierarchicalBitSet::CAPACITY));
+        assert!(set.contains(HierarchicalBitSet::CAPACITY - 1));
+        assert!(!set.contains(HierarchicalBitSet::CAPACITY));
+        assert_eq!(set.len(), 2);
+
+        assert!(set.remove(HierarchicalBitSet::CAPACITY - 1));
+        assert!(!set.remove(HierarchicalBitSet::CAPACITY - 1));
+        assert!(!set.remove(12_345));
+        assert_eq!(set.max_index_checked(), Some(0));
+        // Removing the last member of a region clears it from every level.
+        assert!(set.word(TOP, 0).contains(0));
+        assert!(!set.word(TOP, 0).contains(63));
+        assert!(set.remove(0));
+        assert!(set.word(TOP, 0).is_empty());
+        assert_eq!(format!("{set:?}"), "{}");
+    }
+
+    #[test]
+    fn navigation() {
+        let set: HierarchicalBitSet = [5, 64, 4_095, 300_000, 9_000_000].into_iter().collect();
+        assert_eq!(set.next_after(0), Some(5));
+        assert_eq!(set.next_after(5), Some(64));
+        assert_eq!(set.next_after(64), Some(4_095));
+        assert_eq!(set.next_after(4_095), Some(300_000));
+        assert_eq!(set.next_after(300_000), Some(9_000_000));
+        assert_eq!(set.next_after(9_000_000), None);
+        assert_eq!(set.next_after(usize::MAX), None);
+        assert_eq!(set.prev_before(usize::MAX), Some(9_000_000));
+        assert_eq!(set.prev_before(9_000_000), Some(300_000));
+        assert_eq!(set.prev_before(65), Some(64));
+        assert_eq!(set.prev_before(5), None);
+    }
+
+    #[test]
+    fn iteration_matches_reference() {
+        let mut values = pseudo_random(1, 5_000, HierarchicalBitSet::CAPACITY);
+        values.extend(1_000..1_500);
+        let mut set: HierarchicalBitSet = values.iter().copied().collect();
+        let mut reference: BTreeSet<usize> = values.into_iter().collect();
+        assert_eq!(set.len(), reference.len());
+        assert_matches(&set, &reference);
+
+        for index in pseudo_random(2, 2_000, HierarchicalBitSet::CAPACITY) {
+            assert_eq!(set.remove(index), reference.remove(&index));
+        }
+        for index in 1_000..1_250 {
+            set.remove_quiet(index);
+            reference.remove(&index);
+        }
+        assert_eq!(set.len(), reference.len());
+        assert_matches(&set, &reference);
+    }
+
+    #[test]
+    fn lazy_views() {
+        let a_values = pseudo_random(3, 3_000, 1 << 20);
+        let b_values = pseudo_random(4, 3_000, 1 << 20);
+        let c_values = pseudo_random(5, 3_000, 1 << 20);
+        let a: HierarchicalBitSet = a_values.iter().copied().collect();
+        let b: HierarchicalBitSet = b_values.iter().copied().collect();
+        let c: HierarchicalBitSet = c_values.iter().copied().collect();
+        let a_ref: BTreeSet<usize> = a_values.into_iter().collect();
+        let b_ref: BTreeSet<usize> = b_values.into_iter().collect();
+        let c_ref: BTreeSet<usize> = c_values.into_iter().collect();
+
+        assert_matches(&a.lazy_intersection(&b), &(&a_ref & &b_ref));
+        assert_matches(&a.lazy_union(&b), &(&a_ref | &b_ref));
+        let nested = LazyIntersection::new(a.lazy_union(&b), &c);
+        assert_matches(&nested, &(&(&a_ref | &b_ref) & &c_ref));
+        assert_eq!(
+            a.lazy_union(&b)
+                .iter_indices::<Ascending>()
+                .collect::<HierarchicalBitSet>(),
+            a_ref.union(&b_ref).copied().collect(),
+        );
+    }
+
+    #[test]
+    fn lazy_intersection_skips_false_positives() {
+        // Every level above the members overlaps, even though the members don't.
+        let a: HierarchicalBitSet = [0, 100].into_iter().collect();
+        let b: HierarchicalBitSet = [1, 101].into_iter().collect();
+        let view = a.lazy_intersection(&b);
+        assert!(view.is_empty());
+        assert_eq!(view.iter_indices::<Descending>().next(), None);
+        assert!(!view.contains(0));
+        assert_eq!(a.lazy_union(&b).next_after(1), Some(100));
+    }
+}
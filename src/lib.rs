@@ -1,40 +1,79 @@
 #![doc = include_str!("../README.md")]
 #![cfg_attr(docsrs, feature(doc_cfg, doc_auto_cfg))]
-use rose_bitset_derive::BitSet;
+#[cfg(feature = "alloc")]
+extern crate alloc;
+// Lets the derive macros refer to this crate as `::rose_bitsets`, even from within it.
+extern crate self as rose_bitsets;
+
+mod array;
+mod bitset;
+mod convert;
+mod direction;
+mod enum_set;
+mod error;
+#[cfg(feature = "hierarchical")]
+mod hierarchical;
+mod offset;
+mod order;
+#[cfg(feature = "roaring")]
+mod roaring;
+#[cfg(feature = "alloc")]
+mod vec;
+
+pub use array::{BitArray, BitArrayIndices, BitArrayIter};
+pub use bitset::BitSet;
+pub use convert::Lanes;
+pub use direction::{Ascending, BitReversed, Descending, Direction, GrayCode, Shuffled};
+pub use enum_set::{EnumSet, EnumSetIter, EnumSetType};
+pub use error::{CapacityError, OutOfRangeError};
+#[cfg(feature = "roaring")]
+pub use error::RoaringFormatError;
+#[cfg(feature = "hierarchical")]
+pub use hierarchical::{
+    HierarchicalBitSet, HierarchicalIndices, HierarchicalLayers, LazyIntersection, LazyUnion,
+};
+pub use offset::{OffsetBitSet, OffsetIndices};
+pub use order::{Colex, Inclusion, Lattice, Lex};
+#[cfg(feature = "roaring")]
+pub use roaring::{RoaringBitmap, RoaringIndices};
+#[cfg(feature = "rose-bitset-derive")]
+pub use rose_bitset_derive::{BitSet, EnumSetType};
+#[cfg(feature = "alloc")]
+pub use vec::{BitVecIndices, BitVecSet};
 
 /// A set of 8 bits.
 #[cfg(feature = "b8")]
 #[cfg_attr(docsrs, doc(cfg(feature = "b8")))]
 #[derive(BitSet, Clone, Copy, Default, Eq, Hash, PartialEq)]
-#[bitset(debug, indices, iter, tests)]
+#[bitset(atomic, combinations, debug, indices, iter, permutation, runs, subsets, tests)]
 pub struct BitSet8(u8);
 
 /// A set of 16 bits.
 #[cfg(feature = "b16")]
 #[cfg_attr(docsrs, doc(cfg(feature = "b16")))]
 #[derive(BitSet, Clone, Copy, Default, Eq, Hash, PartialEq)]
-#[bitset(debug, indices, iter, tests)]
+#[bitset(atomic, combinations, debug, indices, iter, permutation, runs, subsets, tests)]
 pub struct BitSet16(u16);
 
 /// A set of 32 bits.
 #[cfg(feature = "b32")]
 #[cfg_attr(docsrs, doc(cfg(feature = "b32")))]
 #[derive(BitSet, Clone, Copy, Default, Eq, Hash, PartialEq)]
-#[bitset(debug, indices, iter, tests)]
+#[bitset(atomic, combinations, debug, indices, iter, permutation, runs, subsets, tests)]
 pub struct BitSet32(u32);
 
 /// A set of 64 bits.
 #[cfg(feature = "b64")]
 #[cfg_attr(docsrs, doc(cfg(feature = "b64")))]
 #[derive(BitSet, Clone, Copy, Default, Eq, Hash, PartialEq)]
-#[bitset(debug, indices, iter, tests)]
+#[bitset(atomic, combinations, debug, indices, iter, permutation, runs, subsets, tests)]
 pub struct BitSet64(u64);
 
 /// A set of 128 bits.
 #[cfg(feature = "b128")]
 #[cfg_attr(docsrs, doc(cfg(feature = "b128")))]
 #[derive(BitSet, Clone, Copy, Default, Eq, Hash, PartialEq)]
-#[bitset(debug, indices, iter, tests)]
+#[bitset(combinations, debug, indices, iter, permutation, runs, subsets, tests)]
 pub struct BitSet128(u128);
 
 /// A bitset the length of a pointer.
@@ -44,22 +83,16 @@ pub struct BitSet128(u128);
 #[cfg(feature = "bsize")]
 #[cfg_attr(docsrs, doc(cfg(feature = "bsize")))]
 #[derive(BitSet, Clone, Copy, Default, Eq, Hash, PartialEq)]
-#[bitset(debug, indices, iter, tests)]
+#[bitset(atomic, combinations, debug, indices, iter, permutation, runs, subsets, tests)]
 pub struct BitSetSize(usize);
 
-/// An iteration order that starts with the smallest end/items and ends with the largest.
-pub struct Ascending;
-
-/// An iteration order that starts with the largest end/items and ends with the smallest.
-pub struct Descending;
-
 #[cfg(test)]
 mod iter_tests {
-    #[cfg(feature = "rose-bitset-derive")]
+    #[cfg(any(feature = "b8", feature = "b16", feature = "b32", feature = "b64"))]
     use crate::{Ascending, Descending};
-    #[cfg(feature = "rose-bitset-derive")]
+    #[cfg(any(feature = "b8", feature = "b16", feature = "b32", feature = "b64"))]
     use rstest::rstest;
-    #[cfg(feature = "rose-bitset-derive")]
+    #[cfg(any(feature = "b8", feature = "b16", feature = "b32", feature = "b64"))]
     use std::iter::zip;
 
     #[cfg(feature = "b8")]
@@ -91,6 +124,10 @@ mod iter_tests {
         const INDICES_6: &[usize] = &[0, 1, 2, 3, 4, 5, 6, 7];
         const BITS_6: &[bool; 8] = &[true; 8];
 
+        const SET_7: BitSet8 = BitSet8::from_bits(0b10000000);
+        const INDICES_7: &[usize] = &[7];
+        const BITS_7: &[bool; 8] = &[false, false, false, false, false, false, false, true];
+
         #[rstest]
         #[case(SET_1, INDICES_1)]
         #[case(SET_2, INDICES_2)]
@@ -98,6 +135,7 @@ mod iter_tests {
         #[case(SET_4, INDICES_4)]
         #[case(SET_5, INDICES_5)]
         #[case(SET_6, INDICES_6)]
+        #[case(SET_7, INDICES_7)]
         fn ascending_indices(#[case] set: BitSet8, #[case] indices: &[usize]) {
             for (&lhs, rhs) in zip(indices, set.iter_indices::<Ascending>()) {
                 assert_eq!(lhs, rhs);
@@ -111,6 +149,7 @@ mod iter_tests {
         #[case(SET_4, INDICES_4)]
         #[case(SET_5, INDICES_5)]
         #[case(SET_6, INDICES_6)]
+        #[case(SET_7, INDICES_7)]
         fn descending_indices(#[case] set: BitSet8, #[case] indices: &[usize]) {
             for (&lhs, rhs) in zip(indices.iter().rev(), set.iter_indices::<Descending>()) {
                 assert_eq!(lhs, rhs);
@@ -124,6 +163,7 @@ mod iter_tests {
         #[case(SET_4, BITS_4)]
         #[case(SET_5, BITS_5)]
         #[case(SET_6, BITS_6)]
+        #[case(SET_7, BITS_7)]
         fn ascending_bits(#[case] set: BitSet8, #[case] bits: &[bool]) {
             for (&lhs, rhs) in zip(bits, set.iter_bits::<Ascending>()) {
                 assert_eq!(lhs, rhs);
@@ -137,6 +177,7 @@ mod iter_tests {
         #[case(SET_4, BITS_4)]
         #[case(SET_5, BITS_5)]
         #[case(SET_6, BITS_6)]
+        #[case(SET_7, BITS_7)]
         fn descending_bits(#[case] set: BitSet8, #[case] bits: &[bool]) {
             for (&lhs, rhs) in zip(bits.iter().rev(), set.iter_bits::<Descending>()) {
                 assert_eq!(lhs, rhs);
@@ -0,0 +1,467 @@
+use crate::{BitSet, OutOfRangeError};
+use core::{
+    fmt,
+    ops::{
+        BitAnd, BitAndAssign, BitOr, BitOrAssign, BitXor, BitXorAssign, Not, Range, Sub, SubAssign,
+    },
+};
+
+/// A bitset over the values `BASE..BASE + S::CAPACITY` rather than `0..S::CAPACITY`.
+///
+/// Values are translated to indices of the underlying set `S` at the boundary, so everything that
+/// goes in or comes out of an [`OffsetBitSet`] is a real value. Inserting a value outside of the
+/// set's [`range`](Self::range) is an error rather than a silent no-op.
+///
+/// ```
+/// use rose_bitsets::{BitSet64, OffsetBitSet};
+///
+/// let mut set = OffsetBitSet::<BitSet64, -32>::new();
+/// set.insert(-32).unwrap();
+/// set.insert(5).unwrap();
+/// assert!(set.insert(32).is_err());
+///
+/// assert!(set.contains(-32));
+/// assert!(set.iter_indices().eq([-32, 5]));
+/// assert!(set.shifted_up_by(2).iter_indices().eq([-30, 7]));
+/// ```
+///
+/// `BASE + S::CAPACITY` must fit in an [`i64`]; anything else is a compile-time error.
+///
+/// ```compile_fail
+/// use rose_bitsets::{BitSet64, OffsetBitSet};
+///
+/// let range = OffsetBitSet::<BitSet64, { i64::MAX }>::range();
+/// ```
+#[derive(Clone, Copy, Eq, Hash, PartialEq)]
+pub struct OffsetBitSet<S, const BASE: i64>(S);
+
+impl<S: BitSet, const BASE: i64> OffsetBitSet<S, BASE> {
+    /// The number of values the set can hold.
+    pub const CAPACITY: usize = S::CAPACITY;
+
+    /// Checks that the end of the set's range fits in an [`i64`].
+    const VALID_BASE: () = assert!(
+        (BASE as i128) + (S::CAPACITY as i128) <= i64::MAX as i128,
+        "`BASE + CAPACITY` must fit in an `i64`",
+    );
+
+    /// Creates an empty set.
+    #[doc(alias = "empty")]
+    #[must_use]
+    pub fn new() -> Self {
+        Self::from_inner(S::new())
+    }
+
+    /// Creates a set containing every value in its range.
+    #[must_use]
+    pub fn all() -> Self {
+        Self::from_inner(S::all())
+    }
+
+    /// Creates a set containing only `value`.
+    pub fn unit(value: i64) -> Result<Self, OutOfRangeError> {
+        Self::index_of(value).map(|index| Self::from_inner(S::unit(index)))
+    }
+
+    /// Creates a set whose underlying set is `set`, so that index `i` of `set` stands for the
+    /// value `BASE + i`.
+    #[must_use]
+    pub fn from_inner(set: S) -> Self {
+        let () = Self::VALID_BASE;
+        Self(set)
+    }
+
+    /// Returns the underlying set, where index `i` stands for the value `BASE + i`.
+    #[must_use]
+    pub fn inner(self) -> S {
+        self.0
+    }
+
+    /// Returns the range of values the set can hold.
+    #[must_use]
+    pub fn range() -> Range<i64> {
+        BASE..Self::value_of(S::CAPACITY)
+    }
+
+    /// Translates `value` into an index of the underlying set.
+    fn index_of(value: i64) -> Result<usize, OutOfRangeError> {
+        let () = Self::VALID_BASE;
+        let index = value as i128 - BASE as i128;
+        if (0..S::CAPACITY as i128).contains(&index) {
+            Ok(index as usize)
+        } else {
+            Err(OutOfRangeError::new(value, Self::range()))
+        }
+    }
+
+    /// Translates an index of the underlying set into a value.
+    fn value_of(index: usize) -> i64 {
+        let () = Self::VALID_BASE;
+        (BASE as i128 + index as i128) as i64
+    }
+
+    /// Creates a new set with values that are in both `self` and `rhs`.
+    #[must_use]
+    pub fn intersection(self, rhs: Self) -> Self {
+        Self(self.0.intersection(rhs.0))
+    }
+
+    /// Creates a new set with values that are in `self` or `rhs`.
+    #[must_use]
+    pub fn union(self, rhs: Self) -> Self {
+        Self(self.0.union(rhs.0))
+    }
+
+    /// Creates a new set with values that are in `self`, but not in `rhs`.
+    #[must_use]
+    pub fn difference(self, rhs: Self) -> Self {
+        Self(self.0.difference(rhs.0))
+    }
+
+    /// Creates a new set with values that are in `self` or `rhs`, but not in both.
+    #[must_use]
+    pub fn symmetric_difference(self, rhs: Self) -> Self {
+        Self(self.0.symmetric_difference(rhs.0))
+    }
+
+    /// Creates a new set with the values in the set's range that are not in `self`.
+    #[must_use]
+    pub fn complement(self) -> Self {
+        Self(self.0.complement())
+    }
+
+    /// Returns `true` if `self` has no elements in common with `rhs`. This is equivalent to
+    /// checking for an empty intersection.
+    #[must_use]
+    pub fn is_disjoint(self, rhs: Self) -> bool {
+        self.0.is_disjoint(rhs.0)
+    }
+
+    /// Returns `true` if the set is a subset of another, i.e., `rhs` contains at least all the
+    /// values in `self`.
+    #[must_use]
+    pub fn is_subset(self, rhs: Self) -> bool {
+        self.0.is_subset(rhs.0)
+    }
+
+    /// Returns `true` if the set is a superset of another, i.e., `self` contains at least all the
+    /// values in `rhs`.
+    #[must_use]
+    pub fn is_superset(self, rhs: Self) -> bool {
+        self.0.is_superset(rhs.0)
+    }
+
+    /// Returns `true` if the set contains no elements.
+    #[must_use]
+    pub fn is_empty(self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Returns `true` if the set contains every value in its range.
+    #[must_use]
+    pub fn is_full(self) -> bool {
+        self.0.is_full()
+    }
+
+    /// Returns the number of elements in the set.
+    #[must_use]
+    pub fn len(self) -> usize {
+        self.0.len()
+    }
+
+    /// Returns `true` if `value` is in the set.
+    ///
+    /// Values outside of the set's range are never in it.
+    #[must_use]
+    pub fn contains(self, value: i64) -> bool {
+        Self::index_of(value).is_ok_and(|index| self.0.contains(index))
+    }
+
+    /// Returns the smallest value in the set, or [`None`] if the set is empty.
+    #[must_use]
+    pub fn min_value(self) -> Option<i64> {
+        (!self.0.is_empty()).then(|| Self::value_of(self.0.min_index()))
+    }
+
+    /// Returns the largest value in the set, or [`None`] if the set is empty.
+    #[must_use]
+    pub fn max_value(self) -> Option<i64> {
+        self.0.max_index_checked().map(Self::value_of)
+    }
+
+    /// Creates a new set with every value increased by `shift`. Values that leave the set's range
+    /// are dropped.
+    #[must_use]
+    pub fn shifted_up_by(self, shift: u32) -> Self {
+        Self(self.0.shifted_up_by(shift))
+    }
+
+    /// Increases every value in the set by `shift`. Values that leave the set's range are
+    /// dropped.
+    pub fn shift_up_by(&mut self, shift: u32) {
+        self.0.shift_up_by(shift);
+    }
+
+    /// Creates a new set with every value decreased by `shift`. Values that leave the set's range
+    /// are dropped.
+    #[must_use]
+    pub fn shifted_down_by(self, shift: u32) -> Self {
+        Self(self.0.shifted_down_by(shift))
+    }
+
+    /// Decreases every value in the set by `shift`. Values that leave the set's range are
+    /// dropped.
+    pub fn shift_down_by(&mut self, shift: u32) {
+        self.0.shift_down_by(shift);
+    }
+
+    /// Moves the set's range so that it starts at `NEW_BASE`, keeping the same values.
+    ///
+    /// Fails with the first value that does not fit in the new range.
+    pub fn rebased<const NEW_BASE: i64>(
+        self,
+    ) -> Result<OffsetBitSet<S, NEW_BASE>, OutOfRangeError> {
+        let mut rebased = OffsetBitSet::new();
+        for value in self.iter_indices() {
+            rebased.insert(value)?;
+        }
+        Ok(rebased)
+    }
+
+    /// Clears the set, removing all values.
+    pub fn clear(&mut self) {
+        self.0.clear();
+    }
+
+    /// Adds `value` to the set. Returns whether the value was not already present, or an error if
+    /// `value` is outside of the set's range.
+    pub fn insert(&mut self, value: i64) -> Result<bool, OutOfRangeError> {
+        Self::index_of(value).map(|index| self.0.insert(index))
+    }
+
+    /// Sets whether `value` is in the set. Returns whether it was in the set before, or an error
+    /// if `value` is outside of the set's range.
+    pub fn replace(&mut self, value: i64, bit: bool) -> Result<bool, OutOfRangeError> {
+        Self::index_of(value).map(|index| self.0.replace(index, bit))
+    }
+
+    /// Removes `value` from the set. Returns whether the value was present.
+    ///
+    /// Values outside of the set's range are never in it, so removing them does nothing.
+    #[must_use = "consider using the return value or ignoring it with `let _ = ...`"]
+    pub fn remove(&mut self, value: i64) -> bool {
+        Self::index_of(value).is_ok_and(|index| self.0.remove(index))
+    }
+
+    /// Creates an iterator over the values in the set, in ascending order.
+    #[must_use]
+    pub fn iter_indices(&self) -> OffsetIndices<'_, S, BASE> {
+        OffsetIndices(self.0.iter_indices())
+    }
+}
+
+/// An iterator over the values in an [`OffsetBitSet`], in ascending order.
+pub struct OffsetIndices<'a, S: BitSet + 'a, const BASE: i64>(S::Indices<'a>);
+
+impl<S: BitSet, const BASE: i64> Iterator for OffsetIndices<'_, S, BASE> {
+    type Item = i64;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next().map(OffsetBitSet::<S, BASE>::value_of)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
+}
+
+impl<S: BitSet, const BASE: i64> fmt::Debug for OffsetBitSet<S, BASE> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.iter_indices()).finish()
+    }
+}
+
+impl<S: BitSet, const BASE: i64> Default for OffsetBitSet<S, BASE> {
+    /// Creates an empty set, checking `BASE` the same way [`new`](Self::new) does.
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<S: BitSet, const BASE: i64> From<S> for OffsetBitSet<S, BASE> {
+    fn from(set: S) -> Self {
+        Self::from_inner(set)
+    }
+}
+
+impl<S: BitSet, const BASE: i64> BitAnd for OffsetBitSet<S, BASE> {
+    type Output = Self;
+
+    fn bitand(self, rhs: Self) -> Self::Output {
+        self.intersection(rhs)
+    }
+}
+
+impl<S: BitSet, const BASE: i64> BitAndAssign for OffsetBitSet<S, BASE> {
+    fn bitand_assign(&mut self, rhs: Self) {
+        *self = self.intersection(rhs);
+    }
+}
+
+impl<S: BitSet, const BASE: i64> BitOr for OffsetBitSet<S, BASE> {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self::Output {
+        self.union(rhs)
+    }
+}
+
+impl<S: BitSet, const BASE: i64> BitOrAssign for OffsetBitSet<S, BASE> {
+    fn bitor_assign(&mut self, rhs: Self) {
+        *self = self.union(rhs);
+    }
+}
+
+impl<S: BitSet, const BASE: i64> BitXor for OffsetBitSet<S, BASE> {
+    type Output = Self;
+
+    fn bitxor(self, rhs: Self) -> Self::Output {
+        self.symmetric_difference(rhs)
+    }
+}
+
+impl<S: BitSet, const BASE: i64> BitXorAssign for OffsetBitSet<S, BASE> {
+    fn bitxor_assign(&mut self, rhs: Self) {
+        *self = self.symmetric_difference(rhs);
+    }
+}
+
+impl<S: BitSet, const BASE: i64> Not for OffsetBitSet<S, BASE> {
+    type Output = Self;
+
+    fn not(self) -> Self::Output {
+        self.complement()
+    }
+}
+
+impl<S: BitSet, const BASE: i64> Sub for OffsetBitSet<S, BASE> {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        self.difference(rhs)
+    }
+}
+
+impl<S: BitSet, const BASE: i64> SubAssign for OffsetBitSet<S, BASE> {
+    fn sub_assign(&mut self, rhs: Self) {
+        *self = self.difference(rhs);
+    }
+}
+
+#[cfg(all(test, feature = "b64"))]
+mod tests {
+    use super::*;
+    use crate::BitSet64;
+
+    type Signed = OffsetBitSet<BitSet64, -32>;
+    type Shifted = OffsetBitSet<BitSet64, 1000>;
+
+    #[test]
+    fn range() {
+        assert_eq!(Signed::range(), -32..32);
+        assert_eq!(Shifted::range(), 1000..1064);
+        assert_eq!(
+            OffsetBitSet::<BitSet64, { i64::MAX - 64 }>::range(),
+            i64::MAX - 64..i64::MAX
+        );
+        assert_eq!(
+            OffsetBitSet::<BitSet64, { i64::MIN }>::range(),
+            i64::MIN..i64::MIN + 64
+        );
+    }
+
+    #[test]
+    fn default_is_empty() {
+        assert_eq!(Signed::default(), Signed::new());
+        assert!(Shifted::default().is_empty());
+    }
+
+    #[test]
+    fn insert_translates_values() {
+        let mut set = Signed::new();
+        assert_eq!(set.insert(-32), Ok(true));
+        assert_eq!(set.insert(-32), Ok(false));
+        assert_eq!(set.insert(0), Ok(true));
+        assert_eq!(set.insert(31), Ok(true));
+        assert_eq!(set.inner(), BitSet64::from_bits(1 | 1 << 32 | 1 << 63));
+        assert_eq!(set.len(), 3);
+        assert!(set.contains(0));
+        assert!(!set.contains(1));
+        assert_eq!(set.min_value(), Some(-32));
+        assert_eq!(set.max_value(), Some(31));
+        assert!(set.remove(0));
+        assert!(!set.remove(0));
+        assert_eq!(set.replace(-32, false), Ok(true));
+        assert_eq!(format!("{set:?}"), "{31}");
+    }
+
+    #[test]
+    fn out_of_range_values_are_reported() {
+        let mut set = Shifted::new();
+        let error = set.insert(999).unwrap_err();
+        assert_eq!(error.value(), 999);
+        assert_eq!(error.range(), 1000..1064);
+        assert_eq!(
+            set.insert(1064),
+            Err(OutOfRangeError::new(1064, 1000..1064))
+        );
+        assert!(Shifted::unit(i64::MIN).is_err());
+        assert!(set.replace(-5, true).is_err());
+        assert!(!set.contains(5000));
+        assert!(!set.remove(5000));
+        assert!(set.is_empty());
+    }
+
+    #[test]
+    fn iteration_yields_values() {
+        let set = Shifted::unit(1003).unwrap() | Shifted::unit(1063).unwrap();
+        assert!(set.iter_indices().eq([1003, 1063]));
+        assert!(Shifted::new().iter_indices().eq([]));
+        assert_eq!(Signed::all().iter_indices().next(), Some(-32));
+        assert_eq!(Signed::all().iter_indices().last(), Some(31));
+    }
+
+    #[test]
+    fn shifts_move_values() {
+        let set = Signed::unit(-1).unwrap() | Signed::unit(30).unwrap();
+        assert!(set.shifted_up_by(1).iter_indices().eq([0, 31]));
+        assert!(set.shifted_up_by(2).iter_indices().eq([1]));
+        assert!(set.shifted_down_by(31).iter_indices().eq([-32, -1]));
+        let mut set = set;
+        set.shift_down_by(40);
+        assert!(set.iter_indices().eq([-10]));
+    }
+
+    #[test]
+    fn rebasing() {
+        let set = Signed::unit(-5).unwrap() | Signed::unit(20).unwrap();
+        let rebased = set.rebased::<-10>().unwrap();
+        assert!(rebased.iter_indices().eq([-5, 20]));
+        assert_eq!(rebased.inner(), BitSet64::from_bits(1 << 5 | 1 << 30));
+        assert_eq!(set.rebased::<0>(), Err(OutOfRangeError::new(-5, 0..64)));
+    }
+
+    #[test]
+    fn set_algebra() {
+        let a = Signed::unit(-3).unwrap() | Signed::unit(4).unwrap();
+        let b = Signed::unit(4).unwrap() | Signed::unit(9).unwrap();
+        assert!((a & b).iter_indices().eq([4]));
+        assert!((a - b).iter_indices().eq([-3]));
+        assert!((a ^ b).iter_indices().eq([-3, 9]));
+        assert_eq!((!a).len(), 62);
+        assert!((a & b).is_subset(a));
+        assert!(a.is_superset(a & b));
+        assert!((a - b).is_disjoint(b));
+        assert!(Signed::all().is_full());
+    }
+}
@@ -0,0 +1,343 @@
+use crate::BitSet;
+use core::cmp::Ordering;
+
+/// A bounded lattice: a partial order in which every pair of elements has a least upper bound (the
+/// join) and a greatest lower bound (the meet), along with a greatest and least element.
+///
+/// Every [`BitSet`] is a lattice under inclusion, with [`union`](BitSet::union) as the join,
+/// [`intersection`](BitSet::intersection) as the meet, [`all`](BitSet::all) at the top and
+/// [`new`](BitSet::new) at the bottom. [`Inclusion`] orders sets the same way.
+pub trait Lattice: Sized {
+    /// Returns the least element that is greater than or equal to both `self` and `rhs`.
+    #[must_use]
+    fn join(self, rhs: Self) -> Self;
+
+    /// Returns the greatest element that is less than or equal to both `self` and `rhs`.
+    #[must_use]
+    fn meet(self, rhs: Self) -> Self;
+
+    /// Returns the element that is greater than or equal to every other.
+    #[must_use]
+    fn top() -> Self;
+
+    /// Returns the element that is less than or equal to every other.
+    #[must_use]
+    fn bottom() -> Self;
+}
+
+impl<S: BitSet> Lattice for S {
+    fn join(self, rhs: Self) -> Self {
+        self.union(rhs)
+    }
+
+    fn meet(self, rhs: Self) -> Self {
+        self.intersection(rhs)
+    }
+
+    fn top() -> Self {
+        Self::all()
+    }
+
+    fn bottom() -> Self {
+        Self::new()
+    }
+}
+
+/// Orders sets colexicographically, i.e., by the largest element that only one of them has.
+///
+/// For the `BitSetN` types, this is the same as comparing their bits as integers.
+///
+/// ```
+/// use rose_bitsets::{BitSet8, Colex};
+///
+/// let mut sets = [0b0110, 0b1000, 0b0001].map(|bits| Colex(BitSet8::from_bits(bits)));
+/// sets.sort();
+/// assert_eq!(sets.map(|set| set.0.bits()), [0b0001, 0b0110, 0b1000]);
+/// assert_eq!(sets[1].succ().map(|set| set.0.bits()), Some(0b0111));
+/// ```
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+#[repr(transparent)]
+pub struct Colex<S>(pub S);
+
+impl<S: BitSet> Colex<S> {
+    /// Returns the set that comes right after this one, or [`None`] if this is [`S::all`].
+    ///
+    /// [`S::all`]: BitSet::all
+    #[must_use]
+    pub fn succ(self) -> Option<Self> {
+        // This is binary increment: the lowest missing element carries into place, clearing
+        // everything below it.
+        let carry = self.0.complement().min_index();
+        if carry >= S::CAPACITY {
+            return None;
+        }
+        Some(Self(self.0.cleared_0_to_i(carry).union(S::unit(carry))))
+    }
+
+    /// Returns the set that comes right before this one, or [`None`] if this is [`S::new`].
+    ///
+    /// [`S::new`]: BitSet::new
+    #[must_use]
+    pub fn pred(self) -> Option<Self> {
+        let borrow = self.0.min_index();
+        if borrow >= S::CAPACITY {
+            return None;
+        }
+        let below = S::all().masked_0_to_i(borrow);
+        Some(Self(self.0.difference(S::unit(borrow)).union(below)))
+    }
+}
+
+impl<S: BitSet> Ord for Colex<S> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match self.0.symmetric_difference(other.0).max_index_checked() {
+            Some(index) if self.0.contains(index) => Ordering::Greater,
+            Some(_) => Ordering::Less,
+            None => Ordering::Equal,
+        }
+    }
+}
+
+impl<S: BitSet> PartialOrd for Colex<S> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<S> From<S> for Colex<S> {
+    fn from(set: S) -> Self {
+        Self(set)
+    }
+}
+
+/// Orders sets lexicographically by their elements in ascending order, the way a sorted list of
+/// each set's elements would be ordered.
+///
+/// ```
+/// use rose_bitsets::{BitSet8, Lex};
+///
+/// let mut sets = [0b0110, 0b1000, 0b0011].map(|bits| Lex(BitSet8::from_bits(bits)));
+/// sets.sort();
+/// // [0, 1] < [1, 2] < [3]
+/// assert_eq!(sets.map(|set| set.0.bits()), [0b0011, 0b0110, 0b1000]);
+/// ```
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+#[repr(transparent)]
+pub struct Lex<S>(pub S);
+
+impl<S: BitSet> Lex<S> {
+    /// Returns the set that comes right after this one, or [`None`] if this is the last set,
+    /// which holds only the largest index.
+    #[must_use]
+    pub fn succ(self) -> Option<Self> {
+        let mut set = self.0;
+        match set.max_index_checked() {
+            None => return Some(Self(S::unit(0))),
+            Some(last) if last + 1 < S::CAPACITY => set.insert_quiet(last + 1),
+            Some(last) => {
+                // Every set that extends this one has been visited, so move on to the next
+                // prefix.
+                set.remove_quiet(last);
+                let next_to_last = set.max_index_checked()?;
+                set.remove_quiet(next_to_last);
+                set.insert_quiet(next_to_last + 1);
+            }
+        }
+        Some(Self(set))
+    }
+
+    /// Returns the set that comes right before this one, or [`None`] if this is the empty set.
+    #[must_use]
+    pub fn pred(self) -> Option<Self> {
+        let mut set = self.0;
+        let last = set.max_index_checked()?;
+        set.remove_quiet(last);
+        if last != 0 && !set.contains(last - 1) {
+            // The set before this one is the last to extend the prefix before it.
+            set.insert_quiet(last - 1);
+            set.insert_quiet(S::CAPACITY - 1);
+        }
+        Some(Self(set))
+    }
+}
+
+impl<S: BitSet> Ord for Lex<S> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        let first_difference = self.0.symmetric_difference(other.0).min_index();
+        if first_difference >= S::CAPACITY {
+            return Ordering::Equal;
+        }
+        // Both sets agree up to the first difference. Whichever set has it comes first, unless
+        // the other set ends there.
+        let (has, lacks, ordering) = if self.0.contains(first_difference) {
+            (self.0, other.0, Ordering::Less)
+        } else {
+            (other.0, self.0, Ordering::Greater)
+        };
+        debug_assert!(has.contains(first_difference));
+        if lacks.cleared_0_to_i(first_difference + 1).is_empty() {
+            ordering.reverse()
+        } else {
+            ordering
+        }
+    }
+}
+
+impl<S: BitSet> PartialOrd for Lex<S> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<S> From<S> for Lex<S> {
+    fn from(set: S) -> Self {
+        Self(set)
+    }
+}
+
+/// Partially orders sets by inclusion, so that `a <= b` exactly when `a` is a subset of `b`.
+///
+/// Sets where neither is a subset of the other are incomparable.
+///
+/// ```
+/// use rose_bitsets::{BitSet8, Inclusion};
+///
+/// let a = Inclusion(BitSet8::from_bits(0b0011));
+/// let b = Inclusion(BitSet8::from_bits(0b0111));
+/// let c = Inclusion(BitSet8::from_bits(0b1100));
+/// assert!(a < b);
+/// assert!(a.partial_cmp(&c).is_none());
+/// ```
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+#[repr(transparent)]
+pub struct Inclusion<S>(pub S);
+
+impl<S: BitSet> PartialOrd for Inclusion<S> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        if self.0 == other.0 {
+            Some(Ordering::Equal)
+        } else if self.0.is_subset(other.0) {
+            Some(Ordering::Less)
+        } else if self.0.is_superset(other.0) {
+            Some(Ordering::Greater)
+        } else {
+            None
+        }
+    }
+}
+
+impl<S: BitSet> Lattice for Inclusion<S> {
+    fn join(self, rhs: Self) -> Self {
+        Self(self.0.union(rhs.0))
+    }
+
+    fn meet(self, rhs: Self) -> Self {
+        Self(self.0.intersection(rhs.0))
+    }
+
+    fn top() -> Self {
+        Self(S::all())
+    }
+
+    fn bottom() -> Self {
+        Self(S::new())
+    }
+}
+
+impl<S> From<S> for Inclusion<S> {
+    fn from(set: S) -> Self {
+        Self(set)
+    }
+}
+
+#[cfg(all(test, feature = "b8"))]
+mod tests {
+    use super::*;
+    use crate::{Ascending, BitSet8};
+    use std::vec::Vec;
+
+    fn every_set() -> impl Iterator<Item = BitSet8> {
+        (0..=u8::MAX).map(BitSet8::from_bits)
+    }
+
+    #[test]
+    fn colex_matches_bits() {
+        for a in every_set() {
+            for b in every_set() {
+                assert_eq!(Colex(a).cmp(&Colex(b)), a.bits().cmp(&b.bits()));
+            }
+        }
+    }
+
+    #[test]
+    fn lex_matches_sorted_elements() {
+        let elements = |set: BitSet8| set.iter_indices::<Ascending>().collect::<Vec<_>>();
+        for a in every_set() {
+            for b in every_set() {
+                assert_eq!(Lex(a).cmp(&Lex(b)), elements(a).cmp(&elements(b)));
+            }
+        }
+    }
+
+    #[test]
+    fn colex_steps_through_every_set() {
+        let mut set = Colex(BitSet8::new());
+        let mut count = 1;
+        while let Some(next) = set.succ() {
+            assert!(set < next);
+            assert_eq!(next.pred(), Some(set));
+            set = next;
+            count += 1;
+        }
+        assert_eq!(count, 256);
+        assert_eq!(set, Colex(BitSet8::all()));
+        assert_eq!(Colex(BitSet8::new()).pred(), None);
+    }
+
+    #[test]
+    fn lex_steps_through_every_set() {
+        let mut sorted: Vec<_> = every_set().map(Lex).collect();
+        sorted.sort();
+        let mut set = Lex(BitSet8::new());
+        for &expected in &sorted[1..] {
+            let next = set.succ().unwrap();
+            assert_eq!(next, expected);
+            assert_eq!(next.pred(), Some(set));
+            set = next;
+        }
+        assert_eq!(set, Lex(BitSet8::unit(7)));
+        assert_eq!(set.succ(), None);
+        assert_eq!(Lex(BitSet8::new()).pred(), None);
+    }
+
+    #[test]
+    fn inclusion_follows_subsets() {
+        for a in every_set() {
+            for b in every_set() {
+                let ordering = Inclusion(a).partial_cmp(&Inclusion(b));
+                assert_eq!(ordering == Some(Ordering::Less), a.is_strict_subset(b));
+                assert_eq!(ordering == Some(Ordering::Equal), a == b);
+                assert_eq!(ordering.is_none(), !a.is_subset(b) && !b.is_subset(a));
+            }
+        }
+    }
+
+    #[test]
+    fn lattice_laws() {
+        let sets = [0b0000_0000, 0b1010_0110, 0b0110_0011, 0b1111_1111].map(BitSet8::from_bits);
+        for a in sets {
+            assert_eq!(a.join(BitSet8::bottom()), a);
+            assert_eq!(a.meet(BitSet8::top()), a);
+            for b in sets {
+                let (join, meet) = (
+                    Inclusion(a).join(Inclusion(b)),
+                    Inclusion(a).meet(Inclusion(b)),
+                );
+                assert!(Inclusion(a) <= join && Inclusion(b) <= join);
+                assert!(meet <= Inclusion(a) && meet <= Inclusion(b));
+                assert_eq!(a.join(a.meet(b)), a);
+                assert_eq!(a.meet(a.join(b)), a);
+            }
+        }
+    }
+}
@@ -0,0 +1,1430 @@
+use crate::{Ascending, BitSet64, Descending, RoaringFormatError};
+use alloc::{boxed::Box, vec, vec::Vec};
+use core::{
+    cmp::Ordering,
+    fmt,
+    hash::{Hash, Hasher},
+    iter,
+    marker::PhantomData,
+    ops::{
+        BitAnd, BitAndAssign, BitOr, BitOrAssign, BitXor, BitXorAssign, RangeInclusive, Sub,
+        SubAssign,
+    },
+    slice,
+};
+
+/// The most values an array container may hold before it is converted into a bitmap container.
+const ARRAY_LIMIT: usize = 4096;
+/// The number of [`BitSet64`] words in a bitmap container.
+const BITMAP_WORDS: usize = 1024;
+
+/// The cookie that starts a serialized bitmap without any run containers.
+const SERIAL_COOKIE_NO_RUNS: u32 = 12346;
+/// The cookie that starts a serialized bitmap with at least one run container.
+const SERIAL_COOKIE: u16 = 12347;
+/// Serialized bitmaps with run containers only have an offset header if they have at least this
+/// many containers.
+const NO_OFFSET_THRESHOLD: usize = 4;
+
+/// A compressed set of [`u32`]s, in the style of a [Roaring bitmap].
+///
+/// The values are split into chunks of 2<sup>16</sup> by their upper 16 bits, and each chunk is
+/// stored in whichever kind of container suits it:
+///
+/// - sparse chunks are sorted arrays of the lower 16 bits,
+/// - dense chunks are bitmaps made of 1024 [`BitSet64`]s,
+/// - chunks made of long stretches of consecutive values can be stored as runs once
+///   [`run_optimize`](Self::run_optimize) is called.
+///
+/// The set can be converted to and from the [portable serialization format], so it can be
+/// exchanged with other Roaring implementations.
+///
+/// ```
+/// use rose_bitsets::{Ascending, RoaringBitmap};
+///
+/// let mut set: RoaringBitmap = (0..100_000).step_by(3).collect();
+/// set.insert_quiet(4_000_000_000);
+///
+/// assert_eq!(set.len(), 33_335);
+/// assert!(set.contains(99_999));
+/// assert!(!set.contains(100_000));
+/// assert_eq!(set.iter_indices::<Ascending>().nth(2), Some(6));
+///
+/// let bytes = set.to_bytes();
+/// assert_eq!(RoaringBitmap::from_bytes(&bytes), Ok(set));
+/// ```
+///
+/// [Roaring bitmap]: https://roaringbitmap.org/
+/// [portable serialization format]: https://github.com/RoaringBitmap/RoaringFormatSpec
+#[derive(Clone, Default)]
+pub struct RoaringBitmap {
+    /// The non-empty containers, sorted by their keys (the upper 16 bits of their values).
+    containers: Vec<(u16, Container)>,
+}
+
+impl RoaringBitmap {
+    /// Creates an empty set.
+    #[doc(alias = "empty")]
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            containers: Vec::new(),
+        }
+    }
+
+    /// Returns the container holding the values whose upper 16 bits are `key`.
+    fn container(&self, key: u16) -> Option<&Container> {
+        self.containers
+            .binary_search_by_key(&key, |&(key, _)| key)
+            .ok()
+            .map(|i| &self.containers[i].1)
+    }
+
+    /// Combines the containers of `self` and `rhs` with matching keys using `op`. Containers
+    /// without a counterpart are kept as they are if `keep_lhs` or `keep_rhs` says so.
+    fn merge_with(
+        &self,
+        rhs: &Self,
+        keep_lhs: bool,
+        keep_rhs: bool,
+        op: impl Fn(&Container, &Container) -> Option<Container>,
+    ) -> Self {
+        let mut containers = Vec::new();
+        let (mut lhs, mut rhs) = (
+            self.containers.iter().peekable(),
+            rhs.containers.iter().peekable(),
+        );
+        loop {
+            let ordering = match (lhs.peek(), rhs.peek()) {
+                (Some((lhs_key, _)), Some((rhs_key, _))) => lhs_key.cmp(rhs_key),
+                (Some(_), None) => Ordering::Less,
+                (None, Some(_)) => Ordering::Greater,
+                (None, None) => break,
+            };
+            match ordering {
+                Ordering::Less => {
+                    let entry = lhs.next().unwrap();
+                    if keep_lhs {
+                        containers.push(entry.clone());
+                    }
+                }
+                Ordering::Greater => {
+                    let entry = rhs.next().unwrap();
+                    if keep_rhs {
+                        containers.push(entry.clone());
+                    }
+                }
+                Ordering::Equal => {
+                    let (key, lhs) = lhs.next().unwrap();
+                    let (_, rhs) = rhs.next().unwrap();
+                    if let Some(container) = op(lhs, rhs) {
+                        containers.push((*key, container));
+                    }
+                }
+            }
+        }
+        Self { containers }
+    }
+
+    /// Creates a new set with values that are in both `self` and `rhs`.
+    #[must_use]
+    pub fn intersection(&self, rhs: &Self) -> Self {
+        self.merge_with(rhs, false, false, Container::intersection)
+    }
+
+    /// Creates a new set with values that are in `self` or `rhs`.
+    #[must_use]
+    pub fn union(&self, rhs: &Self) -> Self {
+        self.merge_with(rhs, true, true, |lhs, rhs| Some(lhs.union(rhs)))
+    }
+
+    /// Creates a new set with values that are in `self`, but not in `rhs`.
+    #[must_use]
+    pub fn difference(&self, rhs: &Self) -> Self {
+        self.merge_with(rhs, true, false, Container::difference)
+    }
+
+    /// Creates a new set with values that are in `self` or `rhs`, but not in both.
+    #[must_use]
+    pub fn symmetric_difference(&self, rhs: &Self) -> Self {
+        self.merge_with(rhs, true, true, Container::symmetric_difference)
+    }
+
+    /// Returns `true` if `self` has no elements in common with `rhs`. This is equivalent to
+    /// checking for an empty intersection.
+    #[must_use]
+    pub fn is_disjoint(&self, rhs: &Self) -> bool {
+        self.intersection(rhs).is_empty()
+    }
+
+    /// Returns `true` if the set is a subset of another, i.e., `rhs` contains at least all the
+    /// values in `self`.
+    #[must_use]
+    pub fn is_subset(&self, rhs: &Self) -> bool {
+        self.difference(rhs).is_empty()
+    }
+
+    /// Returns `true` if the set is a superset of another, i.e., `self` contains at least all the
+    /// values in `rhs`.
+    #[must_use]
+    pub fn is_superset(&self, rhs: &Self) -> bool {
+        rhs.is_subset(self)
+    }
+
+    /// Returns `true` if the set contains no elements.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.containers.is_empty()
+    }
+
+    /// Returns the number of elements in the set.
+    #[must_use]
+    pub fn len(&self) -> u64 {
+        self.containers
+            .iter()
+            .map(|(_, container)| container.len() as u64)
+            .sum()
+    }
+
+    /// Returns `true` if `value` is in the set.
+    #[must_use]
+    pub fn contains(&self, value: u32) -> bool {
+        let (key, low) = split(value);
+        self.container(key)
+            .is_some_and(|container| container.contains(low))
+    }
+
+    /// Returns the smallest value in the set, or [`None`] if the set is empty.
+    #[must_use]
+    pub fn min_index_checked(&self) -> Option<u32> {
+        let (key, container) = self.containers.first()?;
+        Some(join(*key, container.min()))
+    }
+
+    /// Returns the largest value in the set, or [`None`] if the set is empty.
+    #[must_use]
+    pub fn max_index_checked(&self) -> Option<u32> {
+        let (key, container) = self.containers.last()?;
+        Some(join(*key, container.max()))
+    }
+
+    /// Clears the set, removing all values.
+    pub fn clear(&mut self) {
+        self.containers.clear();
+    }
+
+    /// Adds `value` to the set.
+    ///
+    /// If you would like to know if the insertion succeeded, use [`insert`](Self::insert)
+    /// instead.
+    pub fn insert_quiet(&mut self, value: u32) {
+        let _ = self.insert(value);
+    }
+
+    /// Adds `value` to the set. Returns whether the value was not already present.
+    ///
+    /// If the return value is not needed, use [`insert_quiet`](Self::insert_quiet) instead.
+    #[must_use = "consider using the return value or calling `insert_quiet` instead"]
+    pub fn insert(&mut self, value: u32) -> bool {
+        let (key, low) = split(value);
+        match self.containers.binary_search_by_key(&key, |&(key, _)| key) {
+            Ok(i) => self.containers[i].1.insert(low),
+            Err(i) => {
+                self.containers
+                    .insert(i, (key, Container::Array(vec![low])));
+                true
+            }
+        }
+    }
+
+    /// Removes `value` from the set.
+    ///
+    /// If you would like to know if the removal succeeded, use [`remove`](Self::remove)
+    /// instead.
+    pub fn remove_quiet(&mut self, value: u32) {
+        let _ = self.remove(value);
+    }
+
+    /// Removes `value` from the set. Returns whether the value was present.
+    ///
+    /// If the return value is not needed, use [`remove_quiet`](Self::remove_quiet) instead.
+    #[must_use = "consider using the return value or calling `remove_quiet` instead"]
+    pub fn remove(&mut self, value: u32) -> bool {
+        let (key, low) = split(value);
+        let Ok(i) = self.containers.binary_search_by_key(&key, |&(key, _)| key) else {
+            return false;
+        };
+        let removed = self.containers[i].1.remove(low);
+        if self.containers[i].1.len() == 0 {
+            self.containers.remove(i);
+        }
+        removed
+    }
+
+    /// Converts each container into a run container if that would make it smaller, and converts
+    /// run containers back if it would not.
+    ///
+    /// Set operations never produce run containers on their own, so this is worth calling after
+    /// building a set with long stretches of consecutive values.
+    pub fn run_optimize(&mut self) {
+        for (_, container) in &mut self.containers {
+            container.run_optimize();
+        }
+    }
+
+    /// Returns `true` if any of the set's containers are run containers.
+    fn has_runs(&self) -> bool {
+        self.containers
+            .iter()
+            .any(|(_, container)| matches!(container, Container::Run(_)))
+    }
+
+    /// Returns the size of the set's headers in the portable serialization format.
+    fn serialized_header_size(&self) -> usize {
+        let count = self.containers.len();
+        let (cookie_size, has_offsets) = if self.has_runs() {
+            (4 + count.div_ceil(8), count >= NO_OFFSET_THRESHOLD)
+        } else {
+            (8, true)
+        };
+        cookie_size + 4 * count + if has_offsets { 4 * count } else { 0 }
+    }
+
+    /// Returns the number of bytes needed to serialize the set.
+    #[must_use]
+    pub fn serialized_size(&self) -> usize {
+        self.serialized_header_size()
+            + self
+                .containers
+                .iter()
+                .map(|(_, container)| container.serialized_size())
+                .sum::<usize>()
+    }
+
+    /// Appends the set to `bytes` in the [portable serialization format].
+    ///
+    /// [portable serialization format]: https://github.com/RoaringBitmap/RoaringFormatSpec
+    pub fn serialize_into(&self, bytes: &mut Vec<u8>) {
+        bytes.reserve(self.serialized_size());
+        let count = self.containers.len();
+        let has_runs = self.has_runs();
+        if has_runs {
+            let cookie = u32::from(SERIAL_COOKIE) | ((count as u32 - 1) << 16);
+            bytes.extend(cookie.to_le_bytes());
+            let mut run_flags = vec![0u8; count.div_ceil(8)];
+            for (i, (_, container)) in self.containers.iter().enumerate() {
+                if matches!(container, Container::Run(_)) {
+                    run_flags[i / 8] |= 1 << (i % 8);
+                }
+            }
+            bytes.extend(run_flags);
+        } else {
+            bytes.extend(SERIAL_COOKIE_NO_RUNS.to_le_bytes());
+            bytes.extend((count as u32).to_le_bytes());
+        }
+        for (key, container) in &self.containers {
+            bytes.extend(key.to_le_bytes());
+            bytes.extend((container.len() as u16).wrapping_sub(1).to_le_bytes());
+        }
+        if !has_runs || count >= NO_OFFSET_THRESHOLD {
+            let mut offset = self.serialized_header_size();
+            for (_, container) in &self.containers {
+                bytes.extend((offset as u32).to_le_bytes());
+                offset += container.serialized_size();
+            }
+        }
+        for (_, container) in &self.containers {
+            container.serialize_into(bytes);
+        }
+    }
+
+    /// Serializes the set in the [portable serialization format].
+    ///
+    /// [portable serialization format]: https://github.com/RoaringBitmap/RoaringFormatSpec
+    #[must_use]
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        self.serialize_into(&mut bytes);
+        bytes
+    }
+
+    /// Deserializes a set from the [portable serialization format].
+    ///
+    /// The whole of `bytes` must be a single serialized set.
+    ///
+    /// [portable serialization format]: https://github.com/RoaringBitmap/RoaringFormatSpec
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, RoaringFormatError> {
+        let mut reader = Reader(bytes);
+        let cookie = reader.u32()?;
+        let (count, run_flags) = if cookie == SERIAL_COOKIE_NO_RUNS {
+            (reader.u32()? as usize, None)
+        } else if cookie as u16 == SERIAL_COOKIE {
+            let count = (cookie >> 16) as usize + 1;
+            (count, Some(reader.take(count.div_ceil(8))?))
+        } else {
+            return Err(RoaringFormatError::UnknownCookie(cookie));
+        };
+        if count > 1 << 16 {
+            return Err(RoaringFormatError::InvalidHeader);
+        }
+        let header = reader.take(4 * count)?;
+        if run_flags.is_none() || count >= NO_OFFSET_THRESHOLD {
+            // The containers are stored back to back, so the offsets can be skipped.
+            reader.take(4 * count)?;
+        }
+
+        let mut containers: Vec<(u16, Container)> = Vec::with_capacity(count);
+        for (i, description) in header.chunks_exact(4).enumerate() {
+            let key = u16::from_le_bytes([description[0], description[1]]);
+            let len = u16::from_le_bytes([description[2], description[3]]) as usize + 1;
+            if containers
+                .last()
+                .is_some_and(|&(last_key, _)| last_key >= key)
+            {
+                return Err(RoaringFormatError::InvalidHeader);
+            }
+            let is_run = run_flags.is_some_and(|flags| flags[i / 8] & (1 << (i % 8)) != 0);
+            let container = if is_run {
+                Container::deserialize_runs(&mut reader, key)?
+            } else if len <= ARRAY_LIMIT {
+                Container::deserialize_array(&mut reader, key, len)?
+            } else {
+                Container::deserialize_bitmap(&mut reader)?
+            };
+            if container.len() != len {
+                return Err(RoaringFormatError::InvalidContainer(key));
+            }
+            containers.push((key, container));
+        }
+        match reader.0.len() {
+            0 => Ok(Self { containers }),
+            trailing => Err(RoaringFormatError::TrailingBytes(trailing)),
+        }
+    }
+
+    /// Creates an iterator over the values in the set.
+    #[must_use]
+    pub fn iter_indices<D>(&self) -> RoaringIndices<'_, D>
+    where
+        for<'a> RoaringIndices<'a, D>: Iterator<Item = u32>,
+    {
+        RoaringIndices::new(self)
+    }
+}
+
+/// Splits `value` into its upper and lower 16 bits.
+const fn split(value: u32) -> (u16, u16) {
+    ((value >> 16) as u16, value as u16)
+}
+
+/// Undoes [`split`].
+const fn join(key: u16, low: u16) -> u32 {
+    (key as u32) << 16 | low as u32
+}
+
+/// A cursor over serialized bytes.
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], RoaringFormatError> {
+        if self.0.len() < len {
+            return Err(RoaringFormatError::UnexpectedEnd);
+        }
+        let (taken, rest) = self.0.split_at(len);
+        self.0 = rest;
+        Ok(taken)
+    }
+
+    fn u16(&mut self) -> Result<u16, RoaringFormatError> {
+        self.take(2)
+            .map(|bytes| u16::from_le_bytes([bytes[0], bytes[1]]))
+    }
+
+    fn u32(&mut self) -> Result<u32, RoaringFormatError> {
+        self.take(4)
+            .map(|bytes| u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+}
+
+/// A stretch of consecutive values in a run container.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+struct Run {
+    start: u16,
+    end: u16,
+}
+
+/// A bitmap container, along with the number of bits set in it.
+#[derive(Clone)]
+struct Bitmap {
+    words: Box<[BitSet64; BITMAP_WORDS]>,
+    len: usize,
+}
+
+impl Bitmap {
+    fn new() -> Self {
+        Self {
+            words: Box::new([BitSet64::new(); BITMAP_WORDS]),
+            len: 0,
+        }
+    }
+
+    fn from_lows(lows: impl Iterator<Item = u16>) -> Self {
+        let mut bitmap = Self::new();
+        for low in lows {
+            bitmap.insert(low);
+        }
+        bitmap
+    }
+
+    fn contains(&self, low: u16) -> bool {
+        self.words[low as usize / 64].contains(low as usize % 64)
+    }
+
+    fn insert(&mut self, low: u16) -> bool {
+        let inserted = self.words[low as usize / 64].insert(low as usize % 64);
+        self.len += inserted as usize;
+        inserted
+    }
+
+    fn remove(&mut self, low: u16) -> bool {
+        let removed = self.words[low as usize / 64].remove(low as usize % 64);
+        self.len -= removed as usize;
+        removed
+    }
+
+    /// Applies `op` to each pair of words.
+    fn zip_with(&self, rhs: &Self, op: impl Fn(BitSet64, BitSet64) -> BitSet64) -> Self {
+        let mut result = self.clone();
+        for (lhs, &rhs) in result.words.iter_mut().zip(rhs.words.iter()) {
+            *lhs = op(*lhs, rhs);
+        }
+        result.len = result.words.iter().map(|word| word.len()).sum();
+        result
+    }
+}
+
+/// The values of a set that share their upper 16 bits.
+#[derive(Clone)]
+enum Container {
+    /// The lower 16 bits of at most [`ARRAY_LIMIT`] values, in ascending order.
+    Array(Vec<u16>),
+    /// More than [`ARRAY_LIMIT`] values as bits.
+    Bitmap(Bitmap),
+    /// Disjoint runs of values, in ascending order.
+    Run(Vec<Run>),
+}
+
+impl Container {
+    /// Creates the smaller of an array or bitmap container from sorted values, or [`None`] if
+    /// there are no values.
+    fn from_sorted(lows: Vec<u16>) -> Option<Self> {
+        match lows.len() {
+            0 => None,
+            1..=ARRAY_LIMIT => Some(Self::Array(lows)),
+            _ => Some(Self::Bitmap(Bitmap::from_lows(lows.into_iter()))),
+        }
+    }
+
+    /// Creates the smaller of an array or bitmap container from a bitmap, or [`None`] if the
+    /// bitmap is empty.
+    fn from_bitmap(bitmap: Bitmap) -> Option<Self> {
+        match bitmap.len {
+            0 => None,
+            1..=ARRAY_LIMIT => Some(Self::Array(Self::Bitmap(bitmap).lows().collect())),
+            _ => Some(Self::Bitmap(bitmap)),
+        }
+    }
+
+    fn to_bitmap(&self) -> Bitmap {
+        match self {
+            Self::Bitmap(bitmap) => bitmap.clone(),
+            _ => Bitmap::from_lows(self.lows()),
+        }
+    }
+
+    /// Returns an iterator over the lower 16 bits of the container's values, in ascending order.
+    fn lows(&self) -> impl Iterator<Item = u16> + '_ {
+        let mut indices = ContainerIndices::new(self);
+        iter::from_fn(move || indices.next_ascending())
+    }
+
+    fn len(&self) -> usize {
+        match self {
+            Self::Array(lows) => lows.len(),
+            Self::Bitmap(bitmap) => bitmap.len,
+            Self::Run(runs) => runs
+                .iter()
+                .map(|run| (run.end - run.start) as usize + 1)
+                .sum(),
+        }
+    }
+
+    fn contains(&self, low: u16) -> bool {
+        match self {
+            Self::Array(lows) => lows.binary_search(&low).is_ok(),
+            Self::Bitmap(bitmap) => bitmap.contains(low),
+            Self::Run(runs) => {
+                let i = runs.partition_point(|run| run.start <= low);
+                i > 0 && runs[i - 1].end >= low
+            }
+        }
+    }
+
+    fn min(&self) -> u16 {
+        match self {
+            Self::Array(lows) => lows[0],
+            Self::Bitmap(_) => self.lows().next().unwrap(),
+            Self::Run(runs) => runs[0].start,
+        }
+    }
+
+    fn max(&self) -> u16 {
+        match self {
+            Self::Array(lows) => lows[lows.len() - 1],
+            Self::Bitmap(_) => ContainerIndices::new(self).next_descending().unwrap(),
+            Self::Run(runs) => runs[runs.len() - 1].end,
+        }
+    }
+
+    fn insert(&mut self, low: u16) -> bool {
+        match self {
+            Self::Array(lows) => {
+                let Err(i) = lows.binary_search(&low) else {
+                    return false;
+                };
+                lows.insert(i, low);
+                if lows.len() > ARRAY_LIMIT {
+                    *self = Self::Bitmap(self.to_bitmap());
+                }
+                true
+            }
+            Self::Bitmap(bitmap) => bitmap.insert(low),
+            Self::Run(runs) => {
+                let i = runs.partition_point(|run| run.start <= low);
+                if i > 0 && runs[i - 1].end >= low {
+                    return false;
+                }
+                let joins_prev = i > 0 && runs[i - 1].end + 1 == low;
+                let joins_next = i < runs.len() && runs[i].start == low + 1;
+                match (joins_prev, joins_next) {
+                    (true, true) => {
+                        runs[i - 1].end = runs[i].end;
+                        runs.remove(i);
+                    }
+                    (true, false) => runs[i - 1].end = low,
+                    (false, true) => runs[i].start = low,
+                    (false, false) => runs.insert(
+                        i,
+                        Run {
+                            start: low,
+                            end: low,
+                        },
+                    ),
+                }
+                true
+            }
+        }
+    }
+
+    fn remove(&mut self, low: u16) -> bool {
+        match self {
+            Self::Array(lows) => {
+                let Ok(i) = lows.binary_search(&low) else {
+                    return false;
+                };
+                lows.remove(i);
+                true
+            }
+            Self::Bitmap(bitmap) => {
+                let removed = bitmap.remove(low);
+                if bitmap.len <= ARRAY_LIMIT {
+                    *self = Self::Array(self.lows().collect());
+                }
+                removed
+            }
+            Self::Run(runs) => {
+                let i = runs.partition_point(|run| run.start <= low);
+                if i == 0 || runs[i - 1].end < low {
+                    return false;
+                }
+                let run = runs[i - 1];
+                if run.start == run.end {
+                    runs.remove(i - 1);
+                } else if low == run.start {
+                    runs[i - 1].start += 1;
+                } else if low == run.end {
+                    runs[i - 1].end -= 1;
+                } else {
+                    runs[i - 1].end = low - 1;
+                    runs.insert(
+                        i,
+                        Run {
+                            start: low + 1,
+                            end: run.end,
+                        },
+                    );
+                }
+                true
+            }
+        }
+    }
+
+    fn intersection(&self, rhs: &Self) -> Option<Self> {
+        match (self, rhs) {
+            (Self::Array(lows), other) | (other, Self::Array(lows)) => Self::from_sorted(
+                lows.iter()
+                    .copied()
+                    .filter(|&low| other.contains(low))
+                    .collect(),
+            ),
+            _ => Self::from_bitmap(
+                self.to_bitmap()
+                    .zip_with(&rhs.to_bitmap(), BitSet64::intersection),
+            ),
+        }
+    }
+
+    fn union(&self, rhs: &Self) -> Self {
+        match (self, rhs) {
+            (Self::Array(lhs), Self::Array(rhs)) => {
+                Self::from_sorted(merge(lhs, rhs, true)).unwrap()
+            }
+            // Two non-empty containers never have an empty union.
+            _ => Self::from_bitmap(self.to_bitmap().zip_with(&rhs.to_bitmap(), BitSet64::union))
+                .unwrap(),
+        }
+    }
+
+    fn difference(&self, rhs: &Self) -> Option<Self> {
+        match self {
+            Self::Array(lows) => Self::from_sorted(
+                lows.iter()
+                    .copied()
+                    .filter(|&low| !rhs.contains(low))
+                    .collect(),
+            ),
+            _ => Self::from_bitmap(
+                self.to_bitmap()
+                    .zip_with(&rhs.to_bitmap(), BitSet64::difference),
+            ),
+        }
+    }
+
+    fn symmetric_difference(&self, rhs: &Self) -> Option<Self> {
+        match (self, rhs) {
+            (Self::Array(lhs), Self::Array(rhs)) => Self::from_sorted(merge(lhs, rhs, false)),
+            _ => Self::from_bitmap(
+                self.to_bitmap()
+                    .zip_with(&rhs.to_bitmap(), BitSet64::symmetric_difference),
+            ),
+        }
+    }
+
+    fn run_optimize(&mut self) {
+        let len = self.len();
+        let mut runs = 0;
+        let mut prev = None;
+        for low in self.lows() {
+            if prev.is_none_or(|prev: u16| prev + 1 != low) {
+                runs += 1;
+            }
+            prev = Some(low);
+        }
+        let run_size = 2 + 4 * runs;
+        let other_size = if len <= ARRAY_LIMIT {
+            2 * len
+        } else {
+            2 * BITMAP_WORDS * 4
+        };
+        match self {
+            Self::Run(_) if run_size >= other_size => {
+                *self = if len <= ARRAY_LIMIT {
+                    Self::Array(self.lows().collect())
+                } else {
+                    Self::Bitmap(self.to_bitmap())
+                };
+            }
+            Self::Array(_) | Self::Bitmap(_) if run_size < other_size => {
+                let mut runs: Vec<Run> = Vec::with_capacity(runs);
+                for low in self.lows() {
+                    match runs.last_mut() {
+                        Some(run) if run.end + 1 == low => run.end = low,
+                        _ => runs.push(Run {
+                            start: low,
+                            end: low,
+                        }),
+                    }
+                }
+                *self = Self::Run(runs);
+            }
+            _ => {}
+        }
+    }
+
+    fn serialized_size(&self) -> usize {
+        match self {
+            Self::Array(lows) => 2 * lows.len(),
+            Self::Bitmap(_) => 8 * BITMAP_WORDS,
+            Self::Run(runs) => 2 + 4 * runs.len(),
+        }
+    }
+
+    fn serialize_into(&self, bytes: &mut Vec<u8>) {
+        match self {
+            Self::Array(lows) => {
+                for low in lows {
+                    bytes.extend(low.to_le_bytes());
+                }
+            }
+            Self::Bitmap(bitmap) => {
+                for word in bitmap.words.iter() {
+                    bytes.extend(word.bits().to_le_bytes());
+                }
+            }
+            Self::Run(runs) => {
+                bytes.extend((runs.len() as u16).to_le_bytes());
+                for run in runs {
+                    bytes.extend(run.start.to_le_bytes());
+                    bytes.extend((run.end - run.start).to_le_bytes());
+                }
+            }
+        }
+    }
+
+    fn deserialize_array(
+        reader: &mut Reader<'_>,
+        key: u16,
+        len: usize,
+    ) -> Result<Self, RoaringFormatError> {
+        let lows: Vec<u16> = reader
+            .take(2 * len)?
+            .chunks_exact(2)
+            .map(|bytes| u16::from_le_bytes([bytes[0], bytes[1]]))
+            .collect();
+        if lows.is_sorted_by(|lhs, rhs| lhs < rhs) {
+            Ok(Self::Array(lows))
+        } else {
+            Err(RoaringFormatError::InvalidContainer(key))
+        }
+    }
+
+    fn deserialize_bitmap(reader: &mut Reader<'_>) -> Result<Self, RoaringFormatError> {
+        let bytes = reader.take(8 * BITMAP_WORDS)?;
+        let mut bitmap = Bitmap::new();
+        for (word, bytes) in bitmap.words.iter_mut().zip(bytes.chunks_exact(8)) {
+            *word = BitSet64::from_bits(u64::from_le_bytes(bytes.try_into().unwrap()));
+        }
+        bitmap.len = bitmap.words.iter().map(|word| word.len()).sum();
+        Ok(Self::Bitmap(bitmap))
+    }
+
+    fn deserialize_runs(reader: &mut Reader<'_>, key: u16) -> Result<Self, RoaringFormatError> {
+        let count = reader.u16()? as usize;
+        let mut runs: Vec<Run> = Vec::with_capacity(count);
+        for _ in 0..count {
+            let start = reader.u16()?;
+            let end = start
+                .checked_add(reader.u16()?)
+                .ok_or(RoaringFormatError::InvalidContainer(key))?;
+            if runs.last().is_some_and(|last| last.end >= start) {
+                return Err(RoaringFormatError::InvalidContainer(key));
+            }
+            runs.push(Run { start, end });
+        }
+        Ok(Self::Run(runs))
+    }
+}
+
+/// Merges two sorted slices, keeping the values they share only if `keep_shared` is `true`.
+fn merge(lhs: &[u16], rhs: &[u16], keep_shared: bool) -> Vec<u16> {
+    let mut merged = Vec::with_capacity(lhs.len() + rhs.len());
+    let (mut i, mut j) = (0, 0);
+    while i < lhs.len() && j < rhs.len() {
+        match lhs[i].cmp(&rhs[j]) {
+            Ordering::Less => {
+                merged.push(lhs[i]);
+                i += 1;
+            }
+            Ordering::Greater => {
+                merged.push(rhs[j]);
+                j += 1;
+            }
+            Ordering::Equal => {
+                if keep_shared {
+                    merged.push(lhs[i]);
+                }
+                i += 1;
+                j += 1;
+            }
+        }
+    }
+    merged.extend_from_slice(&lhs[i..]);
+    merged.extend_from_slice(&rhs[j..]);
+    merged
+}
+
+/// An iterator over the lower 16 bits of the values in a single container.
+enum ContainerIndices<'a> {
+    Array(slice::Iter<'a, u16>),
+    Bitmap {
+        words: &'a [BitSet64],
+        front: usize,
+        back: usize,
+        current: u64,
+        position: usize,
+    },
+    Run {
+        runs: slice::Iter<'a, Run>,
+        current: RangeInclusive<u16>,
+    },
+}
+
+impl<'a> ContainerIndices<'a> {
+    fn new(container: &'a Container) -> Self {
+        match container {
+            Container::Array(lows) => Self::Array(lows.iter()),
+            Container::Bitmap(bitmap) => Self::Bitmap {
+                words: &bitmap.words[..],
+                front: 0,
+                back: BITMAP_WORDS,
+                current: 0,
+                position: 0,
+            },
+            #[allow(clippy::reversed_empty_ranges)]
+            Container::Run(runs) => Self::Run {
+                runs: runs.iter(),
+                current: 1..=0,
+            },
+        }
+    }
+
+    fn next_ascending(&mut self) -> Option<u16> {
+        match self {
+            Self::Array(lows) => lows.next().copied(),
+            Self::Bitmap {
+                words,
+                front,
+                back,
+                current,
+                position,
+            } => {
+                while *current == 0 {
+                    if *front >= *back {
+                        return None;
+                    }
+                    *current = words[*front].bits();
+                    *position = *front;
+                    *front += 1;
+                }
+                let bit = current.trailing_zeros() as usize;
+                *current &= *current - 1;
+                Some((64 * *position + bit) as u16)
+            }
+            Self::Run { runs, current } => loop {
+                if let Some(low) = current.next() {
+                    return Some(low);
+                }
+                let run = runs.next()?;
+                *current = run.start..=run.end;
+            },
+        }
+    }
+
+    fn next_descending(&mut self) -> Option<u16> {
+        match self {
+            Self::Array(lows) => lows.next_back().copied(),
+            Self::Bitmap {
+                words,
+                front,
+                back,
+                current,
+                position,
+            } => {
+                while *current == 0 {
+                    if *back <= *front {
+                        return None;
+                    }
+                    *back -= 1;
+                    *current = words[*back].bits();
+                    *position = *back;
+                }
+                let bit = current.ilog2() as usize;
+                *current ^= 1 << bit;
+                Some((64 * *position + bit) as u16)
+            }
+            Self::Run { runs, current } => loop {
+                if let Some(low) = current.next_back() {
+                    return Some(low);
+                }
+                let run = runs.next_back()?;
+                *current = run.start..=run.end;
+            },
+        }
+    }
+}
+
+/// An iterator over the values in a [`RoaringBitmap`].
+///
+/// The values come in [`Ascending`] or [`Descending`] order, picked by `D`. Custom
+/// [`Direction`](crate::Direction)s only order indices below 128, so they aren't supported here.
+pub struct RoaringIndices<'a, D = Ascending> {
+    containers: &'a [(u16, Container)],
+    current: Option<(u16, ContainerIndices<'a>)>,
+    _marker: PhantomData<D>,
+}
+
+impl<'a, D> RoaringIndices<'a, D> {
+    /// Creates an iterator over the values in `set`.
+    pub fn new(set: &'a RoaringBitmap) -> Self {
+        Self {
+            containers: &set.containers,
+            current: None,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl Iterator for RoaringIndices<'_, Ascending> {
+    type Item = u32;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some((key, indices)) = &mut self.current
+                && let Some(low) = indices.next_ascending()
+            {
+                return Some(join(*key, low));
+            }
+            let ((key, container), rest) = self.containers.split_first()?;
+            self.containers = rest;
+            self.current = Some((*key, ContainerIndices::new(container)));
+        }
+    }
+}
+
+impl Iterator for RoaringIndices<'_, Descending> {
+    type Item = u32;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some((key, indices)) = &mut self.current
+                && let Some(low) = indices.next_descending()
+            {
+                return Some(join(*key, low));
+            }
+            let ((key, container), rest) = self.containers.split_last()?;
+            self.containers = rest;
+            self.current = Some((*key, ContainerIndices::new(container)));
+        }
+    }
+}
+
+impl fmt::Debug for RoaringBitmap {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set()
+            .entries(self.iter_indices::<Ascending>())
+            .finish()
+    }
+}
+
+impl PartialEq for RoaringBitmap {
+    fn eq(&self, other: &Self) -> bool {
+        self.containers.len() == other.containers.len()
+            && self.containers.iter().zip(&other.containers).all(
+                |((lhs_key, lhs), (rhs_key, rhs))| {
+                    lhs_key == rhs_key && lhs.len() == rhs.len() && lhs.lows().eq(rhs.lows())
+                },
+            )
+    }
+}
+
+impl Eq for RoaringBitmap {}
+
+impl Hash for RoaringBitmap {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        // Containers may store the same values in different ways, so only the values are hashed.
+        for (key, container) in &self.containers {
+            key.hash(state);
+            container.len().hash(state);
+            for low in container.lows() {
+                low.hash(state);
+            }
+        }
+    }
+}
+
+impl FromIterator<u32> for RoaringBitmap {
+    fn from_iter<I: IntoIterator<Item = u32>>(iter: I) -> Self {
+        let mut set = Self::new();
+        set.extend(iter);
+        set
+    }
+}
+
+impl Extend<u32> for RoaringBitmap {
+    fn extend<I: IntoIterator<Item = u32>>(&mut self, iter: I) {
+        for value in iter {
+            self.insert_quiet(value);
+        }
+    }
+}
+
+impl BitAnd for &RoaringBitmap {
+    type Output = RoaringBitmap;
+
+    fn bitand(self, rhs: Self) -> Self::Output {
+        self.intersection(rhs)
+    }
+}
+
+impl BitAnd for RoaringBitmap {
+    type Output = Self;
+
+    fn bitand(self, rhs: Self) -> Self::Output {
+        self.intersection(&rhs)
+    }
+}
+
+impl BitAndAssign<&RoaringBitmap> for RoaringBitmap {
+    fn bitand_assign(&mut self, rhs: &Self) {
+        *self = self.intersection(rhs);
+    }
+}
+
+impl BitAndAssign for RoaringBitmap {
+    fn bitand_assign(&mut self, rhs: Self) {
+        *self &= &rhs;
+    }
+}
+
+impl BitOr for &RoaringBitmap {
+    type Output = RoaringBitmap;
+
+    fn bitor(self, rhs: Self) -> Self::Output {
+        self.union(rhs)
+    }
+}
+
+impl BitOr for RoaringBitmap {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self::Output {
+        self.union(&rhs)
+    }
+}
+
+impl BitOrAssign<&RoaringBitmap> for RoaringBitmap {
+    fn bitor_assign(&mut self, rhs: &Self) {
+        *self = self.union(rhs);
+    }
+}
+
+impl BitOrAssign for RoaringBitmap {
+    fn bitor_assign(&mut self, rhs: Self) {
+        *self |= &rhs;
+    }
+}
+
+impl BitXor for &RoaringBitmap {
+    type Output = RoaringBitmap;
+
+    fn bitxor(self, rhs: Self) -> Self::Output {
+        self.symmetric_difference(rhs)
+    }
+}
+
+impl BitXor for RoaringBitmap {
+    type Output = Self;
+
+    fn bitxor(self, rhs: Self) -> Self::Output {
+        self.symmetric_difference(&rhs)
+    }
+}
+
+impl BitXorAssign<&RoaringBitmap> for RoaringBitmap {
+    fn bitxor_assign(&mut self, rhs: &Self) {
+        *self = self.symmetric_difference(rhs);
+    }
+}
+
+impl BitXorAssign for RoaringBitmap {
+    fn bitxor_assign(&mut self, rhs: Self) {
+        *self ^= &rhs;
+    }
+}
+
+impl Sub for &RoaringBitmap {
+    type Output = RoaringBitmap;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        self.difference(rhs)
+    }
+}
+
+impl Sub for RoaringBitmap {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        self.difference(&rhs)
+    }
+}
+
+impl SubAssign<&RoaringBitmap> for RoaringBitmap {
+    fn sub_assign(&mut self, rhs: &Self) {
+        *self = self.difference(rhs);
+    }
+}
+
+impl SubAssign for RoaringBitmap {
+    fn sub_assign(&mut self, rhs: Self) {
+        *self -= &rhs;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{
+        collections::BTreeSet,
+        hash::{BuildHasher, RandomState},
+    };
+
+    /// A small, deterministic pseudo-random sequence, so the tests don't need any dependencies.
+    fn pseudo_random(seed: u64, count: usize, modulus: u32) -> Vec<u32> {
+        let mut state = seed;
+        (0..count)
+            .map(|_| {
+                state = state
+                    .wrapping_mul(6364136223846793005)
+                    .wrapping_add(1442695040888963407);
+                (state >> 32) as u32 % modulus
+            })
+            .collect()
+    }
+
+    fn assert_matches(set: &RoaringBitmap, reference: &BTreeSet<u32>) {
+        assert_eq!(set.len(), reference.len() as u64);
+        assert!(
+            set.iter_indices::<Ascending>()
+                .eq(reference.iter().copied())
+        );
+        assert!(
+            set.iter_indices::<Descending>()
+                .eq(reference.iter().rev().copied())
+        );
+    }
+
+    fn container_kinds(set: &RoaringBitmap) -> Vec<&'static str> {
+        set.containers
+            .iter()
+            .map(|(_, container)| match container {
+                Container::Array(_) => "array",
+                Container::Bitmap(_) => "bitmap",
+                Container::Run(_) => "run",
+            })
+            .collect()
+    }
+
+    #[test]
+    fn new_set_is_empty() {
+        let set = RoaringBitmap::new();
+        assert!(set.is_empty());
+        assert_eq!(set.len(), 0);
+        assert_eq!(set.min_index_checked(), None);
+        assert_eq!(set.max_index_checked(), None);
+        assert_eq!(set.iter_indices::<Ascending>().next(), None);
+    }
+
+    #[test]
+    fn insert_and_remove() {
+        let mut set = RoaringBitmap::new();
+        assert!(set.insert(7));
+        assert!(!set.insert(7));
+        assert!(set.insert(u32::MAX));
+        assert!(set.insert(70_000));
+        assert!(set.contains(70_000));
+        assert!(!set.contains(70_001));
+        assert_eq!(set.min_index_checked(), Some(7));
+        assert_eq!(set.max_index_checked(), Some(u32::MAX));
+        assert!(set.remove(70_000));
+        assert!(!set.remove(70_000));
+        assert!(!set.remove(12_345_678));
+        assert_eq!(format!("{set:?}"), "{7, 4294967295}");
+        assert_eq!(set.containers.len(), 2);
+    }
+
+    #[test]
+    fn array_becomes_bitmap_and_back() {
+        let mut set: RoaringBitmap = (0..ARRAY_LIMIT as u32).map(|i| 2 * i).collect();
+        assert_eq!(container_kinds(&set), ["array"]);
+        set.insert_quiet(1);
+        assert_eq!(container_kinds(&set), ["bitmap"]);
+        assert_eq!(set.max_index_checked(), Some(2 * (ARRAY_LIMIT as u32 - 1)));
+        set.remove_quiet(0);
+        assert_eq!(container_kinds(&set), ["array"]);
+        assert_eq!(set.min_index_checked(), Some(1));
+        assert_eq!(set.len(), ARRAY_LIMIT as u64);
+    }
+
+    #[test]
+    fn run_containers() {
+        let mut set: RoaringBitmap = (100..60_000).chain([65_536]).collect();
+        assert_eq!(container_kinds(&set), ["bitmap", "array"]);
+        set.run_optimize();
+        assert_eq!(container_kinds(&set), ["run", "array"]);
+        assert!(set.contains(100) && set.contains(59_999) && !set.contains(60_000));
+
+        assert!(set.insert(60_000));
+        assert!(set.insert(99));
+        assert!(set.insert(60_002));
+        assert!(set.insert(60_001));
+        assert!(set.remove(30_000));
+        assert!(set.remove(99));
+        assert!(!set.remove(30_000));
+        let expected: BTreeSet<u32> = (100..60_003)
+            .filter(|&i| i != 30_000)
+            .chain([65_536])
+            .collect();
+        assert_matches(&set, &expected);
+        assert_eq!(set.containers[0].1.serialized_size(), 2 + 4 * 2);
+
+        let mut sparse: RoaringBitmap = [1, 3, 5].into_iter().collect();
+        sparse.run_optimize();
+        assert_eq!(container_kinds(&sparse), ["array"]);
+    }
+
+    #[test]
+    fn set_algebra_matches_reference() {
+        let build = |values: &[u32]| -> (RoaringBitmap, BTreeSet<u32>) {
+            (
+                values.iter().copied().collect(),
+                values.iter().copied().collect(),
+            )
+        };
+        let mut a_values = pseudo_random(1, 20_000, 300_000);
+        a_values.extend(140_000..200_000);
+        let b_values = pseudo_random(2, 5_000, 300_000);
+        let (mut a, a_ref) = build(&a_values);
+        let (b, b_ref) = build(&b_values);
+        a.run_optimize();
+        assert!(container_kinds(&a).contains(&"run"));
+        assert!(container_kinds(&a).contains(&"bitmap") || container_kinds(&a).contains(&"array"));
+
+        assert_matches(&(&a | &b), &(&a_ref | &b_ref));
+        assert_matches(&(&a & &b), &(&a_ref & &b_ref));
+        assert_matches(&(&a - &b), &(&a_ref - &b_ref));
+        assert_matches(&(&b - &a), &(&b_ref - &a_ref));
+        assert_matches(&(&a ^ &b), &(&a_ref ^ &b_ref));
+        assert!((&a & &b).is_subset(&a));
+        assert!(a.is_superset(&(&a - &b)));
+        assert!((&a - &b).is_disjoint(&b));
+        for result in [&a | &b, &a & &b, &a - &b, &b - &a, &a ^ &b] {
+            assert_eq!(RoaringBitmap::from_bytes(&result.to_bytes()), Ok(result));
+        }
+        let mut small: RoaringBitmap = (0..10).collect();
+        small.run_optimize();
+        let union = &small | &[100].into_iter().collect();
+        assert_eq!(container_kinds(&union), ["array"]);
+        assert_eq!(RoaringBitmap::from_bytes(&union.to_bytes()), Ok(union));
+
+        let mut c = a.clone();
+        c ^= &b;
+        c |= &b;
+        assert_eq!(c, &a | &b);
+        c &= &b;
+        assert_eq!(c, b);
+        c -= &a;
+        assert_eq!(c, &b - &a);
+    }
+
+    #[test]
+    fn equality_ignores_representation() {
+        let set: RoaringBitmap = (0..5_000).collect();
+        let mut optimized = set.clone();
+        optimized.run_optimize();
+        assert_eq!(container_kinds(&optimized), ["run"]);
+        assert_eq!(set, optimized);
+        let state = RandomState::new();
+        assert_eq!(state.hash_one(&set), state.hash_one(&optimized));
+    }
+
+    #[test]
+    fn serialize_without_runs() {
+        let set: RoaringBitmap = [1, 2, 3 << 16 | 5].into_iter().collect();
+        #[rustfmt::skip]
+        let expected = [
+            0x3a, 0x30, 0, 0, 2, 0, 0, 0, // cookie and container count
+            0, 0, 1, 0, 3, 0, 0, 0,       // keys and cardinalities
+            24, 0, 0, 0, 28, 0, 0, 0,     // offsets
+            1, 0, 2, 0, 5, 0,             // containers
+        ];
+        assert_eq!(set.to_bytes(), expected);
+        assert_eq!(set.serialized_size(), expected.len());
+        assert_eq!(RoaringBitmap::from_bytes(&expected), Ok(set));
+    }
+
+    #[test]
+    fn serialize_with_runs() {
+        let mut set: RoaringBitmap = (0..100).collect();
+        set.run_optimize();
+        #[rustfmt::skip]
+        let expected = [
+            0x3b, 0x30, 0, 0, // cookie and container count
+            0b1,              // run flags
+            0, 0, 99, 0,      // keys and cardinalities
+            1, 0, 0, 0, 99, 0 // containers
+        ];
+        assert_eq!(set.to_bytes(), expected);
+        assert_eq!(set.serialized_size(), expected.len());
+        assert_eq!(RoaringBitmap::from_bytes(&expected), Ok(set));
+        assert_eq!(
+            RoaringBitmap::from_bytes(&RoaringBitmap::new().to_bytes()),
+            Ok(RoaringBitmap::new())
+        );
+    }
+
+    #[test]
+    fn serialization_round_trip() {
+        let mut values = pseudo_random(3, 30_000, 1 << 20);
+        values.extend(pseudo_random(4, 20_000, 1 << 16));
+        values.extend(500_000..520_000);
+        values.extend((0..8).map(|i| i << 28));
+        let mut set: RoaringBitmap = values.into_iter().collect();
+        set.run_optimize();
+        let kinds = container_kinds(&set);
+        assert!(kinds.len() >= NO_OFFSET_THRESHOLD);
+        assert!(
+            ["array", "bitmap", "run"]
+                .iter()
+                .all(|kind| kinds.contains(kind))
+        );
+
+        let bytes = set.to_bytes();
+        assert_eq!(bytes.len(), set.serialized_size());
+        let copy = RoaringBitmap::from_bytes(&bytes).unwrap();
+        assert_eq!(container_kinds(&copy), kinds);
+        assert_eq!(copy, set);
+    }
+
+    #[test]
+    fn deserialization_errors() {
+        let bytes = RoaringBitmap::from_iter([1, 2, 3]).to_bytes();
+        assert_eq!(
+            RoaringBitmap::from_bytes(&bytes[..bytes.len() - 1]),
+            Err(RoaringFormatError::UnexpectedEnd),
+        );
+        assert_eq!(
+            RoaringBitmap::from_bytes(&[bytes.as_slice(), &[0]].concat()),
+            Err(RoaringFormatError::TrailingBytes(1)),
+        );
+        assert_eq!(
+            RoaringBitmap::from_bytes(&[0, 0, 0, 0]),
+            Err(RoaringFormatError::UnknownCookie(0)),
+        );
+        let mut unsorted = bytes.clone();
+        unsorted[bytes.len() - 2] = 0;
+        assert_eq!(
+            RoaringBitmap::from_bytes(&unsorted),
+            Err(RoaringFormatError::InvalidContainer(0)),
+        );
+    }
+}
@@ -0,0 +1,734 @@
+use crate::{Ascending, Descending};
+use alloc::{vec, vec::Vec};
+use core::{
+    fmt,
+    hash::{Hash, Hasher},
+    marker::PhantomData,
+    ops::{BitAnd, BitAndAssign, BitOr, BitOrAssign, BitXor, BitXorAssign, Sub, SubAssign},
+};
+
+/// A growable set of bits, stored as a [`Vec`] of [`u64`] words.
+///
+/// Unlike the fixed-size bitsets, a [`BitVecSet`] has no upper limit on the indices it can hold;
+/// [`insert`](Self::insert)ing an index past the end of the current storage grows it as needed.
+///
+/// ```
+/// use rose_bitsets::{Ascending, BitVecSet};
+///
+/// let mut set = BitVecSet::new();
+/// set.insert_quiet(3);
+/// set.insert_quiet(1_000);
+///
+/// assert_eq!(set.len(), 2);
+/// assert!(set.contains(1_000));
+/// assert!(set.iter_indices::<Ascending>().eq([3, 1_000]));
+/// ```
+#[derive(Clone, Default)]
+pub struct BitVecSet {
+    words: Vec<u64>,
+}
+
+impl BitVecSet {
+    /// Creates an empty set.
+    #[doc(alias = "empty")]
+    #[must_use]
+    pub const fn new() -> Self {
+        Self { words: Vec::new() }
+    }
+
+    /// Creates an empty set with room for at least `bits` bits before it needs to grow.
+    #[must_use]
+    pub fn with_capacity(bits: usize) -> Self {
+        Self {
+            words: vec![0; bits.div_ceil(64)],
+        }
+    }
+
+    /// Creates a set with the given words, ordered from least to most significant.
+    #[must_use]
+    pub const fn from_words(words: Vec<u64>) -> Self {
+        Self { words }
+    }
+
+    /// Returns the underlying words of the set, ordered from least to most significant.
+    ///
+    /// The slice may end with any number of zeroed words.
+    #[must_use]
+    pub fn words(&self) -> &[u64] {
+        &self.words
+    }
+
+    /// Consumes the set, returning its underlying words.
+    #[must_use]
+    pub fn into_words(self) -> Vec<u64> {
+        self.words
+    }
+
+    /// Returns the number of bits the set can hold before it needs to grow.
+    #[must_use]
+    pub fn capacity(&self) -> usize {
+        64 * self.words.len()
+    }
+
+    /// Drops any trailing zeroed words, releasing as much memory as possible.
+    pub fn shrink_to_fit(&mut self) {
+        self.words.truncate(self.trimmed().len());
+        self.words.shrink_to_fit();
+    }
+
+    /// Returns the words without any trailing zeroes.
+    fn trimmed(&self) -> &[u64] {
+        let len = self
+            .words
+            .iter()
+            .rposition(|&word| word != 0)
+            .map_or(0, |i| i + 1);
+        &self.words[..len]
+    }
+
+    /// Applies `op` to each pair of words, treating missing words as zero.
+    fn zip_with(&self, rhs: &Self, op: impl Fn(u64, u64) -> u64) -> Self {
+        let len = self.words.len().max(rhs.words.len());
+        let word = |words: &[u64], i: usize| words.get(i).copied().unwrap_or(0);
+        Self {
+            words: (0..len)
+                .map(|i| op(word(&self.words, i), word(&rhs.words, i)))
+                .collect(),
+        }
+    }
+
+    /// Creates a new set with values that are in both `self` and `rhs`.
+    #[must_use]
+    pub fn intersection(&self, rhs: &Self) -> Self {
+        Self {
+            words: self
+                .words
+                .iter()
+                .zip(&rhs.words)
+                .map(|(lhs, rhs)| lhs & rhs)
+                .collect(),
+        }
+    }
+
+    /// Creates a new set with values that are in `self` or `rhs`.
+    #[must_use]
+    pub fn union(&self, rhs: &Self) -> Self {
+        self.zip_with(rhs, |lhs, rhs| lhs | rhs)
+    }
+
+    /// Creates a new set with values that are in `self`, but not in `rhs`.
+    #[must_use]
+    pub fn difference(&self, rhs: &Self) -> Self {
+        self.zip_with(rhs, |lhs, rhs| lhs & !rhs)
+    }
+
+    /// Creates a new set with values that are in `self` or `rhs`, but not in both.
+    #[must_use]
+    pub fn symmetric_difference(&self, rhs: &Self) -> Self {
+        self.zip_with(rhs, |lhs, rhs| lhs ^ rhs)
+    }
+
+    /// Returns `true` if `self` has no elements in common with `rhs`. This is equivalent to
+    /// checking for an empty intersection.
+    #[must_use]
+    pub fn is_disjoint(&self, rhs: &Self) -> bool {
+        self.words
+            .iter()
+            .zip(&rhs.words)
+            .all(|(lhs, rhs)| lhs & rhs == 0)
+    }
+
+    /// Returns `true` if the set is a subset of another, i.e., `rhs` contains at least all the
+    /// values in `self`.
+    #[must_use]
+    pub fn is_subset(&self, rhs: &Self) -> bool {
+        self.words
+            .iter()
+            .enumerate()
+            .all(|(i, &word)| word & !rhs.words.get(i).copied().unwrap_or(0) == 0)
+    }
+
+    /// Returns `true` if the set is a strict subset of another, i.e., `rhs` contains all the
+    /// values in `self` **and** is larger than `self`.
+    #[must_use]
+    pub fn is_strict_subset(&self, rhs: &Self) -> bool {
+        self.is_subset(rhs) && self != rhs
+    }
+
+    /// Returns `true` if the set is a superset of another, i.e., `self` contains at least all the
+    /// values in `rhs`.
+    #[must_use]
+    pub fn is_superset(&self, rhs: &Self) -> bool {
+        rhs.is_subset(self)
+    }
+
+    /// Returns `true` if the set is a strict superset of another, i.e., `self` contains all the
+    /// values in `rhs` **and** is larger than `rhs`.
+    #[must_use]
+    pub fn is_strict_superset(&self, rhs: &Self) -> bool {
+        self.is_superset(rhs) && self != rhs
+    }
+
+    /// Returns `true` if the set contains no elements.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.words.iter().all(|&word| word == 0)
+    }
+
+    /// Returns the number of elements in the set.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.words
+            .iter()
+            .map(|word| word.count_ones() as usize)
+            .sum()
+    }
+
+    /// Returns `true` if the bit at `index` is set.
+    #[must_use]
+    pub fn contains(&self, index: usize) -> bool {
+        self.words
+            .get(index / 64)
+            .is_some_and(|word| word & (1 << (index % 64)) != 0)
+    }
+
+    /// Gets the bit at `index`.
+    ///
+    /// If `index` is past the end of the set's storage, this will simply return `false`.
+    #[must_use]
+    pub fn get(&self, index: usize) -> bool {
+        self.contains(index)
+    }
+
+    /// Returns the index of the least significant bit that is set, or [`None`] if no bits are
+    /// set.
+    #[must_use]
+    pub fn min_index_checked(&self) -> Option<usize> {
+        self.words
+            .iter()
+            .position(|&word| word != 0)
+            .map(|i| 64 * i + self.words[i].trailing_zeros() as usize)
+    }
+
+    /// Returns the index of the most significant bit that is set, or [`None`] if no bits are set.
+    #[must_use]
+    pub fn max_index_checked(&self) -> Option<usize> {
+        self.words
+            .iter()
+            .rposition(|&word| word != 0)
+            .map(|i| 64 * i + self.words[i].ilog2() as usize)
+    }
+
+    /// Clears the set, removing all values.
+    ///
+    /// This keeps the allocated storage around for later use.
+    pub fn clear(&mut self) {
+        self.words.fill(0);
+    }
+
+    /// Sets the bit at `index` to `1`, growing the set if necessary.
+    ///
+    /// If you would like to know if the insertion succeeded, use [`insert`](Self::insert)
+    /// instead.
+    ///
+    /// # Panics
+    ///
+    /// The set grows to `index / 64 + 1` words, so a huge `index` needs a huge allocation. Like
+    /// [`Vec::resize`], this panics if the words would take up more than [`isize::MAX`] bytes, and
+    /// aborts if the allocation fails.
+    pub fn insert_quiet(&mut self, index: usize) {
+        let i = index / 64;
+        if i >= self.words.len() {
+            self.words.resize(i + 1, 0);
+        }
+        self.words[i] |= 1 << (index % 64);
+    }
+
+    /// Sets the bit at `index` to `1`, growing the set if necessary. Returns whether the bit was
+    /// not already set.
+    ///
+    /// If the return value is not needed, use [`insert_quiet`](Self::insert_quiet) instead.
+    ///
+    /// # Panics
+    ///
+    /// Panics in the same cases as [`insert_quiet`](Self::insert_quiet).
+    #[must_use = "consider using the return value or calling `insert_quiet` instead"]
+    pub fn insert(&mut self, index: usize) -> bool {
+        let was_set = self.contains(index);
+        self.insert_quiet(index);
+        !was_set
+    }
+
+    /// Sets the bit at `index` to `bit`.
+    ///
+    /// If you would like to know the old value of the bit, use [`replace`](Self::replace)
+    /// instead.
+    ///
+    /// # Panics
+    ///
+    /// Panics in the same cases as [`insert_quiet`](Self::insert_quiet) when `bit` is `true`.
+    pub fn replace_quiet(&mut self, index: usize, bit: bool) {
+        if bit {
+            self.insert_quiet(index)
+        } else {
+            self.remove_quiet(index)
+        }
+    }
+
+    /// Sets the bit at `index` to `bit`. Returns the old value of the bit.
+    ///
+    /// If the return value is not needed, use [`replace_quiet`](Self::replace_quiet) instead.
+    ///
+    /// # Panics
+    ///
+    /// Panics in the same cases as [`insert_quiet`](Self::insert_quiet) when `bit` is `true`.
+    #[must_use = "consider using the return value or calling `replace_quiet` instead"]
+    pub fn replace(&mut self, index: usize, bit: bool) -> bool {
+        let old_bit = self.contains(index);
+        self.replace_quiet(index, bit);
+        old_bit
+    }
+
+    /// Sets the bit at `index` to `0`.
+    ///
+    /// If you would like to know if the removal succeeded, use [`remove`](Self::remove)
+    /// instead.
+    pub fn remove_quiet(&mut self, index: usize) {
+        if let Some(word) = self.words.get_mut(index / 64) {
+            *word &= !(1 << (index % 64));
+        }
+    }
+
+    /// Sets the bit at `index` to `0`. Returns whether the bit was set.
+    ///
+    /// If the return value is not needed, use [`remove_quiet`](Self::remove_quiet) instead.
+    #[must_use = "consider using the return value or calling `remove_quiet` instead"]
+    pub fn remove(&mut self, index: usize) -> bool {
+        let was_set = self.contains(index);
+        self.remove_quiet(index);
+        was_set
+    }
+
+    /// Creates an iterator over the indices of the bits that are set in the set.
+    #[must_use]
+    pub fn iter_indices<D>(&self) -> BitVecIndices<'_, D>
+    where
+        for<'a> BitVecIndices<'a, D>: Iterator<Item = usize>,
+    {
+        BitVecIndices::new(self)
+    }
+}
+
+/// An iterator over the indices of the bits that are set in a [`BitVecSet`].
+///
+/// `D` can only be [`Ascending`] or [`Descending`], since a [`BitVecSet`] has room for more indices
+/// than any other [`Direction`](crate::Direction) can order.
+pub struct BitVecIndices<'a, D = Ascending> {
+    words: &'a [u64],
+    front: usize,
+    back: usize,
+    current: u64,
+    position: usize,
+    _marker: PhantomData<D>,
+}
+
+impl<'a, D> BitVecIndices<'a, D> {
+    /// Creates an iterator over the indices of the bits that are set in `set`.
+    pub fn new(set: &'a BitVecSet) -> Self {
+        let words = set.trimmed();
+        Self {
+            words,
+            front: 0,
+            back: words.len(),
+            current: 0,
+            position: 0,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl Iterator for BitVecIndices<'_, Ascending> {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.current == 0 {
+            if self.front >= self.back {
+                return None;
+            }
+            self.current = self.words[self.front];
+            self.position = self.front;
+            self.front += 1;
+        }
+        let bit = self.current.trailing_zeros() as usize;
+        self.current &= self.current - 1;
+        Some(64 * self.position + bit)
+    }
+}
+
+impl Iterator for BitVecIndices<'_, Descending> {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.current == 0 {
+            if self.back <= self.front {
+                return None;
+            }
+            self.back -= 1;
+            self.current = self.words[self.back];
+            self.position = self.back;
+        }
+        let bit = self.current.ilog2();
+        self.current ^= 1 << bit;
+        Some(64 * self.position + bit as usize)
+    }
+}
+
+impl fmt::Debug for BitVecSet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set()
+            .entries(self.iter_indices::<Ascending>())
+            .finish()
+    }
+}
+
+impl PartialEq for BitVecSet {
+    fn eq(&self, other: &Self) -> bool {
+        self.trimmed() == other.trimmed()
+    }
+}
+
+impl Eq for BitVecSet {}
+
+impl Hash for BitVecSet {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.trimmed().hash(state);
+    }
+}
+
+impl BitAnd for &BitVecSet {
+    type Output = BitVecSet;
+
+    fn bitand(self, rhs: Self) -> Self::Output {
+        self.intersection(rhs)
+    }
+}
+
+impl BitAnd for BitVecSet {
+    type Output = Self;
+
+    fn bitand(self, rhs: Self) -> Self::Output {
+        self.intersection(&rhs)
+    }
+}
+
+impl BitAndAssign<&BitVecSet> for BitVecSet {
+    fn bitand_assign(&mut self, rhs: &Self) {
+        self.words.truncate(rhs.words.len());
+        for (lhs, rhs) in self.words.iter_mut().zip(&rhs.words) {
+            *lhs &= rhs;
+        }
+    }
+}
+
+impl BitAndAssign for BitVecSet {
+    fn bitand_assign(&mut self, rhs: Self) {
+        *self &= &rhs;
+    }
+}
+
+impl BitOr for &BitVecSet {
+    type Output = BitVecSet;
+
+    fn bitor(self, rhs: Self) -> Self::Output {
+        self.union(rhs)
+    }
+}
+
+impl BitOr for BitVecSet {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self::Output {
+        self.union(&rhs)
+    }
+}
+
+impl BitOrAssign<&BitVecSet> for BitVecSet {
+    fn bitor_assign(&mut self, rhs: &Self) {
+        if self.words.len() < rhs.words.len() {
+            self.words.resize(rhs.words.len(), 0);
+        }
+        for (lhs, rhs) in self.words.iter_mut().zip(&rhs.words) {
+            *lhs |= rhs;
+        }
+    }
+}
+
+impl BitOrAssign for BitVecSet {
+    fn bitor_assign(&mut self, rhs: Self) {
+        *self |= &rhs;
+    }
+}
+
+impl BitXor for &BitVecSet {
+    type Output = BitVecSet;
+
+    fn bitxor(self, rhs: Self) -> Self::Output {
+        self.symmetric_difference(rhs)
+    }
+}
+
+impl BitXor for BitVecSet {
+    type Output = Self;
+
+    fn bitxor(self, rhs: Self) -> Self::Output {
+        self.symmetric_difference(&rhs)
+    }
+}
+
+impl BitXorAssign<&BitVecSet> for BitVecSet {
+    fn bitxor_assign(&mut self, rhs: &Self) {
+        if self.words.len() < rhs.words.len() {
+            self.words.resize(rhs.words.len(), 0);
+        }
+        for (lhs, rhs) in self.words.iter_mut().zip(&rhs.words) {
+            *lhs ^= rhs;
+        }
+    }
+}
+
+impl BitXorAssign for BitVecSet {
+    fn bitxor_assign(&mut self, rhs: Self) {
+        *self ^= &rhs;
+    }
+}
+
+impl Sub for &BitVecSet {
+    type Output = BitVecSet;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        self.difference(rhs)
+    }
+}
+
+impl Sub for BitVecSet {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        self.difference(&rhs)
+    }
+}
+
+impl SubAssign<&BitVecSet> for BitVecSet {
+    fn sub_assign(&mut self, rhs: &Self) {
+        for (lhs, rhs) in self.words.iter_mut().zip(&rhs.words) {
+            *lhs &= !rhs;
+        }
+    }
+}
+
+impl SubAssign for BitVecSet {
+    fn sub_assign(&mut self, rhs: Self) {
+        *self -= &rhs;
+    }
+}
+
+impl From<Vec<u64>> for BitVecSet {
+    fn from(words: Vec<u64>) -> Self {
+        Self::from_words(words)
+    }
+}
+
+impl From<BitVecSet> for Vec<u64> {
+    fn from(set: BitVecSet) -> Self {
+        set.into_words()
+    }
+}
+
+macro_rules! impl_fixed_conversions {
+    ($($feature:literal => $bitset:ident),* $(,)?) => {$(
+        #[cfg(feature = $feature)]
+        impl From<crate::$bitset> for BitVecSet {
+            fn from(set: crate::$bitset) -> Self {
+                let bits = set.bits() as u128;
+                let words = (0..crate::$bitset::CAPACITY.div_ceil(64))
+                    .map(|i| (bits >> (64 * i)) as u64)
+                    .collect();
+                Self::from_words(words)
+            }
+        }
+
+        #[cfg(feature = $feature)]
+        impl TryFrom<&BitVecSet> for crate::$bitset {
+            type Error = crate::CapacityError;
+
+            fn try_from(set: &BitVecSet) -> Result<Self, Self::Error> {
+                match set.max_index_checked() {
+                    Some(max) if max >= Self::CAPACITY => {
+                        Err(crate::CapacityError::new(max, Self::CAPACITY))
+                    }
+                    _ => {
+                        let bits = set
+                            .trimmed()
+                            .iter()
+                            .enumerate()
+                            .fold(0u128, |bits, (i, &word)| bits | (word as u128) << (64 * i));
+                        Ok(Self::from_bits(bits as _))
+                    }
+                }
+            }
+        }
+
+        #[cfg(feature = $feature)]
+        impl TryFrom<BitVecSet> for crate::$bitset {
+            type Error = crate::CapacityError;
+
+            fn try_from(set: BitVecSet) -> Result<Self, Self::Error> {
+                Self::try_from(&set)
+            }
+        }
+    )*};
+}
+
+impl_fixed_conversions! {
+    "b8" => BitSet8,
+    "b16" => BitSet16,
+    "b32" => BitSet32,
+    "b64" => BitSet64,
+    "b128" => BitSet128,
+    "bsize" => BitSetSize,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn set_of(indices: &[usize]) -> BitVecSet {
+        let mut set = BitVecSet::new();
+        for &index in indices {
+            set.insert_quiet(index);
+        }
+        set
+    }
+
+    #[test]
+    fn new_set_is_empty() {
+        assert!(BitVecSet::new().is_empty());
+        assert_eq!(BitVecSet::new().capacity(), 0);
+        assert!(BitVecSet::with_capacity(100).is_empty());
+        assert_eq!(BitVecSet::with_capacity(100).capacity(), 128);
+    }
+
+    #[test]
+    fn insert_grows() {
+        let mut set = BitVecSet::new();
+        assert!(set.insert(500));
+        assert!(!set.insert(500));
+        assert!(set.capacity() > 500);
+        assert!(set.contains(500));
+        assert!(!set.contains(499));
+        assert!(!set.contains(1_000_000));
+        assert_eq!(set.len(), 1);
+    }
+
+    #[test]
+    fn remove_and_replace() {
+        let mut set = set_of(&[1, 64, 200]);
+        assert!(set.remove(64));
+        assert!(!set.remove(64));
+        assert!(!set.remove(10_000));
+        assert!(set.replace(200, false));
+        assert!(!set.replace(300, true));
+        assert_eq!(set, set_of(&[1, 300]));
+    }
+
+    #[test]
+    fn equality_ignores_trailing_words() {
+        let mut set = set_of(&[3, 700]);
+        set.remove_quiet(700);
+        assert_eq!(set, set_of(&[3]));
+        set.shrink_to_fit();
+        assert_eq!(set.capacity(), 64);
+    }
+
+    #[test]
+    fn set_algebra() {
+        let a = set_of(&[1, 2, 3, 100, 300]);
+        let b = set_of(&[2, 3, 4, 300]);
+        assert_eq!(&a | &b, set_of(&[1, 2, 3, 4, 100, 300]));
+        assert_eq!(&a & &b, set_of(&[2, 3, 300]));
+        assert_eq!(&a - &b, set_of(&[1, 100]));
+        assert_eq!(&b - &a, set_of(&[4]));
+        assert_eq!(&a ^ &b, set_of(&[1, 4, 100]));
+
+        let mut c = a.clone();
+        c |= &b;
+        c -= &set_of(&[1]);
+        c &= &set_of(&[2, 4, 100, 1000]);
+        c ^= &set_of(&[5]);
+        assert_eq!(c, set_of(&[2, 4, 5, 100]));
+    }
+
+    #[test]
+    fn comparisons() {
+        let a = set_of(&[1, 2, 3, 100]);
+        let b = set_of(&[2, 100]);
+        assert!(b.is_subset(&a));
+        assert!(b.is_strict_subset(&a));
+        assert!(a.is_superset(&b));
+        assert!(!a.is_subset(&b));
+        assert!(a.is_disjoint(&set_of(&[0, 4, 1000])));
+        assert!(BitVecSet::new().is_subset(&b));
+    }
+
+    #[test]
+    fn min_and_max() {
+        let set = set_of(&[70, 130, 900]);
+        assert_eq!(set.min_index_checked(), Some(70));
+        assert_eq!(set.max_index_checked(), Some(900));
+        assert_eq!(BitVecSet::new().min_index_checked(), None);
+        assert_eq!(BitVecSet::new().max_index_checked(), None);
+    }
+
+    #[test]
+    fn iter_indices() {
+        let indices = [0, 5, 63, 64, 65, 300, 1023];
+        let set = set_of(&indices);
+        assert!(set.iter_indices::<Ascending>().eq(indices));
+        assert!(set.iter_indices::<Descending>().eq(indices.into_iter().rev()));
+        assert_eq!(format!("{set:?}"), "{0, 5, 63, 64, 65, 300, 1023}");
+    }
+
+    #[cfg(feature = "b8")]
+    #[test]
+    fn bitset8_round_trip() {
+        let set = crate::BitSet8::from_bits(0b1010_0110);
+        let vec = BitVecSet::from(set);
+        assert!(vec.iter_indices::<Ascending>().eq(set.iter_indices::<Ascending>()));
+        assert_eq!(crate::BitSet8::try_from(&vec), Ok(set));
+    }
+
+    #[cfg(feature = "b64")]
+    #[test]
+    fn bitset64_round_trip() {
+        let set = crate::BitSet64::from_bits(0x8000_0000_0000_0001);
+        assert_eq!(crate::BitSet64::try_from(BitVecSet::from(set)), Ok(set));
+    }
+
+    #[cfg(feature = "b128")]
+    #[test]
+    fn bitset128_round_trip() {
+        let set = crate::BitSet128::from_bits(0x8000_0000_0000_0001_0000_0000_0000_0003);
+        let vec = BitVecSet::from(set);
+        assert!(vec.iter_indices::<Ascending>().eq([0, 1, 64, 127]));
+        assert_eq!(crate::BitSet128::try_from(vec), Ok(set));
+    }
+
+    #[cfg(feature = "b16")]
+    #[test]
+    fn conversion_fails_when_too_large() {
+        let error = crate::BitSet16::try_from(set_of(&[3, 16, 20])).unwrap_err();
+        assert_eq!(error.index(), 20);
+        assert_eq!(error.capacity(), 16);
+        assert_eq!(crate::BitSet16::try_from(BitVecSet::new()), Ok(crate::BitSet16::new()));
+    }
+}
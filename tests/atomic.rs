@@ -0,0 +1,98 @@
+//! Hammers the atomic bitsets from several threads at once.
+#![cfg(all(feature = "b64", target_has_atomic = "64"))]
+
+use rose_bitsets::{AtomicBitSet64, BitSet64};
+use std::{
+    sync::atomic::{AtomicUsize, Ordering},
+    thread,
+};
+
+const THREADS: usize = 8;
+const ROUNDS: usize = 10_000;
+
+#[test]
+fn concurrent_inserts_are_not_lost() {
+    let set = AtomicBitSet64::new(BitSet64::new());
+    thread::scope(|scope| {
+        for thread in 0..THREADS {
+            let set = &set;
+            scope.spawn(move || {
+                for index in (thread..64).step_by(THREADS) {
+                    set.insert(index, Ordering::Relaxed);
+                }
+            });
+        }
+    });
+    assert_eq!(set.into_inner(), BitSet64::all());
+}
+
+#[test]
+fn each_bit_is_claimed_once() {
+    let set = AtomicBitSet64::new(BitSet64::new());
+    let claims = AtomicUsize::new(0);
+    thread::scope(|scope| {
+        for _ in 0..THREADS {
+            scope.spawn(|| {
+                for index in 0..64 {
+                    if !set.insert(index, Ordering::AcqRel).contains(index) {
+                        claims.fetch_add(1, Ordering::Relaxed);
+                    }
+                }
+            });
+        }
+    });
+    assert_eq!(claims.into_inner(), 64);
+}
+
+#[test]
+fn toggles_cancel_out() {
+    let set = AtomicBitSet64::new(BitSet64::from_bits(0xf0f0));
+    thread::scope(|scope| {
+        for thread in 0..THREADS {
+            let set = &set;
+            scope.spawn(move || {
+                let mask = BitSet64::from_bits(0x0101 << thread);
+                for _ in 0..2 * ROUNDS {
+                    set.symmetric_difference_with(mask, Ordering::Relaxed);
+                }
+            });
+        }
+    });
+    assert_eq!(set.into_inner(), BitSet64::from_bits(0xf0f0));
+}
+
+#[test]
+fn compare_exchange_moves_a_token() {
+    // Each thread moves the single member of the set from index `i` to `i + 1` whenever it sees
+    // it, so the token must have been moved exactly once per successful exchange.
+    let set = AtomicBitSet64::new(BitSet64::unit(0));
+    let moves = AtomicUsize::new(0);
+    thread::scope(|scope| {
+        for _ in 0..THREADS {
+            scope.spawn(|| {
+                let mut current = set.load(Ordering::Acquire);
+                for _ in 0..ROUNDS {
+                    let index = current.min_index();
+                    if index == 63 {
+                        break;
+                    }
+                    match set.compare_exchange_weak(
+                        current,
+                        BitSet64::unit(index + 1),
+                        Ordering::AcqRel,
+                        Ordering::Acquire,
+                    ) {
+                        Ok(_) => {
+                            moves.fetch_add(1, Ordering::Relaxed);
+                            current = BitSet64::unit(index + 1);
+                        }
+                        Err(actual) => current = actual,
+                    }
+                }
+            });
+        }
+    });
+    let set = set.into_inner();
+    assert_eq!(set.len(), 1);
+    assert_eq!(set.min_index(), moves.into_inner());
+}
@@ -0,0 +1,144 @@
+//! Checks that the `BitSet` derive works for types defined outside of `rose_bitsets`.
+#![cfg(feature = "rose-bitset-derive")]
+
+use rose_bitsets::{Ascending, BitSet, Descending};
+
+/// Unix-style permissions for the owner, group, and everyone else.
+#[derive(BitSet, Clone, Copy, Default, Eq, Hash, PartialEq)]
+#[bitset(atomic, debug, indices, iter, tests)]
+pub struct Permissions(u16);
+
+#[derive(BitSet, Clone, Copy, Default, Eq, Hash, PartialEq)]
+#[bitset(
+    crate = ::rose_bitsets,
+    indices = Slots,
+    iter = SlotBits,
+    atomic = SharedSlots,
+    subsets = SlotSubsets,
+    combinations = SlotChoices,
+    permutation = SlotShuffle,
+)]
+pub struct SlotMask(u64);
+
+/// The days of the week, starting from Monday.
+#[derive(BitSet, Clone, Copy, Default, Eq, Hash, PartialEq)]
+#[bitset(capacity = 7, combinations, debug, indices, iter, permutation, runs, subsets, tests)]
+pub struct Weekdays(u8);
+
+fn count<S: BitSet>(set: S) -> usize {
+    set.iter_indices().count()
+}
+
+#[test]
+fn default_names() {
+    let set = Permissions::from_bits(0b1_0010_0100);
+    let indices: PermissionsIndices<'_, Ascending> = set.iter_indices();
+    assert!(indices.eq([2, 5, 8]));
+    let bits: PermissionsIter<'_, Descending> = set.iter_bits();
+    assert_eq!(bits.filter(|&bit| bit).count(), 3);
+    assert_eq!(format!("{set:?}"), "{2, 5, 8}");
+}
+
+#[test]
+fn custom_names() {
+    let set = SlotMask::from_bits(0x8000_0000_0000_0001);
+    let indices: Slots<'_, Descending> = set.iter_indices();
+    assert!(indices.eq([63, 0]));
+    let bits: SlotBits<'_, Ascending> = set.iter_bits();
+    assert_eq!(bits.count(), 64);
+    let mut mapping = SlotShuffle::identity().mapping();
+    mapping.swap(0, 1);
+    let swap_first_two = SlotShuffle::new(mapping).unwrap();
+    assert!(set.permuted(&swap_first_two) == SlotMask::from_bits(0x8000_0000_0000_0002));
+}
+
+#[test]
+fn implements_trait() {
+    assert_eq!(<Permissions as BitSet>::CAPACITY, 16);
+    assert_eq!(count(Permissions::all()), 16);
+    assert_eq!(count(SlotMask::unit(40)), 1);
+}
+
+#[test]
+fn custom_capacity() {
+    assert_eq!(Weekdays::CAPACITY, 7);
+    assert_eq!(Weekdays::all().bits(), 0b0111_1111);
+    assert_eq!(Weekdays::from_bits(0xff), Weekdays::all());
+    assert!(Weekdays::unit(7).is_empty());
+    assert!(!Weekdays::all().contains(7));
+    assert_eq!(Weekdays::unit(2).complement().len(), 6);
+    assert_eq!(Weekdays::unit(6).shifted_up_by(1), Weekdays::new());
+    assert!(Weekdays::all().iter_indices::<Descending>().eq((0..7).rev()));
+    assert_eq!(Weekdays::unit(6).iter_bits::<Descending>().next(), Some(true));
+    assert_eq!(Weekdays::all().iter_bits::<Ascending>().count(), 7);
+    assert_eq!(format!("{:?}", Weekdays::all()), "{0, 1, 2, 3, 4, 5, 6}");
+}
+
+#[test]
+fn boundary_names_follow_the_width() {
+    // These names are part of the public API, so they must not drift from the width in bits.
+    let mut permissions = Permissions::all();
+    permissions.clear_i_to_16(4);
+    assert_eq!(permissions.bits(), 0b1111);
+    assert_eq!(Permissions::all().masked_i_to_16(12).bits(), 0xf000);
+    assert_eq!(SlotMask::all().cleared_i_to_64(1).bits(), 1);
+    let mut weekdays = Weekdays::all();
+    weekdays.mask_i_to_7(5);
+    assert_eq!(weekdays.bits(), 0b0110_0000);
+}
+
+#[cfg(feature = "b8")]
+#[test]
+fn builtin_boundary_names() {
+    let mut set = rose_bitsets::BitSet8::all();
+    set.clear_i_to_8(3);
+    assert_eq!(set.bits(), 0b0111);
+    assert_eq!(rose_bitsets::BitSet8::all().masked_i_to_8(6).bits(), 0b1100_0000);
+}
+
+#[cfg(all(feature = "bsize", target_pointer_width = "64"))]
+#[test]
+fn pointer_width_boundary_names() {
+    let set = rose_bitsets::BitSetSize::all().cleared_i_to_64(2);
+    assert_eq!(set.bits(), 0b11);
+}
+
+#[test]
+fn atomic_names() {
+    use std::sync::atomic::Ordering;
+
+    let permissions = AtomicPermissions::new(Permissions::unit(3));
+    assert_eq!(format!("{permissions:?}"), "{3}");
+    let slots = SharedSlots::default();
+    assert!(slots.insert(40, Ordering::Relaxed).is_empty());
+    assert!(slots.into_inner() == SlotMask::unit(40));
+}
+
+#[test]
+fn subset_names() {
+    let subsets: SlotSubsets = SlotMask::from_bits(0b1011).subsets();
+    assert_eq!(subsets.count(), 8);
+    let pairs: SlotChoices = SlotMask::from_bits(0b1011).combinations(2);
+    assert!(pairs.map(SlotMask::bits).eq([0b0011, 0b1001, 0b1010]));
+    let weekends: WeekdaysCombinations = Weekdays::all().combinations(2);
+    assert_eq!(weekends.len(), 21);
+    assert_eq!(Weekdays::power_set().len(), 128);
+}
+
+#[cfg(all(feature = "b64", feature = "b128"))]
+#[test]
+fn large_combination_counts() {
+    use rose_bitsets::{BitSet64, BitSet128};
+
+    let halves = BitSet64::all().combinations(32);
+    assert_eq!(halves.size_hint(), (1_832_624_140_942_590_534, Some(1_832_624_140_942_590_534)));
+    assert_eq!(BitSet128::all().combinations(64).size_hint(), (usize::MAX, None));
+    assert_eq!(BitSet128::power_set().size_hint(), (usize::MAX, None));
+    let mut halves = BitSet128::all().combinations(64);
+    assert_eq!(halves.next(), Some(BitSet128::from_bits(u64::MAX as u128)));
+    assert_eq!(halves.next_back(), Some(BitSet128::from_bits((u64::MAX as u128) << 64)));
+
+    let last = BitSet128::from_range(64..);
+    assert_eq!(last.rank_combination(), 23_951_146_041_928_082_866_135_587_776_380_551_749);
+    assert_eq!(BitSet128::unrank_combination(64, last.rank_combination()), Some(last));
+}